@@ -21,6 +21,7 @@ use databend_meta_runtime_api::ChannelError;
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_runtime_api::TlsConfig;
 use databend_meta_types::ConnectionError;
+use databend_meta_types::GrpcCompression;
 use databend_meta_types::MetaClientError;
 use databend_meta_types::MetaNetworkError;
 use databend_meta_types::protobuf::meta_service_client::MetaServiceClient;
@@ -69,6 +70,8 @@ pub struct MetaChannelManager<R> {
 
     grpc_max_message_size: usize,
 
+    grpc_compression: GrpcCompression,
+
     _phantom: PhantomData<R>,
 }
 
@@ -81,6 +84,7 @@ impl<R: SpawnApi> MetaChannelManager<R> {
         endpoints: Arc<Mutex<Endpoints>>,
         connection_ttl: Option<Duration>,
         grpc_max_message_size: usize,
+        grpc_compression: GrpcCompression,
     ) -> Self {
         Self {
             username: username.to_string(),
@@ -90,6 +94,7 @@ impl<R: SpawnApi> MetaChannelManager<R> {
             endpoints,
             connection_ttl: connection_ttl.unwrap_or(DEFAULT_CONNECTION_TTL),
             grpc_max_message_size,
+            grpc_compression,
             _phantom: PhantomData,
         }
     }
@@ -123,7 +128,7 @@ impl<R: SpawnApi> MetaChannelManager<R> {
             handshake_res.as_ref().err()
         );
 
-        let (token, server_version) = handshake_res?;
+        let (token, server_version, server_features) = handshake_res?;
 
         // Update the token for the client interceptor.
         // Safe unwrap(): it is the first time setting it.
@@ -132,6 +137,7 @@ impl<R: SpawnApi> MetaChannelManager<R> {
         Ok(EstablishedClient::new(
             real_client,
             server_version,
+            server_features,
             addr,
             self.endpoints.clone(),
         ))
@@ -139,20 +145,22 @@ impl<R: SpawnApi> MetaChannelManager<R> {
 
     /// Create a MetaServiceClient with authentication interceptor, using instance config.
     fn new_real_client(&self, chan: Channel) -> (RealClient, Arc<OnceCell<Vec<u8>>>) {
-        Self::new_real_client_with_size(chan, self.grpc_max_message_size)
+        Self::new_real_client_with_size(chan, self.grpc_max_message_size, self.grpc_compression)
     }
 
     /// Create a MetaServiceClient with default message size, for testing.
     pub fn new_real_client_for_testing(chan: Channel) -> (RealClient, Arc<OnceCell<Vec<u8>>>) {
-        Self::new_real_client_with_size(chan, DEFAULT_GRPC_MESSAGE_SIZE)
+        Self::new_real_client_with_size(chan, DEFAULT_GRPC_MESSAGE_SIZE, GrpcCompression::None)
     }
 
-    /// Create a MetaServiceClient with the specified max message size.
+    /// Create a MetaServiceClient with the specified max message size and request compression.
     ///
+    /// Compressed responses are always accepted.
     /// The returned `OnceCell` is used to fill in a token for the interceptor.
     fn new_real_client_with_size(
         chan: Channel,
         max_message_size: usize,
+        compression: GrpcCompression,
     ) -> (RealClient, Arc<OnceCell<Vec<u8>>>) {
         let once = Arc::new(OnceCell::new());
 
@@ -160,10 +168,18 @@ impl<R: SpawnApi> MetaChannelManager<R> {
             token: once.clone(),
         };
 
-        let client = MetaServiceClient::with_interceptor(chan, interceptor)
+        let mut client = MetaServiceClient::with_interceptor(chan, interceptor)
             .max_decoding_message_size(max_message_size)
             .max_encoding_message_size(max_message_size);
 
+        for encoding in GrpcCompression::ACCEPTED {
+            client = client.accept_compressed(encoding);
+        }
+
+        if let Some(encoding) = compression.encoding() {
+            client = client.send_compressed(encoding);
+        }
+
         (client, once)
     }
 
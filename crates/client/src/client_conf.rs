@@ -14,6 +14,8 @@
 
 use std::time::Duration;
 
+use databend_meta_types::GrpcCompression;
+
 use crate::channel_manager::DEFAULT_GRPC_MESSAGE_SIZE;
 
 #[derive(Clone, Debug, Default)]
@@ -45,6 +47,14 @@ pub struct RpcClientConf {
 
     /// Maximum gRPC message size in bytes for client connections.
     pub grpc_max_message_size: usize,
+
+    /// Compression of the requests sent to the meta-service.
+    ///
+    /// Requests are compressed without negotiation and a server older than `260205.4.0`
+    /// rejects every compressed request:
+    /// enable it only after all meta-service nodes are upgraded.
+    /// Compressed responses are always accepted.
+    pub grpc_compression: GrpcCompression,
 }
 
 impl RpcClientConf {
@@ -74,6 +84,7 @@ impl RpcClientConf {
             auto_sync_interval: None,
             unhealthy_endpoint_evict_time: Default::default(),
             grpc_max_message_size: DEFAULT_GRPC_MESSAGE_SIZE,
+            grpc_compression: GrpcCompression::None,
         }
     }
 }
@@ -49,6 +49,7 @@ use tonic::codegen::BoxStream;
 use crate::ClientWorkerRequest;
 use crate::InitFlag;
 use crate::RequestFor;
+use crate::RequestOptions;
 use crate::Streamed;
 use crate::established_client::EstablishedClient;
 use crate::grpc_action::GetKVReply;
@@ -161,6 +162,17 @@ impl<RT: SpawnApi> ClientHandle<RT> {
         self.request(txn).await.map_err(MetaError::from)
     }
 
+    /// Run a transaction with the retry policy and timeout in `options`.
+    pub async fn transaction_with_options(
+        &self,
+        txn: TxnRequest,
+        options: RequestOptions,
+    ) -> Result<TxnReply, MetaError> {
+        self.request_with_options(txn, options)
+            .await
+            .map_err(MetaError::from)
+    }
+
     pub async fn get_kv(&self, key: &str) -> Result<GetKVReply, MetaError> {
         let mut res = self.mget_kv(&[key.to_string()]).await?;
         Ok(res.pop().flatten())
@@ -187,6 +199,27 @@ impl<RT: SpawnApi> ClientHandle<RT> {
     #[fastrace::trace]
     #[async_backtrace::framed]
     pub async fn request<Req, E>(&self, req: Req) -> Result<Req::Reply, E>
+    where
+        Req: RequestFor,
+        Req: Into<message::Request>,
+        Result<Req::Reply, E>: TryFrom<Response>,
+        <Result<Req::Reply, E> as TryFrom<Response>>::Error: std::fmt::Display,
+        E: From<MetaClientError> + Debug,
+    {
+        self.request_with_options(req, RequestOptions::default())
+            .await
+    }
+
+    /// Send a request to the internal worker task, overriding the retry policy and timeout.
+    ///
+    /// See [`RequestOptions`] for the requests these options apply to.
+    #[fastrace::trace]
+    #[async_backtrace::framed]
+    pub async fn request_with_options<Req, E>(
+        &self,
+        req: Req,
+        options: RequestOptions,
+    ) -> Result<Req::Reply, E>
     where
         Req: RequestFor,
         Req: Into<message::Request>,
@@ -195,7 +228,7 @@ impl<RT: SpawnApi> ClientHandle<RT> {
         E: From<MetaClientError> + Debug,
     {
         let rx = self
-            .send_request_to_worker(req)
+            .send_request_to_worker(req, &options)
             .map_err(MetaClientError::from)?;
 
         let recv_res = RT::unlimited_future(async move {
@@ -220,7 +253,7 @@ impl<RT: SpawnApi> ClientHandle<RT> {
         let _g = request_inflight::<RT>().counted_guard();
 
         let rx = self
-            .send_request_to_worker(req)
+            .send_request_to_worker(req, &RequestOptions::default())
             .map_err(MetaClientError::from)?;
 
         let recv_res = rx.blocking_recv();
@@ -231,6 +264,7 @@ impl<RT: SpawnApi> ClientHandle<RT> {
     fn send_request_to_worker<Req>(
         &self,
         req: Req,
+        options: &RequestOptions,
     ) -> Result<oneshot::Receiver<Response>, ConnectionError>
     where
         Req: Into<message::Request>,
@@ -242,6 +276,7 @@ impl<RT: SpawnApi> ClientHandle<RT> {
             request_id: META_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
             resp_tx: tx,
             req: req.into(),
+            options: options.to_rpc_options(),
             span: Span::enter_with_local_parent(std::any::type_name::<ClientWorkerRequest>()),
             tracking_fn: Some(RT::capture_tracking_context()),
         };
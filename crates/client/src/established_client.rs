@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
@@ -41,6 +42,7 @@ use databend_meta_types::protobuf::StreamItem;
 use databend_meta_types::protobuf::WatchRequest;
 use databend_meta_types::protobuf::WatchResponse;
 use databend_meta_types::protobuf::meta_service_client::MetaServiceClient;
+use databend_meta_version::Feature;
 use display_more::DisplayOptionExt;
 use log::debug;
 use log::error;
@@ -98,6 +100,9 @@ pub struct EstablishedClient {
 
     server_protocol_version: u64,
 
+    /// The features the server provides, received in handshake.
+    server_features: BTreeSet<Feature>,
+
     /// The target endpoint this client connected to.
     ///
     /// Note that `target_endpoint` may be different from the `self.endpoints.current()`,
@@ -145,6 +150,7 @@ impl EstablishedClient {
     pub(crate) fn new(
         client: MetaServiceClient<InterceptedService<Channel, AuthInterceptor>>,
         server_protocol_version: u64,
+        server_features: BTreeSet<Feature>,
         target_endpoint: impl ToString,
         endpoints: Arc<Mutex<Endpoints>>,
     ) -> Self {
@@ -162,6 +168,7 @@ impl EstablishedClient {
         let client = Self {
             client,
             server_protocol_version,
+            server_features,
             target_endpoint: target_endpoint.to_string(),
             endpoints,
             endpoints_string,
@@ -187,6 +194,15 @@ impl EstablishedClient {
         self.server_protocol_version
     }
 
+    pub fn server_features(&self) -> &BTreeSet<Feature> {
+        &self.server_features
+    }
+
+    /// Returns whether the server provides `feature`.
+    pub fn has_feature(&self, feature: Feature) -> bool {
+        self.server_features.contains(&feature)
+    }
+
     pub(crate) fn set_error(&self, error: Status) {
         *self.error.lock() = Some(error);
     }
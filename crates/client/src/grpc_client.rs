@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -26,6 +27,7 @@ use databend_meta_runtime_api::ClientMetricsApi;
 use databend_meta_runtime_api::RuntimeApi;
 use databend_meta_runtime_api::TlsConfig;
 use databend_meta_types::ConnectionError;
+use databend_meta_types::GrpcCompression;
 use databend_meta_types::MetaClientError;
 use databend_meta_types::MetaError;
 use databend_meta_types::MetaHandshakeError;
@@ -39,12 +41,14 @@ use databend_meta_types::protobuf::ClusterStatus;
 use databend_meta_types::protobuf::Empty;
 use databend_meta_types::protobuf::ExportedChunk;
 use databend_meta_types::protobuf::HandshakeRequest;
+use databend_meta_types::protobuf::KvListRequest;
 use databend_meta_types::protobuf::MemberListReply;
 use databend_meta_types::protobuf::MemberListRequest;
 use databend_meta_types::protobuf::RaftRequest;
 use databend_meta_types::protobuf::WatchRequest;
 use databend_meta_types::protobuf::WatchResponse;
 use databend_meta_types::protobuf::meta_service_client::MetaServiceClient;
+use databend_meta_version::Feature;
 use databend_meta_version::Version;
 use fastrace::Span;
 use fastrace::func_name;
@@ -84,13 +88,15 @@ use crate::endpoints::Endpoints;
 use crate::endpoints::rotate_failing_endpoint;
 use crate::errors::CreationError;
 use crate::established_client::EstablishedClient;
+use crate::grpc_action::ListKVReq;
 use crate::message;
 use crate::message::Response;
 use crate::pool::Pool;
+use crate::retry_policy::RpcOptions;
 use crate::rpc_handler::ResponseAction;
 use crate::rpc_handler::RpcHandler;
+use crate::rpc_handler::with_timeout;
 
-const RPC_RETRIES: usize = 4;
 const AUTH_TOKEN_KEY: &str = "auth-token-bin";
 
 pub(crate) type RealClient = MetaServiceClient<InterceptedService<Channel, AuthInterceptor>>;
@@ -145,7 +151,7 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
     /// The worker is a singleton and the returned handle is cheap to clone.
     /// When all handles are dropped the worker will quit, then the runtime will be destroyed.
     pub fn try_new(conf: &RpcClientConf) -> Result<Arc<ClientHandle<RT>>, CreationError> {
-        Self::try_create(
+        Self::try_create_with_features(
             conf.get_endpoints(),
             &conf.username,
             &conf.password,
@@ -153,6 +159,7 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
             conf.auto_sync_interval,
             conf.tls_conf.clone(),
             conf.grpc_max_message_size,
+            conf.grpc_compression,
         )
     }
 
@@ -175,6 +182,7 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
             auto_sync_interval,
             tls_config,
             grpc_max_message_size,
+            GrpcCompression::None,
         )
     }
 
@@ -200,6 +208,7 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
         auto_sync_interval: Option<Duration>,
         tls_config: Option<RpcClientTlsConfig>,
         grpc_max_message_size: usize,
+        grpc_compression: GrpcCompression,
     ) -> Result<Arc<ClientHandle<RT>>, CreationError> {
         Self::endpoints_non_empty(&endpoints_str)?;
 
@@ -218,6 +227,7 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
             endpoints.clone(),
             None, // Use default connection TTL
             grpc_max_message_size,
+            grpc_compression,
         );
 
         let rt = RT::new(
@@ -321,6 +331,7 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
         let request_id = worker_request.request_id;
         let resp_tx = worker_request.resp_tx;
         let req = worker_request.req;
+        let options = worker_request.options;
         let req_name = req.name();
         let req_str = format!("{:?}", req);
 
@@ -328,18 +339,16 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
         let resp = match req {
             message::Request::StreamMGet(r) => {
                 let strm = self
-                    .kv_read_v1(MetaGrpcReadReq::MGetKV(r.into_inner()))
+                    .kv_read_v1(MetaGrpcReadReq::MGetKV(r.into_inner()), &options)
                     .await;
                 Response::StreamMGet(strm)
             }
             message::Request::StreamList(r) => {
-                let strm = self
-                    .kv_read_v1(MetaGrpcReadReq::ListKV(r.into_inner()))
-                    .await;
+                let strm = self.list(r.into_inner(), &options).await;
                 Response::StreamMGet(strm)
             }
             message::Request::Txn(r) => {
-                let resp = self.transaction(r).await;
+                let resp = self.transaction(r, &options).await;
                 Response::Txn(resp)
             }
             message::Request::Watch(r) => {
@@ -689,23 +698,28 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
     pub(crate) async fn kv_read_v1(
         &self,
         grpc_req: MetaGrpcReadReq,
+        options: &RpcOptions,
     ) -> Result<BoxStream<pb::StreamItem>, MetaError> {
         debug!("{}::kv_read_v1 request: {:?}", self, grpc_req);
 
         let raft_req: RaftRequest = grpc_req.clone().into();
-        let mut rpc_handler = RpcHandler::new(self);
+        let mut rpc_handler = RpcHandler::new(self, "kv_read_v1", options, false);
 
-        for _i in 0..RPC_RETRIES {
+        while rpc_handler.next_attempt().await {
             let req = RT::prepare_request(Request::new(raft_req.clone()));
+            let timeout = rpc_handler.remaining();
 
             let established = rpc_handler.new_established_client().await?;
 
             debug!("{}::kv_read_v1 established client: {:?}", self, established);
 
-            let result = established
-                .kv_read_v1(req)
-                .inspect_elapsed_over(threshold(), info_spent("kv_read_v1"))
-                .await;
+            let result = with_timeout(
+                timeout,
+                established
+                    .kv_read_v1(req)
+                    .inspect_elapsed_over(threshold(), info_spent("kv_read_v1")),
+            )
+            .await;
 
             debug!("{self}::kv_read_v1 result: {:?}", result);
 
@@ -726,22 +740,90 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
         Err(net_err.into())
     }
 
+    /// List key-values by prefix.
+    ///
+    /// It calls `kv_list()` if the server advertises [`Feature::KvList`],
+    /// otherwise it falls back to `kv_read_v1()`.
+    #[fastrace::trace]
+    #[async_backtrace::framed]
+    pub(crate) async fn list(
+        &self,
+        list_req: ListKVReq,
+        options: &RpcOptions,
+    ) -> Result<BoxStream<pb::StreamItem>, MetaError> {
+        debug!("{}::list request: {:?}", self, list_req);
+
+        let mut rpc_handler = RpcHandler::new(self, "list", options, false);
+
+        while rpc_handler.next_attempt().await {
+            let timeout = rpc_handler.remaining();
+
+            let mut established = rpc_handler.new_established_client().await?;
+
+            let result = if established.has_feature(Feature::KvList) {
+                let req = RT::prepare_request(Request::new(KvListRequest {
+                    prefix: list_req.prefix.clone(),
+                    limit: None,
+                }));
+
+                let fu = established
+                    .kv_list(req)
+                    .inspect_elapsed_over(threshold(), info_spent("kv_list"));
+                with_timeout(timeout, fu).await
+            } else {
+                let raft_req: RaftRequest = MetaGrpcReadReq::ListKV(list_req.clone()).into();
+                let req = RT::prepare_request(Request::new(raft_req));
+
+                let fu = established
+                    .kv_read_v1(req)
+                    .inspect_elapsed_over(threshold(), info_spent("kv_read_v1"));
+                with_timeout(timeout, fu).await
+            };
+
+            debug!("{self}::list result: {:?}", result);
+
+            let retryable = rpc_handler.process_response_result(&list_req, result)?;
+
+            let response = match retryable {
+                ResponseAction::Success(resp) => resp,
+                ResponseAction::ShouldRetry => {
+                    continue;
+                }
+            };
+
+            let strm = response.into_inner();
+            return Ok(strm.boxed());
+        }
+
+        let net_err = rpc_handler.create_network_error();
+        Err(net_err.into())
+    }
+
     #[fastrace::trace]
     #[async_backtrace::framed]
-    pub(crate) async fn transaction(&self, txn: TxnRequest) -> Result<TxnReply, MetaClientError> {
+    pub(crate) async fn transaction(
+        &self,
+        txn: TxnRequest,
+        options: &RpcOptions,
+    ) -> Result<TxnReply, MetaClientError> {
         debug!("{self}::transaction request: {txn}");
 
-        let mut rpc_handler = RpcHandler::new(self);
+        let is_write = !txn.is_read_only();
+        let mut rpc_handler = RpcHandler::new(self, "transaction", options, is_write);
 
-        for _i in 0..RPC_RETRIES {
+        while rpc_handler.next_attempt().await {
             let req = RT::prepare_request(Request::new(txn.clone()));
+            let timeout = rpc_handler.remaining();
 
             let established = rpc_handler.new_established_client().await?;
 
-            let result = established
-                .transaction(req)
-                .inspect_elapsed_over(threshold(), info_spent("transaction"))
-                .await;
+            let result = with_timeout(
+                timeout,
+                established
+                    .transaction(req)
+                    .inspect_elapsed_over(threshold(), info_spent("transaction")),
+            )
+            .await;
 
             let retryable = rpc_handler.process_response_result(&txn, result)?;
 
@@ -785,6 +867,15 @@ impl<RT: RuntimeApi> MetaGrpcClient<RT> {
 ///
 /// Handshake succeeds if both of these two assertions hold.
 ///
+/// ## Features
+///
+/// `S` also replies with the names of the features it provides,
+/// so that `C` can decide which RPC to use without comparing versions.
+/// An older `S` replies with no feature,
+/// in which case the features are derived from `S.ver` with [`databend_meta_version::Spec`].
+///
+/// It returns the auth token, `S.ver` and the features `S` provides.
+///
 /// E.g.:
 /// - `S: (ver=3, min_cli_ver=1)` is compatible with `C: (ver=3, min_srv_ver=2)`.
 /// - `S: (ver=4, min_cli_ver=4)` is **NOT** compatible with `C: (ver=3, min_srv_ver=2)`.
@@ -810,7 +901,7 @@ pub async fn handshake(
     required_server_version: &Version,
     username: &str,
     password: &str,
-) -> Result<(Vec<u8>, u64), MetaHandshakeError> {
+) -> Result<(Vec<u8>, u64, BTreeSet<Feature>), MetaHandshakeError> {
     debug!("client version: {client_version}");
 
     let auth = BasicAuth {
@@ -861,10 +952,22 @@ pub async fn handshake(
         )));
     }
 
+    let features = if resp.features.is_empty() {
+        databend_meta_version::spec().server_features_at(server_version)
+    } else {
+        // Ignore the features unknown to this client, which are added by a newer server.
+        resp.features
+            .iter()
+            .filter_map(|name| Feature::from_name(name))
+            .collect()
+    };
+
+    debug!("server version: {server_version}, features: {:?}", features);
+
     let token = resp.payload;
     let server_version = resp.protocol_version;
 
-    Ok((token, server_version))
+    Ok((token, server_version, features))
 }
 
 fn is_status_retryable(status: &Status) -> bool {
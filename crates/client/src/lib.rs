@@ -26,8 +26,12 @@ pub(crate) mod established_client;
 mod grpc_action;
 mod grpc_client;
 mod message;
+mod optimistic_txn;
 mod pool;
+mod prefix_cache;
 pub mod required;
+mod resilient_watch;
+mod retry_policy;
 pub(crate) mod rpc_handler;
 
 pub use channel_manager::DEFAULT_GRPC_MESSAGE_SIZE;
@@ -51,5 +55,15 @@ pub use grpc_client::handshake;
 pub use message::ClientWorkerRequest;
 pub use message::InitFlag;
 pub use message::Streamed;
+pub use optimistic_txn::Committed;
+pub use optimistic_txn::OptimisticTxn;
+pub use optimistic_txn::OptimisticTxnError;
+pub use optimistic_txn::OptimisticTxnOptions;
+pub use prefix_cache::PrefixCache;
+pub use prefix_cache::PrefixCacheClosed;
+pub use prefix_cache::WaitForSeqError;
 pub use required::FeatureSpec;
 pub use required::VersionTuple;
+pub use resilient_watch::WatchEvent;
+pub use retry_policy::RequestOptions;
+pub use retry_policy::RetryPolicy;
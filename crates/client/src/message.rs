@@ -33,6 +33,7 @@ use tonic::codegen::BoxStream;
 use crate::established_client::EstablishedClient;
 use crate::grpc_action::ListKVReq;
 use crate::grpc_action::MGetKVReq;
+use crate::retry_policy::RpcOptions;
 
 /// A request that is sent by a meta-client handle to its worker.
 pub struct ClientWorkerRequest {
@@ -44,6 +45,9 @@ pub struct ClientWorkerRequest {
     /// Request body
     pub(crate) req: Request,
 
+    /// Retry policy and deadline of this request.
+    pub(crate) options: RpcOptions,
+
     /// Tracing span for this request
     pub(crate) span: Span,
 
@@ -55,6 +59,7 @@ impl fmt::Debug for ClientWorkerRequest {
         f.debug_struct("ClientWorkerRequest")
             .field("request_id", &self.request_id)
             .field("req", &self.req)
+            .field("options", &self.options)
             .finish()
     }
 }
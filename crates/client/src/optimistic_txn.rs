@@ -0,0 +1,280 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Optimistic transaction: read keys, compute, then commit the writes only if
+//! none of the read keys has changed since they were read.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use databend_meta_runtime_api::SpawnApi;
+use databend_meta_types::MetaError;
+use databend_meta_types::SeqV;
+use databend_meta_types::TxnCondition;
+use databend_meta_types::TxnOp;
+use databend_meta_types::TxnRequest;
+use log::info;
+use parking_lot::Mutex;
+use tokio::time::sleep;
+
+use crate::ClientHandle;
+use crate::RetryPolicy;
+
+/// Options of [`ClientHandle::optimistic_txn_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimisticTxnOptions {
+    /// The max number of attempts and the backoff between them when an attempt conflicts.
+    ///
+    /// `retry_uncertain_writes` does not apply: a conflicting attempt is never applied.
+    pub retry_policy: RetryPolicy,
+}
+
+impl Default for OptimisticTxnOptions {
+    fn default() -> Self {
+        Self {
+            retry_policy: RetryPolicy::default()
+                .with_max_attempts(11)
+                .with_backoff(Duration::from_millis(10), Duration::from_secs(1)),
+        }
+    }
+}
+
+impl OptimisticTxnOptions {
+    /// Set the max number of retries after the first attempt conflicts.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.retry_policy.max_attempts = max_retries.saturating_add(1);
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.retry_policy = self.retry_policy.with_backoff(initial, max);
+        self
+    }
+
+    /// Max number of retries after the first attempt conflicts.
+    pub fn max_retries(&self) -> u32 {
+        self.retry_policy.max_attempts.saturating_sub(1)
+    }
+}
+
+/// The value returned by the closure of a committed optimistic transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Committed<T> {
+    pub value: T,
+
+    /// The number of retries because of conflicts, `0` if the first attempt is committed.
+    pub retries: u32,
+}
+
+/// Error returned by [`ClientHandle::optimistic_txn`].
+#[derive(Debug, thiserror::Error)]
+pub enum OptimisticTxnError<E> {
+    /// The closure returned an error, nothing is committed.
+    #[error("optimistic txn aborted: {0}")]
+    Aborted(E),
+
+    /// Every attempt conflicts with other writers.
+    #[error("optimistic txn conflicts after {retries} retries, on keys: {keys:?}")]
+    Conflict { retries: u32, keys: Vec<String> },
+
+    #[error(transparent)]
+    MetaError(#[from] MetaError),
+}
+
+#[derive(Debug, Default)]
+struct TxnState {
+    /// The keys read and the `seq` when they were read, `0` for absent keys.
+    reads: BTreeMap<String, u64>,
+
+    /// The write operations buffered until commit.
+    writes: Vec<TxnOp>,
+}
+
+impl TxnState {
+    fn to_txn_request(&self) -> TxnRequest {
+        let conditions = self
+            .reads
+            .iter()
+            .map(|(key, seq)| TxnCondition::eq_seq(key, *seq))
+            .collect();
+
+        TxnRequest::new(conditions, self.writes.clone())
+    }
+}
+
+/// An attempt of an optimistic transaction, passed to the closure of
+/// [`ClientHandle::optimistic_txn`].
+///
+/// Every key read through it is recorded with its `seq`, and writes are buffered.
+/// On commit, the writes are applied only if no read key has changed.
+/// Reads do not see the writes buffered in the same attempt.
+pub struct OptimisticTxn<'a, RT: SpawnApi> {
+    client: &'a ClientHandle<RT>,
+    state: Arc<Mutex<TxnState>>,
+}
+
+impl<RT: SpawnApi> Clone for OptimisticTxn<'_, RT> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<RT: SpawnApi> fmt::Debug for OptimisticTxn<'_, RT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OptimisticTxn")
+            .field("state", &*self.state.lock())
+            .finish()
+    }
+}
+
+impl<'a, RT: SpawnApi> OptimisticTxn<'a, RT> {
+    fn new(client: &'a ClientHandle<RT>) -> Self {
+        Self {
+            client,
+            state: Default::default(),
+        }
+    }
+
+    /// Read a key and add it to the read set.
+    ///
+    /// If a key is read more than once, the `seq` of the first read is checked on commit.
+    pub async fn get(&self, key: &str) -> Result<Option<SeqV>, MetaError> {
+        let value = self.client.get_kv(key).await?;
+        self.record_read(key, value.as_ref());
+        Ok(value)
+    }
+
+    /// Read several keys and add them to the read set.
+    pub async fn mget(&self, keys: &[String]) -> Result<Vec<Option<SeqV>>, MetaError> {
+        let values = self.client.mget_kv(keys).await?;
+        for (key, value) in keys.iter().zip(values.iter()) {
+            self.record_read(key, value.as_ref());
+        }
+        Ok(values)
+    }
+
+    pub fn put(&self, key: impl ToString, value: Vec<u8>) {
+        self.write(TxnOp::put(key, value));
+    }
+
+    pub fn put_with_ttl(&self, key: impl ToString, value: Vec<u8>, ttl: Option<Duration>) {
+        self.write(TxnOp::put_with_ttl(key, value, ttl));
+    }
+
+    pub fn delete(&self, key: impl ToString) {
+        self.write(TxnOp::delete(key));
+    }
+
+    /// Buffer an arbitrary write operation.
+    pub fn write(&self, op: TxnOp) {
+        self.state.lock().writes.push(op);
+    }
+
+    fn record_read(&self, key: &str, value: Option<&SeqV>) {
+        let seq = value.map(|v| v.seq).unwrap_or(0);
+        self.state
+            .lock()
+            .reads
+            .entry(key.to_string())
+            .or_insert(seq);
+    }
+}
+
+impl<RT: SpawnApi> ClientHandle<RT> {
+    /// Run `f` as an optimistic transaction with the default [`OptimisticTxnOptions`].
+    ///
+    /// See [`Self::optimistic_txn_with`].
+    pub async fn optimistic_txn<'a, T, E, F, Fut>(
+        &'a self,
+        f: F,
+    ) -> Result<Committed<T>, OptimisticTxnError<E>>
+    where
+        F: FnMut(OptimisticTxn<'a, RT>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.optimistic_txn_with(OptimisticTxnOptions::default(), f)
+            .await
+    }
+
+    /// Run `f` as an optimistic transaction.
+    ///
+    /// `f` reads keys and buffers writes with the given [`OptimisticTxn`].
+    /// When it returns `Ok`, the writes are committed on condition that every read key
+    /// still has the `seq` it was read with.
+    /// If another writer changed any of them, `f` is called again with a new [`OptimisticTxn`],
+    /// after a backoff, up to [`OptimisticTxnOptions::max_retries`] times.
+    ///
+    /// The read set is checked even if there is no write,
+    /// so that the returned value is computed from a consistent view.
+    pub async fn optimistic_txn_with<'a, T, E, F, Fut>(
+        &'a self,
+        options: OptimisticTxnOptions,
+        mut f: F,
+    ) -> Result<Committed<T>, OptimisticTxnError<E>>
+    where
+        F: FnMut(OptimisticTxn<'a, RT>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut retries = 0;
+
+        loop {
+            let txn = OptimisticTxn::new(self);
+            let state = txn.state.clone();
+
+            let value = f(txn).await.map_err(OptimisticTxnError::Aborted)?;
+
+            let req = state.lock().to_txn_request();
+            let reply = self.transaction(req).await?;
+
+            if reply.success {
+                return Ok(Committed { value, retries });
+            }
+
+            let keys = state.lock().reads.keys().cloned().collect::<Vec<_>>();
+
+            if retries >= options.max_retries() {
+                return Err(OptimisticTxnError::Conflict { retries, keys });
+            }
+
+            retries += 1;
+            let backoff = options.retry_policy.backoff(retries);
+
+            info!(
+                "optimistic txn conflicts on keys: {:?}, retry {} after {:?}",
+                keys, retries, backoff
+            );
+
+            sleep(backoff).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OptimisticTxnOptions;
+
+    #[test]
+    fn test_max_retries() {
+        assert_eq!(10, OptimisticTxnOptions::default().max_retries());
+
+        let opts = OptimisticTxnOptions::default().with_max_retries(2);
+        assert_eq!(3, opts.retry_policy.max_attempts);
+        assert_eq!(2, opts.max_retries());
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A local cache of the key-values under a dir, kept up to date by a watch stream.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use databend_meta_runtime_api::SpawnApi;
+use databend_meta_types::SeqV;
+use databend_meta_types::protobuf::WatchRequest;
+use databend_meta_types::protobuf::WatchResponse;
+use futures::StreamExt;
+use log::debug;
+use log::info;
+use log::warn;
+use parking_lot::Mutex;
+use tokio::select;
+use tokio::sync::oneshot;
+use tokio::sync::watch;
+
+use crate::ClientHandle;
+use crate::WatchEvent;
+
+/// Error returned when the background worker of a [`PrefixCache`] has quit.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("PrefixCache worker for '{dir}' quit")]
+pub struct PrefixCacheClosed {
+    pub dir: String,
+}
+
+/// Error returned by [`PrefixCache::wait_for_seq`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WaitForSeqError {
+    #[error(transparent)]
+    Closed(#[from] PrefixCacheClosed),
+
+    /// The `seq` is not applied to the cache within the timeout.
+    #[error("PrefixCache for '{dir}' did not see seq {seq} in {timeout:?}")]
+    Timeout {
+        dir: String,
+        seq: u64,
+        timeout: Duration,
+    },
+}
+
+/// A local copy of all the key-values under a dir, e.g., `tenant/abc/`.
+///
+/// A background task watches the dir with initial flush:
+/// the cache is filled with all existing key-values,
+/// then every change event is applied to it.
+/// If the watch stream is reset, see [`ClientHandle::watch_resilient`],
+/// the cache is replaced with the newly flushed key-values;
+/// until then, the previous content is served.
+///
+/// The background task quits when this cache is dropped,
+/// or when the watch fails with an error that re-watching does not resolve, see [`WatchEvent::Failed`].
+pub struct PrefixCache {
+    dir: String,
+
+    kvs: Arc<Mutex<BTreeMap<String, SeqV>>>,
+
+    /// The greatest `seq` applied to the cache, `None` until the first initialization completes.
+    seq_rx: watch::Receiver<Option<u64>>,
+
+    /// Dropping it notifies the background task to quit.
+    _cancel_tx: oneshot::Sender<()>,
+}
+
+impl fmt::Display for PrefixCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrefixCache({})", self.dir)
+    }
+}
+
+impl PrefixCache {
+    /// Create a cache of the key-values under `dir` and start the background task that fills it.
+    ///
+    /// A slash "/" is appended to `dir` if it does not end with one.
+    pub fn new<RT: SpawnApi>(client: Arc<ClientHandle<RT>>, dir: impl ToString) -> Self {
+        let req = WatchRequest::new_dir(dir.to_string()).with_initial_flush(true);
+        let dir = req.key.clone();
+
+        let kvs = Arc::new(Mutex::new(BTreeMap::new()));
+        let (seq_tx, seq_rx) = watch::channel(None);
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+
+        let worker = CacheWorker {
+            client,
+            req,
+            kvs: kvs.clone(),
+            seq_tx,
+        };
+
+        RT::spawn(
+            worker.run(cancel_rx),
+            Some(format!("PrefixCache({})::run()", dir)),
+        );
+
+        Self {
+            dir,
+            kvs,
+            seq_rx,
+            _cancel_tx: cancel_tx,
+        }
+    }
+
+    /// The dir this cache holds, always ends with a slash.
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+
+    pub fn get(&self, key: &str) -> Option<SeqV> {
+        self.kvs.lock().get(key).cloned()
+    }
+
+    /// List the cached key-values starting with `prefix`, in key order.
+    ///
+    /// The returned key-values are a consistent snapshot:
+    /// no change event is applied in the middle of listing.
+    pub fn list(&self, prefix: &str) -> Vec<(String, SeqV)> {
+        let kvs = self.kvs.lock();
+
+        kvs.range(prefix.to_string()..)
+            .take_while(|(k, _)| k.starts_with(prefix))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Returns the greatest `seq` applied to this cache,
+    /// or `None` if the cache is not yet initialized.
+    pub fn last_seq(&self) -> Option<u64> {
+        *self.seq_rx.borrow()
+    }
+
+    /// Wait until all the existing key-values are loaded for the first time.
+    pub async fn wait_initialized(&self) -> Result<(), PrefixCacheClosed> {
+        self.wait_until(|seq| seq.is_some()).await
+    }
+
+    /// Wait until the change with `seq` is applied to this cache.
+    ///
+    /// It is used to read the cache after a write to a key under the dir,
+    /// with the `seq` in the write reply.
+    /// A delete does not produce a new `seq`, and a write outside of the dir is never seen,
+    /// in which cases it returns [`WaitForSeqError::Timeout`] after `timeout`.
+    pub async fn wait_for_seq(&self, seq: u64, timeout: Duration) -> Result<(), WaitForSeqError> {
+        let fu = self.wait_until(|last| last.is_some_and(|x| x >= seq));
+
+        match tokio::time::timeout(timeout, fu).await {
+            Ok(res) => Ok(res?),
+            Err(_elapsed) => Err(WaitForSeqError::Timeout {
+                dir: self.dir.clone(),
+                seq,
+                timeout,
+            }),
+        }
+    }
+
+    async fn wait_until(
+        &self,
+        f: impl FnMut(&Option<u64>) -> bool,
+    ) -> Result<(), PrefixCacheClosed> {
+        let mut rx = self.seq_rx.clone();
+        rx.wait_for(f).await.map_err(|_| PrefixCacheClosed {
+            dir: self.dir.clone(),
+        })?;
+        Ok(())
+    }
+}
+
+/// The background task of a [`PrefixCache`].
+struct CacheWorker<RT: SpawnApi> {
+    client: Arc<ClientHandle<RT>>,
+    req: WatchRequest,
+    kvs: Arc<Mutex<BTreeMap<String, SeqV>>>,
+    seq_tx: watch::Sender<Option<u64>>,
+}
+
+impl<RT: SpawnApi> fmt::Display for CacheWorker<RT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrefixCache({})", self.req.key)
+    }
+}
+
+impl<RT: SpawnApi> CacheWorker<RT> {
+    async fn run(self, mut cancel_rx: oneshot::Receiver<()>) {
+        info!("{} start", self);
+
+        let mut strm = self.client.watch_resilient(self.req.clone());
+
+        // Key-values received in the initialization phase,
+        // they replace the cache when the initialization completes.
+        let mut initial = Some(BTreeMap::new());
+
+        loop {
+            let ev = select! {
+                _ = &mut cancel_rx => {
+                    info!("{} received quit signal, quit", self);
+                    return;
+                }
+                ev = strm.next() => ev,
+            };
+
+            let Some(ev) = ev else {
+                warn!("{} watch stream ended, quit", self);
+                return;
+            };
+
+            let resp = match ev {
+                WatchEvent::Response(resp) => resp,
+                WatchEvent::Reset { reason } => {
+                    info!("{} watch reset: {}; re-initialize", self, reason);
+                    initial = Some(BTreeMap::new());
+                    continue;
+                }
+                WatchEvent::Failed(status) => {
+                    warn!("{} watch failed: {}, quit", self, status);
+                    return;
+                }
+            };
+
+            if resp.is_initialization_complete_flag() {
+                if let Some(kvs) = initial.take() {
+                    self.replace(kvs);
+                }
+                continue;
+            }
+
+            match initial.as_mut() {
+                Some(kvs) => {
+                    Self::apply(kvs, resp);
+                }
+                None => {
+                    let seq = Self::apply(&mut self.kvs.lock(), resp);
+                    self.update_seq(seq);
+                }
+            }
+        }
+    }
+
+    /// Replace the cache content with the key-values loaded by initialization.
+    fn replace(&self, kvs: BTreeMap<String, SeqV>) {
+        let seq = kvs.values().map(|v| v.seq).max().unwrap_or_default();
+
+        info!("{} initialized: {} keys, max seq: {}", self, kvs.len(), seq);
+
+        *self.kvs.lock() = kvs;
+        self.update_seq(seq);
+    }
+
+    /// Apply an event to `kvs` and return the `seq` of the new value, or `0` for a delete.
+    fn apply(kvs: &mut BTreeMap<String, SeqV>, resp: WatchResponse) -> u64 {
+        let Some((key, _prev, current)) = resp.unpack() else {
+            return 0;
+        };
+
+        debug!("PrefixCache apply: {}: {:?}", key, current);
+
+        match current {
+            Some(v) => {
+                let seq = v.seq;
+                kvs.insert(key, v);
+                seq
+            }
+            None => {
+                kvs.remove(&key);
+                0
+            }
+        }
+    }
+
+    fn update_seq(&self, seq: u64) {
+        self.seq_tx.send_modify(|last| {
+            *last = Some(std::cmp::max(last.unwrap_or_default(), seq));
+        });
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A watch stream that re-watches when the underlying gRPC stream fails.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use databend_meta_runtime_api::SpawnApi;
+use databend_meta_types::MetaClientError;
+use databend_meta_types::protobuf::WatchRequest;
+use databend_meta_types::protobuf::WatchResponse;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use log::info;
+use log::warn;
+use tokio::time::sleep;
+use tonic::Code;
+use tonic::Status;
+use tonic::codec::Streaming;
+
+use crate::ClientHandle;
+use crate::RetryPolicy;
+
+/// The backoff before re-watching after it fails to connect or the stream is broken.
+fn rewatch_backoff() -> RetryPolicy {
+    RetryPolicy::default().with_backoff(Duration::from_millis(50), Duration::from_secs(5))
+}
+
+/// An item of the stream returned by [`ClientHandle::watch_resilient`].
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// A response received from the watch stream.
+    Response(WatchResponse),
+
+    /// The watch stream was broken and has been re-established.
+    ///
+    /// Changes made in between are not delivered.
+    /// If the [`WatchRequest`] has `initial_flush` enabled,
+    /// all the current key-values are sent again followed by an initialization-complete flag,
+    /// and the caller should discard the state it built from the previous stream.
+    Reset {
+        /// Why the previous stream was broken.
+        reason: String,
+    },
+
+    /// The watch failed with an error that re-watching does not resolve,
+    /// e.g., `InvalidArgument` or `ResourceExhausted`.
+    ///
+    /// It is the last item of the stream.
+    Failed(Status),
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchEvent::Response(resp) => write!(f, "Response({:?})", resp),
+            WatchEvent::Reset { reason } => write!(f, "Reset({})", reason),
+            WatchEvent::Failed(status) => write!(f, "Failed({})", status),
+        }
+    }
+}
+
+struct ResilientWatch<RT: SpawnApi> {
+    client: Arc<ClientHandle<RT>>,
+    req: WatchRequest,
+
+    strm: Option<Streaming<WatchResponse>>,
+
+    /// Why the last stream was broken, `None` if no stream has been established.
+    broken: Option<String>,
+
+    /// Whether a stream has been established before.
+    established: bool,
+
+    backoff: RetryPolicy,
+
+    /// The number of failures since the last response is received.
+    failures: u32,
+
+    /// Whether a non-retryable error is returned, after which the stream ends.
+    failed: bool,
+}
+
+impl<RT: SpawnApi> fmt::Display for ResilientWatch<RT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ResilientWatch([{}, {:?}))",
+            self.req.key, self.req.key_end
+        )
+    }
+}
+
+impl<RT: SpawnApi> ResilientWatch<RT> {
+    async fn next_event(&mut self) -> WatchEvent {
+        loop {
+            if let Some(strm) = self.strm.as_mut() {
+                let reason = match strm.next().await {
+                    Some(Ok(resp)) => {
+                        // Only a stream that delivers a response resets the backoff,
+                        // a stream that is accepted then closed at once does not.
+                        self.failures = 0;
+                        return WatchEvent::Response(resp);
+                    }
+                    Some(Err(status)) => {
+                        if !self.is_retryable(&status) {
+                            return self.fail(status);
+                        }
+                        status.to_string()
+                    }
+                    None => "watch stream closed by server".to_string(),
+                };
+
+                let backoff = self.next_backoff();
+                warn!(
+                    "{} stream broken: {}; re-watch after {:?}",
+                    self, reason, backoff
+                );
+
+                self.strm = None;
+                self.broken = Some(reason);
+                sleep(backoff).await;
+                continue;
+            }
+
+            match Self::connect(&self.client, &self.req).await {
+                Ok(strm) => {
+                    self.strm = Some(strm);
+
+                    if self.established {
+                        let reason = self.broken.take().unwrap_or_default();
+                        info!(
+                            "{} re-established, previous stream broken: {}",
+                            self, reason
+                        );
+                        return WatchEvent::Reset { reason };
+                    }
+                    self.established = true;
+                }
+                Err(e) => {
+                    if let Some(code) = e.status_code() {
+                        let status = Status::new(code, e.to_string());
+                        if !self.is_retryable(&status) {
+                            return self.fail(status);
+                        }
+                    }
+
+                    let backoff = self.next_backoff();
+                    warn!("{} failed to watch: {}; retry after {:?}", self, e, backoff);
+
+                    sleep(backoff).await;
+                }
+            }
+        }
+    }
+
+    /// Returns whether to re-watch after the watch fails with `status`.
+    ///
+    /// It is decided as a read by [`RetryPolicy::is_retryable`], except that
+    /// a lagged stream(`DataLoss`) and a broken connection(`Unknown`) are re-watched too.
+    fn is_retryable(&self, status: &Status) -> bool {
+        match status.code() {
+            Code::DataLoss | Code::Unknown => true,
+            _ => self.backoff.is_retryable(status, false),
+        }
+    }
+
+    fn fail(&mut self, status: Status) -> WatchEvent {
+        warn!("{} failed with non-retryable error: {}; stop", self, status);
+
+        self.strm = None;
+        self.failed = true;
+        WatchEvent::Failed(status)
+    }
+
+    /// Count a failure and return the sleep time before re-watching.
+    fn next_backoff(&mut self) -> Duration {
+        self.failures += 1;
+        self.backoff.backoff(self.failures)
+    }
+
+    /// It does not borrow `self`: the stream in it is not `Sync`.
+    async fn connect(
+        client: &ClientHandle<RT>,
+        req: &WatchRequest,
+    ) -> Result<Streaming<WatchResponse>, MetaClientError> {
+        if req.initial_flush {
+            client.watch_with_initialization(req.clone()).await
+        } else {
+            client.watch(req.clone()).await
+        }
+    }
+}
+
+impl<RT: SpawnApi> ClientHandle<RT> {
+    /// Watch with a stream that does not end on a transient error:
+    /// when the gRPC stream is broken, e.g., by a leader change or a server restart,
+    /// it re-watches with backoff, and emits a [`WatchEvent::Reset`] once a new stream is
+    /// established.
+    /// An error that re-watching does not resolve is emitted as [`WatchEvent::Failed`],
+    /// and the stream ends.
+    ///
+    /// The server does not support resuming a watch from a `seq`,
+    /// thus a [`WatchEvent::Reset`] means changes may be missed;
+    /// enable `initial_flush` in `watch` to receive a full copy again after it.
+    ///
+    /// Drop the returned stream to stop watching.
+    pub fn watch_resilient(
+        self: &Arc<Self>,
+        watch: WatchRequest,
+    ) -> BoxStream<'static, WatchEvent> {
+        let state = ResilientWatch {
+            client: self.clone(),
+            req: watch,
+            strm: None,
+            broken: None,
+            established: false,
+            backoff: rewatch_backoff(),
+            failures: 0,
+            failed: false,
+        };
+
+        let strm = futures::stream::unfold(state, |mut state| async move {
+            if state.failed {
+                return None;
+            }
+            let ev = state.next_event().await;
+            Some((ev, state))
+        });
+
+        Box::pin(strm)
+    }
+}
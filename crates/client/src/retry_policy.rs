@@ -0,0 +1,179 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retry policy and per-request options of the RPCs sent by a [`ClientHandle`](crate::ClientHandle).
+
+use std::time::Duration;
+use std::time::Instant;
+
+use tonic::Code;
+use tonic::Status;
+
+/// Defines how a request is retried when the RPC fails with a transient error.
+///
+/// A failed RPC is retried on the next endpoint, after a backoff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Max number of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// The sleep time before the first retry.
+    /// The next sleep time is 2 times of the previous one, up to `max_backoff`.
+    pub initial_backoff: Duration,
+
+    pub max_backoff: Duration,
+
+    /// Whether to retry a write when the error does not tell if it is applied,
+    /// e.g., `Internal` or `Cancelled`.
+    ///
+    /// A read is always retried on such errors.
+    /// Disable it for a write that must not be applied twice, such as a `fetch_increase_u64`.
+    pub retry_uncertain_writes: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            retry_uncertain_writes: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Send a request only once.
+    pub fn no_retry() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn with_retry_uncertain_writes(mut self, retry: bool) -> Self {
+        self.retry_uncertain_writes = retry;
+        self
+    }
+
+    /// Returns whether an RPC that failed with `status` should be retried.
+    pub fn is_retryable(&self, status: &Status, is_write: bool) -> bool {
+        match status.code() {
+            // The request is rejected before being handled.
+            Code::Unauthenticated | Code::Unavailable => true,
+            // The request may have been applied.
+            Code::Internal | Code::Cancelled => !is_write || self.retry_uncertain_writes,
+            _ => false,
+        }
+    }
+
+    /// The sleep time before the `n`-th retry, starting from 1.
+    pub(crate) fn backoff(&self, n: u32) -> Duration {
+        let factor = 2u32.saturating_pow(n.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Options to override the defaults of a single request sent by a [`ClientHandle`](crate::ClientHandle).
+///
+/// They apply to the requests that are retried by the client: reads, lists and transactions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    /// Override the default [`RetryPolicy`].
+    pub retry_policy: Option<RetryPolicy>,
+
+    /// Max time for the request to complete, including all retries.
+    ///
+    /// For a request that returns a stream, it limits the time to receive the stream,
+    /// not the time to consume it.
+    pub timeout: Option<Duration>,
+}
+
+impl RequestOptions {
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Resolve the options when a request is sent.
+    pub(crate) fn to_rpc_options(&self) -> RpcOptions {
+        RpcOptions {
+            retry_policy: self.retry_policy.clone().unwrap_or_default(),
+            deadline: self.timeout.map(|t| Instant::now() + t),
+        }
+    }
+}
+
+/// The resolved [`RequestOptions`] carried with a request to the client worker.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RpcOptions {
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) deadline: Option<Instant>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tonic::Status;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn test_backoff() {
+        let p = RetryPolicy::default()
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+        assert_eq!(Duration::from_millis(10), p.backoff(1));
+        assert_eq!(Duration::from_millis(20), p.backoff(2));
+        assert_eq!(Duration::from_millis(40), p.backoff(3));
+        assert_eq!(Duration::from_millis(50), p.backoff(4));
+
+        assert_eq!(Duration::ZERO, RetryPolicy::default().backoff(3));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let p = RetryPolicy::default();
+
+        assert!(p.is_retryable(&Status::unavailable(""), true));
+        assert!(p.is_retryable(&Status::internal(""), true));
+        assert!(!p.is_retryable(&Status::deadline_exceeded(""), false));
+        assert!(!p.is_retryable(&Status::invalid_argument(""), false));
+
+        let p = p.with_retry_uncertain_writes(false);
+
+        assert!(p.is_retryable(&Status::unavailable(""), true));
+        assert!(p.is_retryable(&Status::unauthenticated(""), true));
+        assert!(!p.is_retryable(&Status::internal(""), true));
+        assert!(!p.is_retryable(&Status::cancelled(""), true));
+        assert!(p.is_retryable(&Status::internal(""), false));
+        assert!(p.is_retryable(&Status::cancelled(""), false));
+    }
+}
@@ -19,6 +19,7 @@ use std::time::Instant;
 
 use anyerror::AnyError;
 use databend_base::futures::ElapsedFutureExt;
+use databend_meta_runtime_api::ClientMetricsApi;
 use databend_meta_runtime_api::RuntimeApi;
 use databend_meta_types::ConnectionError;
 use databend_meta_types::MetaClientError;
@@ -26,12 +27,15 @@ use databend_meta_types::MetaNetworkError;
 use log::debug;
 use log::info;
 use log::warn;
-use tonic::Code;
+use tokio::time::sleep;
+use tokio::time::timeout;
 use tonic::Response;
 use tonic::Status;
 
 use crate::MetaGrpcClient;
+use crate::RetryPolicy;
 use crate::established_client::EstablishedClient;
+use crate::retry_policy::RpcOptions;
 
 /// Represents the action to take after processing an RPC response.
 ///
@@ -54,14 +58,31 @@ pub(crate) enum ResponseAction<T> {
 ///
 /// # Example
 /// ```ignore
-/// let mut handler = RpcHandler::new(&client, feature_spec);
-/// let established = handler.new_established_client().await?;
-/// let result = handler.process_response_result(&request, rpc_result)?;
+/// let mut handler = RpcHandler::new(&client, "transaction", &options, true);
+/// while handler.next_attempt().await {
+///     let established = handler.new_established_client().await?;
+///     let result = handler.process_response_result(&request, rpc_result)?;
+/// }
 /// ```
 pub(crate) struct RpcHandler<'a, RT: RuntimeApi> {
     /// Reference to the gRPC client for meta-service communication
     pub(crate) client: &'a MetaGrpcClient<RT>,
 
+    /// Name of the RPC, for recording metrics.
+    pub(crate) req_name: &'static str,
+
+    /// Decides whether and when to retry a failed RPC.
+    pub(crate) retry_policy: RetryPolicy,
+
+    /// The RPC must complete before this time, including all retries.
+    pub(crate) deadline: Option<Instant>,
+
+    /// Whether the RPC may change the state, it is retried with care.
+    pub(crate) is_write: bool,
+
+    /// Number of attempts started so far.
+    pub(crate) attempts: u32,
+
     /// History of failed RPC attempts, storing endpoint description and error details.
     /// Used for error reporting and debugging connection issues.
     pub(crate) rpc_failures: Vec<(String, Status)>,
@@ -72,15 +93,59 @@ pub(crate) struct RpcHandler<'a, RT: RuntimeApi> {
 }
 
 impl<'a, RT: RuntimeApi> RpcHandler<'a, RT> {
-    /// Creates a new RPC handler for the given client and feature requirements.
-    pub(crate) fn new(client: &'a MetaGrpcClient<RT>) -> Self {
+    /// Creates a new RPC handler for the given client, with the retry policy and deadline in `options`.
+    pub(crate) fn new(
+        client: &'a MetaGrpcClient<RT>,
+        req_name: &'static str,
+        options: &RpcOptions,
+        is_write: bool,
+    ) -> Self {
         Self {
             client,
+            req_name,
+            retry_policy: options.retry_policy.clone(),
+            deadline: options.deadline,
+            is_write,
+            attempts: 0,
             rpc_failures: Vec::new(),
             established_client: None,
         }
     }
 
+    /// Prepares for the next attempt, sleeping for the backoff if it is a retry.
+    ///
+    /// Returns `false` if the retry policy allows no more attempt, or the deadline is reached.
+    pub(crate) async fn next_attempt(&mut self) -> bool {
+        if self.attempts >= self.retry_policy.max_attempts.max(1) {
+            return false;
+        }
+
+        if self.attempts > 0 {
+            let backoff = self.retry_policy.backoff(self.attempts);
+
+            if self.remaining().is_some_and(|left| left <= backoff) {
+                return false;
+            }
+
+            if !backoff.is_zero() {
+                sleep(backoff).await;
+            }
+        }
+
+        if self.remaining() == Some(Duration::ZERO) {
+            return false;
+        }
+
+        self.attempts += 1;
+        true
+    }
+
+    /// Returns the time left before the deadline, or `None` if there is no deadline.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
     /// Establishes a new connection to the meta-service and validates feature compatibility.
     ///
     /// This method will:
@@ -99,14 +164,17 @@ impl<'a, RT: RuntimeApi> RpcHandler<'a, RT> {
     pub(crate) async fn new_established_client(
         &mut self,
     ) -> Result<&mut EstablishedClient, MetaClientError> {
-        let client = self
-            .client
-            .get_established_client()
-            .inspect_elapsed_over(
-                default_timing_threshold(),
-                create_timing_logger("MetaGrpcClient::get_established_client"),
-            )
-            .await?;
+        let fu = self.client.get_established_client().inspect_elapsed_over(
+            default_timing_threshold(),
+            create_timing_logger("MetaGrpcClient::get_established_client"),
+        );
+
+        let client = match self.remaining() {
+            None => fu.await?,
+            Some(left) => timeout(left, fu)
+                .await
+                .map_err(|_| deadline_exceeded(left))??,
+        };
 
         self.established_client = Some((client, Instant::now()));
 
@@ -170,7 +238,7 @@ impl<'a, RT: RuntimeApi> RpcHandler<'a, RT> {
             }
         };
 
-        if is_status_retryable(&status) {
+        if self.retry_policy.is_retryable(&status, self.is_write) {
             let client_display = established_client.to_string();
 
             warn!(
@@ -201,9 +269,16 @@ impl<'a, RT: RuntimeApi> RpcHandler<'a, RT> {
     /// - Details of each failure
     /// - The RPC operation that was being attempted
     pub(crate) fn create_network_error(&self) -> MetaNetworkError {
+        let reason = if self.remaining() == Some(Duration::ZERO) {
+            "deadline exceeded "
+        } else {
+            ""
+        };
+
         let conn_err = ConnectionError::new(
             AnyError::error(format_args!(
-                "after {} retries: {:?}",
+                "{}after {} retries: {:?}",
+                reason,
                 self.rpc_failures.len(),
                 self.rpc_failures
             )),
@@ -214,6 +289,36 @@ impl<'a, RT: RuntimeApi> RpcHandler<'a, RT> {
     }
 }
 
+impl<RT: RuntimeApi> Drop for RpcHandler<'_, RT> {
+    fn drop(&mut self) {
+        if self.attempts > 0 {
+            RT::ClientMetrics::record_request_attempts(self.req_name, self.attempts);
+        }
+    }
+}
+
+/// Runs an RPC with a timeout, if there is one.
+///
+/// It returns `DeadlineExceeded` when the timeout is reached, which is not retried.
+pub(crate) async fn with_timeout<T>(
+    left: Option<Duration>,
+    fu: impl Future<Output = Result<T, Status>>,
+) -> Result<T, Status> {
+    match left {
+        None => fu.await,
+        Some(left) => timeout(left, fu)
+            .await
+            .unwrap_or_else(|_| Err(deadline_exceeded(left))),
+    }
+}
+
+fn deadline_exceeded(left: Duration) -> Status {
+    Status::deadline_exceeded(format!(
+        "MetaGrpcClient: request deadline exceeded, time left was {:?}",
+        left
+    ))
+}
+
 /// Returns the default timing threshold for RPC operations.
 fn default_timing_threshold() -> Duration {
     Duration::from_millis(300)
@@ -225,11 +330,3 @@ fn create_timing_logger<T>(msg: impl Display) -> impl Fn(&T, Duration, Duration)
         info!("{} spent: total: {:?}, busy: {:?}", msg, total, busy);
     }
 }
-
-/// Determines if a gRPC status code indicates a retryable error.
-fn is_status_retryable(status: &Status) -> bool {
-    matches!(
-        status.code(),
-        Code::Unauthenticated | Code::Unavailable | Code::Internal | Code::Cancelled
-    )
-}
@@ -28,6 +28,7 @@ use databend_meta_runtime_api::TokioRuntime;
 use databend_meta_types::MetaError;
 use databend_meta_types::UpsertKV;
 use databend_meta_types::protobuf::StreamItem;
+use databend_meta_version::Feature;
 use futures::StreamExt;
 use log::info;
 use tonic::codegen::BoxStream;
@@ -114,7 +115,10 @@ async fn test_grpc_client_handshake_timeout() {
         )
         .await;
 
-        assert!(res.is_ok());
+        // The test server sends no features, they are derived from its version.
+        let (_token, _server_version, features) = res.unwrap();
+        assert!(features.contains(&Feature::KvReadV1));
+        assert!(!features.contains(&Feature::KvList));
     }
 }
 
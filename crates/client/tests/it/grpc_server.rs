@@ -67,6 +67,7 @@ impl MetaService for GrpcServiceForTestImpl {
             Ok(HandshakeResponse {
                 protocol_version: MIN_SERVER_VERSION.to_digit(),
                 payload: vec![],
+                features: vec![],
             })
         });
         Ok(Response::new(Box::pin(output)))
@@ -0,0 +1,171 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt;
+
+use crate::kvapi::Key;
+use crate::kvapi::PbApiError;
+use crate::kvapi::Value;
+use crate::kvapi::ValueCodec;
+
+type DecodeDependencies =
+    Box<dyn Fn(&str, &[u8]) -> Result<Vec<String>, PbApiError<Infallible>> + Send + Sync>;
+
+struct Entry {
+    /// A root record is an entry point, e.g., a name-to-id record, which is not referenced by others.
+    root: bool,
+    decode: DecodeDependencies,
+}
+
+/// Decodes raw records of registered [`Key`] types to find out the keys a record depends on,
+/// with [`Value::dependency_keys`].
+///
+/// A raw key is resolved to the registered type with the longest matching [`Key::PREFIX`].
+#[derive(Default)]
+pub struct DependencyRegistry {
+    entries: BTreeMap<&'static str, Entry>,
+}
+
+impl fmt::Debug for DependencyRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DependencyRegistry")
+            .field("prefixes", &self.entries.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl DependencyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a key type whose records are expected to be referenced by other records.
+    pub fn register<K>(self) -> Self
+    where
+        K: Key + 'static,
+        K::ValueType: Value<KeyType = K> + ValueCodec,
+    {
+        self.add::<K>(false)
+    }
+
+    /// Register a key type whose records are entry points and are never orphans.
+    pub fn register_root<K>(self) -> Self
+    where
+        K: Key + 'static,
+        K::ValueType: Value<KeyType = K> + ValueCodec,
+    {
+        self.add::<K>(true)
+    }
+
+    fn add<K>(mut self, root: bool) -> Self
+    where
+        K: Key + 'static,
+        K::ValueType: Value<KeyType = K> + ValueCodec,
+    {
+        let decode = |key: &str, value: &[u8]| -> Result<Vec<String>, PbApiError<Infallible>> {
+            let key = K::from_str_key(key)?;
+            let value = K::ValueType::decode_value(value)?;
+            Ok(value.dependency_keys(&key).into_iter().collect())
+        };
+
+        self.entries.insert(K::PREFIX, Entry {
+            root,
+            decode: Box::new(decode),
+        });
+        self
+    }
+
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(prefix, _)| {
+                key.strip_prefix(*prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|(_, entry)| entry)
+    }
+
+    /// Returns the [`Key::PREFIX`] of every registered type.
+    pub fn prefixes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.keys().copied()
+    }
+
+    /// Returns if the record of `key` is an entry point, or `None` if its type is not registered.
+    pub fn is_root(&self, key: &str) -> Option<bool> {
+        self.entry(key).map(|e| e.root)
+    }
+
+    /// Decode a raw record and returns the keys it depends on.
+    ///
+    /// Returns `None` if the type of `key` is not registered.
+    pub fn dependency_keys<E>(
+        &self,
+        key: &str,
+        value: &[u8],
+    ) -> Result<Option<Vec<String>>, PbApiError<E>> {
+        let Some(entry) = self.entry(key) else {
+            return Ok(None);
+        };
+
+        match (entry.decode)(key, value) {
+            Ok(keys) => Ok(Some(keys)),
+            Err(PbApiError::KeyError(e)) => Err(PbApiError::KeyError(e)),
+            Err(PbApiError::ValueCodecError(e)) => Err(PbApiError::ValueCodecError(e)),
+            Err(PbApiError::KvApiError(never)) => match never {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::kvapi::DependencyRegistry;
+    use crate::kvapi::PbApiError;
+    use crate::kvapi::testing::MetaKey;
+    use crate::kvapi::testing::NameKey;
+
+    #[test]
+    fn test_dependency_keys() -> anyhow::Result<()> {
+        let reg = DependencyRegistry::new()
+            .register_root::<NameKey>()
+            .register::<MetaKey>();
+
+        assert_eq!(Some(true), reg.is_root("name/a"));
+        assert_eq!(Some(false), reg.is_root("meta/1"));
+        assert_eq!(None, reg.is_root("metadata/1"));
+        assert_eq!(None, reg.is_root("foo/1"));
+        assert_eq!(vec!["meta", "name"], reg.prefixes().collect::<Vec<_>>());
+
+        let got = reg.dependency_keys::<io::Error>("name/a", b"1")?;
+        assert_eq!(Some(vec!["meta/1".to_string()]), got);
+
+        let got = reg.dependency_keys::<io::Error>("meta/1", b"2,3")?;
+        assert_eq!(Some(vec!["meta/2".to_string(), "meta/3".to_string()]), got);
+
+        let got = reg.dependency_keys::<io::Error>("foo/1", b"2,3")?;
+        assert_eq!(None, got);
+
+        let res = reg.dependency_keys::<io::Error>("meta/x", b"");
+        assert!(matches!(res, Err(PbApiError::KeyError(_))));
+
+        let res = reg.dependency_keys::<io::Error>("name/a", b"x");
+        assert!(matches!(res, Err(PbApiError::ValueCodecError(_))));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use databend_meta_types::SeqV;
+use log::debug;
+
+use crate::kvapi::DependencyRegistry;
+use crate::kvapi::KVApi;
+use crate::kvapi::KvApiExt;
+use crate::kvapi::PbApiError;
+
+/// The records reachable from a root key by following [`Value::dependency_keys`](crate::kvapi::Value::dependency_keys).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// The records found, by key.
+    pub records: BTreeMap<String, SeqV>,
+
+    /// The keys each found record depends on.
+    ///
+    /// A record of a type that is not registered has no dependency.
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+
+    /// The keys that are depended on but not found.
+    pub missing: BTreeSet<String>,
+}
+
+impl DependencyGraph {
+    /// Returns the references `(from, to)` pointing to a key that does not exist.
+    pub fn dangling(&self) -> Vec<(String, String)> {
+        self.edges
+            .iter()
+            .flat_map(|(from, to)| to.iter().map(move |t| (from, t)))
+            .filter(|(_from, to)| self.missing.contains(*to))
+            .map(|(from, to)| (from.clone(), to.clone()))
+            .collect()
+    }
+}
+
+/// Builds the [`DependencyGraph`] reachable from a root key.
+///
+/// Records are fetched level by level with [`KVApi::get_many_kv`].
+pub struct DependencyWalker<'a, KV: ?Sized> {
+    kv: &'a KV,
+    registry: &'a DependencyRegistry,
+}
+
+impl<'a, KV> DependencyWalker<'a, KV>
+where KV: KVApi + ?Sized
+{
+    pub fn new(kv: &'a KV, registry: &'a DependencyRegistry) -> Self {
+        Self { kv, registry }
+    }
+
+    /// Walk from `root`, following every dependency until no new key is found.
+    ///
+    /// A missing root is recorded in [`DependencyGraph::missing`].
+    pub async fn walk(&self, root: &str) -> Result<DependencyGraph, PbApiError<KV::Error>> {
+        let mut graph = DependencyGraph::default();
+
+        let mut visited = BTreeSet::from([root.to_string()]);
+        let mut frontier = vec![root.to_string()];
+
+        while !frontier.is_empty() {
+            debug!("DependencyWalker: fetch {} keys", frontier.len());
+
+            let values = self
+                .kv
+                .mget_kv(&frontier)
+                .await
+                .map_err(PbApiError::KvApiError)?;
+
+            let mut next = vec![];
+
+            for (key, value) in frontier.into_iter().zip(values) {
+                let Some(seqv) = value else {
+                    graph.missing.insert(key);
+                    continue;
+                };
+
+                let deps = self
+                    .registry
+                    .dependency_keys(&key, &seqv.data)?
+                    .unwrap_or_default();
+
+                for dep in &deps {
+                    if visited.insert(dep.clone()) {
+                        next.push(dep.clone());
+                    }
+                }
+
+                graph.edges.insert(key.clone(), deps.into_iter().collect());
+                graph.records.insert(key, seqv);
+            }
+
+            frontier = next;
+        }
+
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kvapi::DependencyRegistry;
+    use crate::kvapi::DependencyWalker;
+    use crate::kvapi::testing::MemKVApi;
+    use crate::kvapi::testing::MetaKey;
+    use crate::kvapi::testing::NameKey;
+
+    fn s(x: &str) -> String {
+        x.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_walk() -> anyhow::Result<()> {
+        let kv = MemKVApi::with_kvs(&[
+            ("name/a", "1"),
+            ("meta/1", "2,3"),
+            // A cycle back to meta/1
+            ("meta/2", "1"),
+            ("meta/4", ""),
+        ]);
+
+        let reg = DependencyRegistry::new()
+            .register_root::<NameKey>()
+            .register::<MetaKey>();
+
+        let walker = DependencyWalker::new(&kv, &reg);
+
+        let graph = walker.walk("name/a").await?;
+        assert_eq!(
+            vec![s("meta/1"), s("meta/2"), s("name/a")],
+            graph.records.keys().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![s("meta/3")],
+            graph.missing.into_iter().collect::<Vec<_>>()
+        );
+
+        let graph = walker.walk("meta/2").await?;
+        assert_eq!(vec![(s("meta/1"), s("meta/3"))], graph.dangling());
+
+        let graph = walker.walk("name/b").await?;
+        assert!(graph.records.is_empty());
+        assert!(graph.dangling().is_empty());
+        assert_eq!(
+            vec![s("name/b")],
+            graph.missing.into_iter().collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use display_more::DisplaySliceExt;
+use futures_util::StreamExt;
+use futures_util::TryStreamExt;
+use futures_util::stream::BoxStream;
+use log::info;
+
+use crate::kvapi::DependencyRegistry;
+use crate::kvapi::KVApi;
+use crate::kvapi::KvApiExt;
+use crate::kvapi::ListOptions;
+use crate::kvapi::PbApiError;
+
+/// The max number of keys [`IntegrityChecker`] reads in one `mget_kv`.
+const MGET_CHUNK_SIZE: usize = 1024;
+
+/// The referential-integrity problems found by [`IntegrityChecker`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The number of records scanned.
+    pub scanned: usize,
+
+    /// References `(from, to)` pointing to a key that does not exist.
+    pub dangling: Vec<(String, String)>,
+
+    /// Scanned records that are not referenced by any record of a registered type.
+    ///
+    /// Records of root types or of unregistered types are never orphans.
+    pub orphans: Vec<String>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if neither dangling reference nor orphan is found.
+    pub fn is_ok(&self) -> bool {
+        self.dangling.is_empty() && self.orphans.is_empty()
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dangling = self
+            .dangling
+            .iter()
+            .map(|(from, to)| format!("{}->{}", from, to))
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "scanned: {}, dangling: {}, orphans: {}",
+            self.scanned,
+            dangling.display_n(10),
+            self.orphans.display_n(10)
+        )
+    }
+}
+
+/// Scans the records under a prefix and checks the references between them
+/// with [`Value::dependency_keys`](crate::kvapi::Value::dependency_keys).
+///
+/// A referred key outside the prefix is looked up with [`KVApi::get_many_kv`].
+/// The records of the registered types outside the prefix are scanned as well,
+/// so that a record referred to only from outside the prefix is not reported as an orphan.
+pub struct IntegrityChecker<'a, KV: ?Sized> {
+    kv: &'a KV,
+    registry: &'a DependencyRegistry,
+}
+
+impl<'a, KV> IntegrityChecker<'a, KV>
+where KV: KVApi + ?Sized
+{
+    pub fn new(kv: &'a KV, registry: &'a DependencyRegistry) -> Self {
+        Self { kv, registry }
+    }
+
+    pub async fn check(&self, prefix: &str) -> Result<IntegrityReport, PbApiError<KV::Error>> {
+        let mut scanned = BTreeSet::new();
+        let mut references = vec![];
+        let mut referenced = BTreeSet::new();
+
+        let mut strm = self.list(prefix).await?;
+
+        while let Some((key, value)) = strm.try_next().await.map_err(PbApiError::KvApiError)? {
+            let deps = self
+                .registry
+                .dependency_keys(&key, &value)?
+                .unwrap_or_default();
+
+            for dep in deps {
+                // A record referring to itself does not make it referenced.
+                if dep != key {
+                    referenced.insert(dep.clone());
+                }
+                references.push((key.clone(), dep));
+            }
+
+            scanned.insert(key);
+        }
+
+        // Find the references to the scanned records from the registered types outside the prefix.
+        for type_prefix in self.registry.prefixes() {
+            let type_prefix = format!("{}/", type_prefix);
+
+            // Already scanned.
+            if type_prefix.starts_with(prefix) {
+                continue;
+            }
+
+            let mut strm = self.list(&type_prefix).await?;
+
+            while let Some((key, value)) = strm.try_next().await.map_err(PbApiError::KvApiError)? {
+                if key.starts_with(prefix) {
+                    continue;
+                }
+
+                let deps = self
+                    .registry
+                    .dependency_keys(&key, &value)?
+                    .unwrap_or_default();
+
+                for dep in deps {
+                    if scanned.contains(&dep) {
+                        referenced.insert(dep);
+                    }
+                }
+            }
+        }
+
+        let outside = referenced
+            .iter()
+            .filter(|k| !scanned.contains(*k))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut existing_outside = BTreeSet::new();
+
+        for keys in outside.chunks(MGET_CHUNK_SIZE) {
+            let values = self
+                .kv
+                .mget_kv(keys)
+                .await
+                .map_err(PbApiError::KvApiError)?;
+
+            existing_outside.extend(
+                keys.iter()
+                    .zip(values)
+                    .filter_map(|(k, v)| v.map(|_| k.clone())),
+            );
+        }
+
+        let dangling = references
+            .into_iter()
+            .filter(|(_from, to)| !scanned.contains(to) && !existing_outside.contains(to))
+            .collect::<Vec<_>>();
+
+        let orphans = scanned
+            .iter()
+            .filter(|k| self.registry.is_root(k) == Some(false) && !referenced.contains(*k))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let report = IntegrityReport {
+            scanned: scanned.len(),
+            dangling,
+            orphans,
+        };
+
+        info!("IntegrityChecker: prefix: '{}': {}", prefix, report);
+
+        Ok(report)
+    }
+
+    /// List the raw records under `prefix` as a stream.
+    async fn list(
+        &self,
+        prefix: &str,
+    ) -> Result<BoxStream<'static, Result<(String, Vec<u8>), KV::Error>>, PbApiError<KV::Error>>
+    {
+        let strm = self
+            .kv
+            .list_kv(ListOptions::unlimited(prefix))
+            .await
+            .map_err(PbApiError::KvApiError)?;
+
+        // list_kv() does not return None value
+        let strm = strm.map_ok(|item| (item.key, item.value.map(|v| v.data).unwrap_or_default()));
+
+        Ok(strm.boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kvapi::DependencyRegistry;
+    use crate::kvapi::IntegrityChecker;
+    use crate::kvapi::IntegrityReport;
+    use crate::kvapi::testing::MemKVApi;
+    use crate::kvapi::testing::MetaKey;
+    use crate::kvapi::testing::NameKey;
+
+    fn s(x: &str) -> String {
+        x.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_check() -> anyhow::Result<()> {
+        let kv = MemKVApi::with_kvs(&[
+            ("name/a", "1"),
+            ("name/b", "5"),
+            ("meta/1", "2,3"),
+            ("meta/2", "1"),
+            ("meta/4", ""),
+            // Not registered
+            ("foo/1", "1"),
+        ]);
+
+        let reg = DependencyRegistry::new()
+            .register_root::<NameKey>()
+            .register::<MetaKey>();
+
+        let checker = IntegrityChecker::new(&kv, &reg);
+
+        let report = checker.check("").await?;
+        assert_eq!(
+            IntegrityReport {
+                scanned: 6,
+                dangling: vec![(s("meta/1"), s("meta/3")), (s("name/b"), s("meta/5"))],
+                orphans: vec![s("meta/4")],
+            },
+            report
+        );
+        assert!(!report.is_ok());
+        assert_eq!(
+            "scanned: 6, dangling: [meta/1->meta/3,name/b->meta/5], orphans: [meta/4]",
+            report.to_string()
+        );
+
+        // Referred records outside the prefix are looked up.
+        let report = checker.check("name/").await?;
+        assert_eq!(
+            IntegrityReport {
+                scanned: 2,
+                dangling: vec![(s("name/b"), s("meta/5"))],
+                orphans: vec![],
+            },
+            report
+        );
+
+        // Referred to only from outside the prefix: not an orphan.
+        let report = checker.check("meta/1").await?;
+        assert_eq!(
+            IntegrityReport {
+                scanned: 1,
+                dangling: vec![(s("meta/1"), s("meta/3"))],
+                orphans: vec![],
+            },
+            report
+        );
+
+        let report = checker.check("meta/").await?;
+        assert_eq!(
+            IntegrityReport {
+                scanned: 3,
+                dangling: vec![(s("meta/1"), s("meta/3"))],
+                orphans: vec![s("meta/4")],
+            },
+            report
+        );
+
+        // Not referred to by any record.
+        let report = checker.check("meta/4").await?;
+        assert_eq!(
+            IntegrityReport {
+                scanned: 1,
+                dangling: vec![],
+                orphans: vec![s("meta/4")],
+            },
+            report
+        );
+
+        let report = checker.check("name/a").await?;
+        assert!(report.is_ok());
+
+        Ok(())
+    }
+
+    /// The referred records outside the prefix are looked up in several chunks.
+    #[tokio::test]
+    async fn test_check_outside_more_than_a_chunk() -> anyhow::Result<()> {
+        let n = super::MGET_CHUNK_SIZE * 2 + 1;
+
+        // Every name refers to a meta, only the metas with even id exist.
+        let mut kvs = vec![];
+        for i in 0..n {
+            kvs.push((format!("name/{:05}", i), i.to_string()));
+            if i % 2 == 0 {
+                kvs.push((format!("meta/{}", i), s("")));
+            }
+        }
+        let kvs = kvs
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Vec<_>>();
+        let kv = MemKVApi::with_kvs(&kvs);
+
+        let reg = DependencyRegistry::new()
+            .register_root::<NameKey>()
+            .register::<MetaKey>();
+
+        let report = IntegrityChecker::new(&kv, &reg).check("name/").await?;
+
+        let dangling = (0..n)
+            .filter(|i| i % 2 == 1)
+            .map(|i| (format!("name/{:05}", i), format!("meta/{}", i)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            IntegrityReport {
+                scanned: n,
+                dangling,
+                orphans: vec![],
+            },
+            report
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed access to meta-service with structured keys and decoded values.
+
+use async_trait::async_trait;
+use databend_meta_types::Change;
+use databend_meta_types::MatchSeq;
+use databend_meta_types::MetaSpec;
+use databend_meta_types::Operation;
+use databend_meta_types::SeqV;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::With;
+use futures_util::StreamExt;
+use futures_util::TryStreamExt;
+use futures_util::stream::BoxStream;
+
+use crate::kvapi::DirName;
+use crate::kvapi::KVApi;
+use crate::kvapi::Key;
+use crate::kvapi::KeyError;
+use crate::kvapi::KvApiExt;
+use crate::kvapi::ListOptions;
+use crate::kvapi::NonEmptyItem;
+use crate::kvapi::ValueCodec;
+use crate::kvapi::ValueCodecError;
+
+/// Error returned by the methods of [`KvPbApi`].
+#[derive(Debug, thiserror::Error)]
+pub enum PbApiError<E> {
+    /// A key returned by meta-service can not be parsed into the structured key.
+    #[error(transparent)]
+    KeyError(#[from] KeyError),
+
+    #[error(transparent)]
+    ValueCodecError(#[from] ValueCodecError),
+
+    /// Error returned by the underlying [`KVApi`].
+    #[error(transparent)]
+    KvApiError(E),
+}
+
+/// A typed [`UpsertKV`] whose key is a [`Key`] and whose value is encoded with [`ValueCodec`].
+#[derive(Clone, Debug)]
+pub struct UpsertPb<K: Key> {
+    pub key: K,
+
+    /// Same as [`UpsertKV::seq`].
+    pub seq: MatchSeq,
+
+    /// The value to set, or [`Operation::Delete`] to delete it.
+    pub value: Operation<K::ValueType>,
+
+    /// Meta data of a value.
+    pub value_meta: Option<MetaSpec>,
+}
+
+impl<K: Key> UpsertPb<K> {
+    pub fn new(
+        key: K,
+        seq: MatchSeq,
+        value: Operation<K::ValueType>,
+        value_meta: Option<MetaSpec>,
+    ) -> Self {
+        Self {
+            key,
+            seq,
+            value,
+            value_meta,
+        }
+    }
+
+    /// Insert the value only if the key is absent.
+    pub fn insert(key: K, value: K::ValueType) -> Self {
+        Self::new(key, MatchSeq::Exact(0), Operation::Update(value), None)
+    }
+
+    /// Insert or overwrite the value.
+    pub fn update(key: K, value: K::ValueType) -> Self {
+        Self::new(key, MatchSeq::GE(0), Operation::Update(value), None)
+    }
+
+    /// Delete the key if it is present.
+    pub fn delete(key: K) -> Self {
+        Self::new(key, MatchSeq::GE(1), Operation::Delete, None)
+    }
+
+    /// Build the [`UpsertKV`] with the value encoded.
+    pub fn to_upsert_kv(&self) -> Result<UpsertKV, ValueCodecError>
+    where K::ValueType: ValueCodec {
+        #[allow(deprecated)]
+        let value = match &self.value {
+            Operation::Update(v) => Operation::Update(v.encode_value()?),
+            Operation::Delete => Operation::Delete,
+            Operation::AsIs => Operation::AsIs,
+        };
+
+        Ok(UpsertKV::new(
+            self.key.to_string_key(),
+            self.seq,
+            value,
+            self.value_meta.clone(),
+        ))
+    }
+}
+
+impl<K: Key> With<MatchSeq> for UpsertPb<K> {
+    fn with(mut self, seq: MatchSeq) -> Self {
+        self.seq = seq;
+        self
+    }
+}
+
+impl<K: Key> With<MetaSpec> for UpsertPb<K> {
+    fn with(mut self, meta: MetaSpec) -> Self {
+        self.value_meta = Some(meta);
+        self
+    }
+}
+
+/// Decode the value in a [`SeqV`] with [`ValueCodec`].
+pub fn decode_seqv<T: ValueCodec>(seqv: SeqV) -> Result<SeqV<T>, ValueCodecError> {
+    Ok(SeqV {
+        seq: seqv.seq,
+        meta: seqv.meta,
+        data: T::decode_value(&seqv.data)?,
+    })
+}
+
+/// Typed methods on top of [`KVApi`], with [`Key`] as key and [`ValueCodec`] to encode values.
+#[async_trait]
+pub trait KvPbApi: KVApi {
+    /// Get the value of a structured key and decode it.
+    async fn get_pb<K>(
+        &self,
+        key: &K,
+    ) -> Result<Option<SeqV<K::ValueType>>, PbApiError<Self::Error>>
+    where
+        K: Key + Sync,
+        K::ValueType: ValueCodec + Send,
+    {
+        let seqv = self
+            .get_kv(&key.to_string_key())
+            .await
+            .map_err(PbApiError::KvApiError)?;
+
+        let seqv = seqv.map(decode_seqv::<K::ValueType>).transpose()?;
+        Ok(seqv)
+    }
+
+    /// List the records in a directory and decode them.
+    ///
+    /// Only the keys in `dir` followed by a slash `/` are listed,
+    /// e.g., `DirName::new(FooKey{a:1, b:"x", c:2})` lists the keys starting with `pref/1/x/`.
+    async fn list_pb<K>(
+        &self,
+        dir: &DirName<K>,
+    ) -> Result<
+        BoxStream<'static, Result<NonEmptyItem<K>, PbApiError<Self::Error>>>,
+        PbApiError<Self::Error>,
+    >
+    where
+        K: Key + Send + Sync + 'static,
+        K::ValueType: ValueCodec + Send + 'static,
+    {
+        let prefix = dir.dir_name_with_slash();
+
+        let strm = self
+            .list_kv(ListOptions::unlimited(prefix.as_str()))
+            .await
+            .map_err(PbApiError::KvApiError)?;
+
+        let strm = strm
+            .map_err(PbApiError::KvApiError)
+            .and_then(|item| async move {
+                let key = K::from_str_key(&item.key)?;
+                // Safe unwrap(): list_kv() does not return None value
+                let seqv = decode_seqv(SeqV::from(item.value.unwrap()))?;
+                Ok(NonEmptyItem::new(key, seqv))
+            });
+
+        Ok(strm.boxed())
+    }
+
+    /// Same as [`Self::list_pb`] but collect the records into a [`Vec`].
+    async fn list_pb_vec<K>(
+        &self,
+        dir: &DirName<K>,
+    ) -> Result<Vec<NonEmptyItem<K>>, PbApiError<Self::Error>>
+    where
+        K: Key + Send + Sync + 'static,
+        K::ValueType: ValueCodec + Send + 'static,
+    {
+        let strm = self.list_pb(dir).await?;
+        strm.try_collect().await
+    }
+
+    /// Update or insert a record with the value encoded.
+    ///
+    /// Returns the previous and the resulting value, decoded.
+    async fn upsert_pb<K>(
+        &self,
+        req: &UpsertPb<K>,
+    ) -> Result<Change<K::ValueType>, PbApiError<Self::Error>>
+    where
+        K: Key + Sync,
+        K::ValueType: ValueCodec + Send + Sync,
+    {
+        let upsert_kv = req.to_upsert_kv()?;
+
+        let change = self
+            .upsert_kv(upsert_kv)
+            .await
+            .map_err(PbApiError::KvApiError)?;
+
+        Ok(Change {
+            ident: change.ident,
+            prev: change.prev.map(decode_seqv).transpose()?,
+            result: change.result.map(decode_seqv).transpose()?,
+        })
+    }
+}
+
+impl<T: KVApi + ?Sized> KvPbApi for T {}
+
+#[cfg(test)]
+mod tests {
+    use databend_meta_types::MatchSeq;
+    use databend_meta_types::UpsertKV;
+    use databend_meta_types::With;
+
+    use crate::kvapi::DirName;
+    use crate::kvapi::KVApi;
+    use crate::kvapi::KvPbApi;
+    use crate::kvapi::NonEmptyItem;
+    use crate::kvapi::PbApiError;
+    use crate::kvapi::UpsertPb;
+    use crate::kvapi::testing::FooKey;
+    use crate::kvapi::testing::FooValue;
+    use crate::kvapi::testing::MemKVApi;
+
+    fn foo_key(c: u64) -> FooKey {
+        FooKey {
+            a: 1,
+            b: "b".to_string(),
+            c,
+        }
+    }
+
+    fn foo(s: &str) -> FooValue {
+        FooValue(s.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_get_upsert_pb() -> anyhow::Result<()> {
+        let kv = MemKVApi::default();
+
+        assert_eq!(None, kv.get_pb(&foo_key(2)).await?);
+
+        let change = kv
+            .upsert_pb(&UpsertPb::insert(foo_key(2), foo("x")))
+            .await?;
+        assert_eq!(None, change.prev);
+        assert_eq!(Some(foo("x")), change.result.map(|x| x.data));
+
+        let got = kv.get_pb(&foo_key(2)).await?.unwrap();
+        assert_eq!((1, foo("x")), (got.seq, got.data));
+
+        // Insert does not overwrite
+        let change = kv
+            .upsert_pb(&UpsertPb::insert(foo_key(2), foo("y")))
+            .await?;
+        assert!(!change.is_changed());
+
+        // Update with a mismatching seq does not take effect
+        let req = UpsertPb::update(foo_key(2), foo("y")).with(MatchSeq::Exact(5));
+        let change = kv.upsert_pb(&req).await?;
+        assert!(!change.is_changed());
+
+        let req = UpsertPb::update(foo_key(2), foo("y")).with(MatchSeq::Exact(1));
+        let change = kv.upsert_pb(&req).await?;
+        assert_eq!(Some(foo("x")), change.prev.map(|x| x.data));
+        assert_eq!(Some(foo("y")), change.result.map(|x| x.data));
+
+        let change = kv.upsert_pb(&UpsertPb::delete(foo_key(2))).await?;
+        assert_eq!(Some(foo("y")), change.prev.map(|x| x.data));
+        assert_eq!(None, change.result);
+
+        assert_eq!(None, kv.get_pb(&foo_key(2)).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_pb() -> anyhow::Result<()> {
+        let kv = MemKVApi::default();
+
+        for c in [3, 1, 2] {
+            kv.upsert_pb(&UpsertPb::update(foo_key(c), foo(&format!("v{}", c))))
+                .await?;
+        }
+
+        // Not in the dir `pref/1/b/`
+        kv.upsert_kv(UpsertKV::update("pref/1/bb/1", b"v")).await?;
+
+        let got = kv.list_pb_vec(&DirName::new(foo_key(0))).await?;
+        let got = got
+            .into_iter()
+            .map(|NonEmptyItem { key, seqv }| (key.c, seqv.data))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, foo("v1")), (2, foo("v2")), (3, foo("v3"))], got);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_pb_invalid_key() -> anyhow::Result<()> {
+        let kv = MemKVApi::default();
+
+        kv.upsert_kv(UpsertKV::update("pref/1/b/x", b"v")).await?;
+
+        let res = kv.list_pb_vec(&DirName::new(foo_key(0))).await;
+        assert!(matches!(res, Err(PbApiError::KeyError(_))), "{:?}", res);
+
+        Ok(())
+    }
+}
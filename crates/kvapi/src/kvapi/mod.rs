@@ -13,18 +13,24 @@
 // limitations under the License.
 
 mod api;
+mod dependency_registry;
+mod dependency_walker;
 mod dir_name;
 mod helper;
+mod integrity_checker;
 mod item;
 mod key;
 mod key_builder;
 mod key_codec;
 mod key_parser;
 mod kv_api_ext;
+mod kv_pb_api;
 mod list_options;
 mod message;
 mod pair;
+mod txn_builder;
 mod value;
+mod value_codec;
 mod value_with_name;
 
 pub(crate) mod testing;
@@ -34,7 +40,12 @@ pub use api::KVApi;
 pub use api::KVStream;
 pub use api::fail_fast;
 pub use api::limit_stream;
+pub use dependency_registry::DependencyRegistry;
+pub use dependency_walker::DependencyGraph;
+pub use dependency_walker::DependencyWalker;
 pub use dir_name::DirName;
+pub use integrity_checker::IntegrityChecker;
+pub use integrity_checker::IntegrityReport;
 pub use item::Item;
 pub use item::NonEmptyItem;
 pub use key::Key;
@@ -43,6 +54,10 @@ pub use key_builder::KeyBuilder;
 pub use key_codec::KeyCodec;
 pub use key_parser::KeyParser;
 pub use kv_api_ext::KvApiExt;
+pub use kv_pb_api::KvPbApi;
+pub use kv_pb_api::PbApiError;
+pub use kv_pb_api::UpsertPb;
+pub use kv_pb_api::decode_seqv;
 pub use list_options::ListOptions;
 pub use message::GetKVReply;
 pub use message::GetKVReq;
@@ -54,5 +69,19 @@ pub use message::UpsertKVReply;
 pub use pair::BasicPair;
 pub use pair::Pair;
 pub use pair::SeqPair;
+pub use txn_builder::BranchBuilder;
+pub use txn_builder::BranchHandle;
+pub use txn_builder::DeleteOp;
+pub use txn_builder::FetchIncreaseOp;
+pub use txn_builder::GetOp;
+pub use txn_builder::Predicate;
+pub use txn_builder::PutOp;
+pub use txn_builder::TxnBuilder;
+pub use txn_builder::TxnOpHandle;
+pub use txn_builder::TxnOpKind;
+pub use txn_builder::TxnResult;
+pub use txn_builder::TxnResultError;
 pub use value::Value;
+pub use value_codec::ValueCodec;
+pub use value_codec::ValueCodecError;
 pub use value_with_name::ValueWithName;
@@ -12,12 +12,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use databend_meta_types::Change;
+use databend_meta_types::MatchSeq;
+use databend_meta_types::Operation;
+use databend_meta_types::SeqV;
+use databend_meta_types::TxnReply;
+use databend_meta_types::TxnRequest;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::protobuf as pb;
+use databend_meta_types::protobuf::StreamItem;
+use futures_util::StreamExt;
+use futures_util::TryStreamExt;
+use futures_util::stream::BoxStream;
+
 use crate::kvapi;
+use crate::kvapi::KVApi;
+use crate::kvapi::KVStream;
 use crate::kvapi::Key;
 use crate::kvapi::KeyCodec;
 use crate::kvapi::KeyError;
 use crate::kvapi::KeyParser;
+use crate::kvapi::ListOptions;
 use crate::kvapi::Value;
+use crate::kvapi::ValueCodec;
+use crate::kvapi::ValueCodecError;
+use crate::kvapi::limit_stream;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -43,8 +67,8 @@ impl KeyCodec for FooKey {
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
-pub(crate) struct FooValue;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FooValue(pub(crate) String);
 
 impl Key for FooKey {
     const PREFIX: &'static str = "pref";
@@ -62,3 +86,247 @@ impl Value for FooValue {
         []
     }
 }
+
+impl ValueCodec for FooValue {
+    fn encode_value(&self) -> Result<Vec<u8>, ValueCodecError> {
+        Ok(self.0.as_bytes().to_vec())
+    }
+
+    fn decode_value(buf: &[u8]) -> Result<Self, ValueCodecError> {
+        let s = String::from_utf8(buf.to_vec()).map_err(ValueCodecError::new)?;
+        Ok(FooValue(s))
+    }
+}
+
+/// A name-to-id record that points to a [`MetaKey`] record: `name/<name> -> <id>`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NameKey {
+    pub(crate) name: String,
+}
+
+impl KeyCodec for NameKey {
+    fn encode_key(&self, b: kvapi::KeyBuilder) -> kvapi::KeyBuilder {
+        b.push_str(&self.name)
+    }
+
+    fn decode_key(parser: &mut KeyParser) -> Result<Self, KeyError>
+    where Self: Sized {
+        let name = parser.next_str()?;
+        Ok(NameKey { name })
+    }
+}
+
+impl Key for NameKey {
+    const PREFIX: &'static str = "name";
+    type ValueType = IdValue;
+
+    fn parent(&self) -> Option<String> {
+        None
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IdValue(pub(crate) u64);
+
+impl Value for IdValue {
+    type KeyType = NameKey;
+
+    fn dependency_keys(&self, _key: &Self::KeyType) -> impl IntoIterator<Item = String> {
+        [MetaKey { id: self.0 }.to_string_key()]
+    }
+}
+
+impl ValueCodec for IdValue {
+    fn encode_value(&self) -> Result<Vec<u8>, ValueCodecError> {
+        Ok(self.0.to_string().into_bytes())
+    }
+
+    fn decode_value(buf: &[u8]) -> Result<Self, ValueCodecError> {
+        let s = std::str::from_utf8(buf).map_err(ValueCodecError::new)?;
+        let id = s.parse().map_err(ValueCodecError::new)?;
+        Ok(IdValue(id))
+    }
+}
+
+/// A record that refers to other [`MetaKey`] records by id: `meta/<id> -> <id>,<id>...`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MetaKey {
+    pub(crate) id: u64,
+}
+
+impl KeyCodec for MetaKey {
+    fn encode_key(&self, b: kvapi::KeyBuilder) -> kvapi::KeyBuilder {
+        b.push_u64(self.id)
+    }
+
+    fn decode_key(parser: &mut KeyParser) -> Result<Self, KeyError>
+    where Self: Sized {
+        let id = parser.next_u64()?;
+        Ok(MetaKey { id })
+    }
+}
+
+impl Key for MetaKey {
+    const PREFIX: &'static str = "meta";
+    type ValueType = MetaValue;
+
+    fn parent(&self) -> Option<String> {
+        None
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MetaValue(pub(crate) Vec<u64>);
+
+impl Value for MetaValue {
+    type KeyType = MetaKey;
+
+    fn dependency_keys(&self, _key: &Self::KeyType) -> impl IntoIterator<Item = String> {
+        self.0
+            .iter()
+            .map(|id| MetaKey { id: *id }.to_string_key())
+            .collect::<Vec<_>>()
+    }
+}
+
+impl ValueCodec for MetaValue {
+    fn encode_value(&self) -> Result<Vec<u8>, ValueCodecError> {
+        let s = self
+            .0
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Ok(s.into_bytes())
+    }
+
+    fn decode_value(buf: &[u8]) -> Result<Self, ValueCodecError> {
+        let s = std::str::from_utf8(buf).map_err(ValueCodecError::new)?;
+        let ids = s
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse().map_err(ValueCodecError::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MetaValue(ids))
+    }
+}
+
+/// An in-memory [`KVApi`] for testing, without expiration and transaction support.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub(crate) struct MemKVApi {
+    inner: Mutex<MemKVApiInner>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+struct MemKVApiInner {
+    last_seq: u64,
+    kvs: BTreeMap<String, SeqV>,
+}
+
+#[allow(dead_code)]
+impl MemKVApi {
+    /// Create an instance with the given key-values, with seq starting from 1.
+    pub(crate) fn with_kvs(kvs: &[(&str, &str)]) -> Self {
+        let mut inner = MemKVApiInner::default();
+        for (k, v) in kvs {
+            inner.last_seq += 1;
+            let seqv = SeqV {
+                seq: inner.last_seq,
+                meta: None,
+                data: v.as_bytes().to_vec(),
+            };
+            inner.kvs.insert(k.to_string(), seqv);
+        }
+
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+}
+
+#[async_trait]
+impl KVApi for MemKVApi {
+    type Error = io::Error;
+
+    async fn upsert_kv(&self, req: UpsertKV) -> Result<Change<Vec<u8>>, Self::Error> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let prev = inner.kvs.get(&req.key).cloned();
+        let prev_seq = prev.as_ref().map_or(0, |x| x.seq);
+
+        let matched = match req.seq {
+            MatchSeq::Any => true,
+            MatchSeq::Exact(s) => prev_seq == s,
+            MatchSeq::GE(s) => prev_seq >= s,
+        };
+
+        if !matched {
+            return Ok(Change::new(prev.clone(), prev));
+        }
+
+        #[allow(deprecated)]
+        let result = match req.value {
+            Operation::Update(data) => {
+                inner.last_seq += 1;
+                let seqv = SeqV {
+                    seq: inner.last_seq,
+                    meta: None,
+                    data,
+                };
+                inner.kvs.insert(req.key, seqv.clone());
+                Some(seqv)
+            }
+            Operation::Delete => {
+                inner.kvs.remove(&req.key);
+                None
+            }
+            Operation::AsIs => prev.clone(),
+        };
+
+        Ok(Change::new(prev, result))
+    }
+
+    async fn get_many_kv(
+        &self,
+        keys: BoxStream<'static, Result<String, Self::Error>>,
+    ) -> Result<KVStream<Self::Error>, Self::Error> {
+        let kvs = self.inner.lock().unwrap().kvs.clone();
+
+        let strm = keys.map_ok(move |k| {
+            let v = kvs.get(&k).cloned().map(pb::SeqV::from);
+            StreamItem::new(k, v)
+        });
+
+        Ok(strm.boxed())
+    }
+
+    async fn list_kv(
+        &self,
+        opts: ListOptions<'_, str>,
+    ) -> Result<KVStream<Self::Error>, Self::Error> {
+        let items = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .kvs
+                .range(opts.prefix.to_string()..)
+                .take_while(|(k, _)| k.starts_with(opts.prefix))
+                .map(|(k, v)| Ok(StreamItem::new(k.clone(), Some(pb::SeqV::from(v.clone())))))
+                .collect::<Vec<_>>()
+        };
+
+        Ok(limit_stream(futures_util::stream::iter(items), opts.limit))
+    }
+
+    async fn transaction(&self, _txn: TxnRequest) -> Result<TxnReply, Self::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "MemKVApi does not support transaction",
+        ))
+    }
+}
@@ -0,0 +1,614 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A fluent builder of [`pb::TxnRequest`] that returns typed handles to decode the responses.
+//!
+//! ```ignore
+//! let mut txn = TxnBuilder::new();
+//!
+//! let mut b = txn.branch().if_absent("a").if_prefix_count("b/", 0);
+//! let put = b.put("a", b"1".to_vec());
+//!
+//! let mut fallback = txn.branch();
+//! let get = fallback.get("a");
+//!
+//! let res = txn.execute(&kv).await?;
+//! if let Some(change) = res.get(&put)? { /* the first branch is executed */ }
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use databend_meta_types::Change;
+use databend_meta_types::ConditionResult;
+use databend_meta_types::SeqV;
+use databend_meta_types::protobuf as pb;
+use databend_meta_types::protobuf::boolean_expression::CombiningOperator;
+use databend_meta_types::txn_op_response::Response;
+
+use crate::kvapi;
+
+/// A condition tree of a transaction branch, with nested `AND` and `OR`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Condition(pb::TxnCondition),
+    And(Vec<Predicate>),
+    Or(Vec<Predicate>),
+}
+
+impl From<pb::TxnCondition> for Predicate {
+    fn from(cond: pb::TxnCondition) -> Self {
+        Predicate::Condition(cond)
+    }
+}
+
+impl Predicate {
+    /// The `seq` of `key` equals `seq`.
+    pub fn seq_eq(key: impl ToString, seq: u64) -> Self {
+        pb::TxnCondition::eq_seq(key, seq).into()
+    }
+
+    /// The `key` does not exist.
+    pub fn absent(key: impl ToString) -> Self {
+        Self::seq_eq(key, 0)
+    }
+
+    /// The `key` exists.
+    pub fn exists(key: impl ToString) -> Self {
+        pb::TxnCondition::match_seq(key, ConditionResult::Gt, 0).into()
+    }
+
+    /// The value of `key` equals `value`.
+    pub fn value_eq(key: impl ToString, value: Vec<u8>) -> Self {
+        pb::TxnCondition::eq_value(key, value).into()
+    }
+
+    /// The number of keys starting with `prefix` equals `count`.
+    pub fn prefix_count(prefix: impl ToString, count: u64) -> Self {
+        pb::TxnCondition::keys_with_prefix(prefix, count).into()
+    }
+
+    /// Combine with `other` so that both must be met.
+    pub fn and(self, other: impl Into<Predicate>) -> Self {
+        match self {
+            Predicate::And(mut ps) => {
+                ps.push(other.into());
+                Predicate::And(ps)
+            }
+            p => Predicate::And(vec![p, other.into()]),
+        }
+    }
+
+    /// Combine with `other` so that either one must be met.
+    pub fn or(self, other: impl Into<Predicate>) -> Self {
+        match self {
+            Predicate::Or(mut ps) => {
+                ps.push(other.into());
+                Predicate::Or(ps)
+            }
+            p => Predicate::Or(vec![p, other.into()]),
+        }
+    }
+
+    pub fn into_expression(self) -> pb::BooleanExpression {
+        match self {
+            Predicate::Condition(cond) => pb::BooleanExpression::from_conditions_and([cond]),
+            Predicate::And(ps) => Self::combine(CombiningOperator::And, ps),
+            Predicate::Or(ps) => Self::combine(CombiningOperator::Or, ps),
+        }
+    }
+
+    fn combine(op: CombiningOperator, predicates: Vec<Predicate>) -> pb::BooleanExpression {
+        let mut expr = pb::BooleanExpression {
+            conditions: vec![],
+            operator: op as i32,
+            sub_expressions: vec![],
+        };
+
+        for p in predicates {
+            match p {
+                Predicate::Condition(cond) => expr.conditions.push(cond),
+                p => expr.sub_expressions.push(p.into_expression()),
+            }
+        }
+
+        expr
+    }
+}
+
+/// The kind of a transaction operation, which defines how its response is decoded.
+pub trait TxnOpKind {
+    /// The name of the expected response, for error messages.
+    const NAME: &'static str;
+
+    type Output;
+
+    /// Decode the response, or return it back if it is not of this kind.
+    fn decode(response: Response) -> Result<Self::Output, Response>;
+}
+
+/// A `get` operation, decoded into the current value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GetOp;
+
+impl TxnOpKind for GetOp {
+    const NAME: &'static str = "Get";
+    type Output = Option<SeqV>;
+
+    fn decode(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::Get(r) => Ok(r.value.map(SeqV::from)),
+            r => Err(r),
+        }
+    }
+}
+
+/// A `put` operation, decoded into the value before and after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PutOp;
+
+impl TxnOpKind for PutOp {
+    const NAME: &'static str = "Put";
+    type Output = Change<Vec<u8>>;
+
+    fn decode(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::Put(r) => {
+                let (_key, prev, current) = r.unpack();
+                Ok(Change::new(prev, current))
+            }
+            r => Err(r),
+        }
+    }
+}
+
+/// A `delete` operation, decoded into the value before and after it.
+///
+/// The value is unchanged if the delete did not take place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeleteOp;
+
+impl TxnOpKind for DeleteOp {
+    const NAME: &'static str = "Delete";
+    type Output = Change<Vec<u8>>;
+
+    fn decode(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::Delete(r) => {
+                let prev = r.prev_value.map(SeqV::from);
+                if r.success {
+                    Ok(Change::new(prev, None))
+                } else {
+                    Ok(Change::new(prev.clone(), prev))
+                }
+            }
+            r => Err(r),
+        }
+    }
+}
+
+/// A `fetch_increase_u64` operation, decoded into the value before and after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchIncreaseOp;
+
+impl TxnOpKind for FetchIncreaseOp {
+    const NAME: &'static str = "FetchIncreaseU64";
+    type Output = pb::FetchIncreaseU64Response;
+
+    fn decode(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::FetchIncreaseU64(r) => Ok(r),
+            r => Err(r),
+        }
+    }
+}
+
+/// Refers to a branch added by [`TxnBuilder::branch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchHandle {
+    index: usize,
+}
+
+impl BranchHandle {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The `execution_path` in the [`pb::TxnReply`] if this branch is executed.
+    pub fn path(&self) -> String {
+        format!("operation:{}", self.index)
+    }
+}
+
+/// Refers to an operation in a branch, to decode its response from a [`TxnResult`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxnOpHandle<K: TxnOpKind> {
+    branch: BranchHandle,
+    index: usize,
+    key: String,
+    _p: PhantomData<K>,
+}
+
+impl<K: TxnOpKind> TxnOpHandle<K> {
+    pub fn branch(&self) -> BranchHandle {
+        self.branch
+    }
+
+    /// The position of this operation in its branch.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl<K: TxnOpKind> fmt::Display for TxnOpHandle<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}({})",
+            self.branch.path(),
+            self.index,
+            K::NAME,
+            self.key
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Branch {
+    predicate: Option<Predicate>,
+    ops: Vec<pb::TxnOp>,
+}
+
+/// Build a [`pb::TxnRequest`] of conditional branches.
+///
+/// Branches are evaluated in the order they are added,
+/// and only the first one whose predicate is met is executed.
+/// A branch without any predicate always matches,
+/// thus it should be the last one, as a fallback.
+/// If no branch matches, no operation is executed.
+#[derive(Debug, Clone, Default)]
+pub struct TxnBuilder {
+    branches: Vec<Branch>,
+}
+
+impl TxnBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a branch and return a builder to add predicates and operations to it.
+    pub fn branch(&mut self) -> BranchBuilder<'_> {
+        let index = self.branches.len();
+        self.branches.push(Branch::default());
+        BranchBuilder { txn: self, index }
+    }
+
+    pub fn build(self) -> pb::TxnRequest {
+        let operations = self
+            .branches
+            .into_iter()
+            .map(|b| {
+                pb::ConditionalOperation::new(b.predicate.map(Predicate::into_expression), b.ops)
+            })
+            .collect();
+
+        pb::TxnRequest {
+            operations,
+            condition: vec![],
+            if_then: vec![],
+            else_then: vec![],
+        }
+    }
+
+    /// Build the request, run it with `kv` and return the result for decoding.
+    pub async fn execute<KV>(self, kv: &KV) -> Result<TxnResult, KV::Error>
+    where KV: kvapi::KVApi + ?Sized {
+        let reply = kv.transaction(self.build()).await?;
+        Ok(TxnResult::new(reply))
+    }
+}
+
+/// Adds predicates and operations to a branch of a [`TxnBuilder`].
+///
+/// Predicates added to a branch are combined with `AND`.
+pub struct BranchBuilder<'a> {
+    txn: &'a mut TxnBuilder,
+    index: usize,
+}
+
+impl BranchBuilder<'_> {
+    pub fn handle(&self) -> BranchHandle {
+        BranchHandle { index: self.index }
+    }
+
+    /// Require the `seq` of `key` to equal `seq`.
+    pub fn if_seq_eq(self, key: impl ToString, seq: u64) -> Self {
+        self.when(Predicate::seq_eq(key, seq))
+    }
+
+    /// Require `key` to be absent.
+    pub fn if_absent(self, key: impl ToString) -> Self {
+        self.when(Predicate::absent(key))
+    }
+
+    /// Require the number of keys starting with `prefix` to equal `count`.
+    pub fn if_prefix_count(self, prefix: impl ToString, count: u64) -> Self {
+        self.when(Predicate::prefix_count(prefix, count))
+    }
+
+    /// Require `predicate` to be met, e.g., a nested `OR` of several conditions.
+    pub fn when(mut self, predicate: impl Into<Predicate>) -> Self {
+        let branch = &mut self.txn.branches[self.index];
+        branch.predicate = Some(match branch.predicate.take() {
+            None => predicate.into(),
+            Some(p) => p.and(predicate),
+        });
+        self
+    }
+
+    pub fn get(&mut self, key: impl ToString) -> TxnOpHandle<GetOp> {
+        self.push(key, pb::TxnOp::get)
+    }
+
+    pub fn put(&mut self, key: impl ToString, value: Vec<u8>) -> TxnOpHandle<PutOp> {
+        self.push(key, |k| pb::TxnOp::put(k, value))
+    }
+
+    pub fn put_with_ttl(
+        &mut self,
+        key: impl ToString,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> TxnOpHandle<PutOp> {
+        self.push(key, |k| pb::TxnOp::put_with_ttl(k, value, ttl))
+    }
+
+    pub fn delete(&mut self, key: impl ToString) -> TxnOpHandle<DeleteOp> {
+        self.push(key, pb::TxnOp::delete)
+    }
+
+    /// Set the value of `key` to `max(current, max_value) + delta`.
+    pub fn fetch_increase(
+        &mut self,
+        key: impl ToString,
+        max_value: u64,
+        delta: i64,
+    ) -> TxnOpHandle<FetchIncreaseOp> {
+        self.push(key, |k| pb::TxnOp::fetch_increase_u64(k, max_value, delta))
+    }
+
+    fn push<K: TxnOpKind>(
+        &mut self,
+        key: impl ToString,
+        build_op: impl FnOnce(String) -> pb::TxnOp,
+    ) -> TxnOpHandle<K> {
+        let key = key.to_string();
+        let branch = self.handle();
+        let ops = &mut self.txn.branches[self.index].ops;
+
+        let handle = TxnOpHandle {
+            branch,
+            index: ops.len(),
+            key: key.clone(),
+            _p: PhantomData,
+        };
+
+        ops.push(build_op(key));
+        handle
+    }
+}
+
+/// Error occurs when the [`pb::TxnReply`] does not match the operations of an executed branch.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TxnResultError {
+    #[error("no response for {op}")]
+    MissingResponse { op: String },
+
+    #[error("unexpected response for {op}: {got}")]
+    UnexpectedResponse { op: String, got: String },
+}
+
+/// The reply of a transaction built with [`TxnBuilder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxnResult {
+    reply: pb::TxnReply,
+}
+
+impl TxnResult {
+    pub fn new(reply: pb::TxnReply) -> Self {
+        Self { reply }
+    }
+
+    pub fn reply(&self) -> &pb::TxnReply {
+        &self.reply
+    }
+
+    pub fn into_reply(self) -> pb::TxnReply {
+        self.reply
+    }
+
+    /// Returns the index of the executed branch, or `None` if no branch is executed.
+    pub fn executed_branch(&self) -> Option<usize> {
+        self.reply.executed_branch_index().ok().flatten()
+    }
+
+    pub fn is_executed(&self, branch: &BranchHandle) -> bool {
+        self.executed_branch() == Some(branch.index)
+    }
+
+    /// Decode the response of the operation referred to by `handle`.
+    ///
+    /// It returns `None` if the branch of the operation is not executed.
+    pub fn get<K: TxnOpKind>(
+        &self,
+        handle: &TxnOpHandle<K>,
+    ) -> Result<Option<K::Output>, TxnResultError> {
+        if !self.is_executed(&handle.branch) {
+            return Ok(None);
+        }
+
+        let response = self
+            .reply
+            .responses
+            .get(handle.index)
+            .and_then(|r| r.response.clone())
+            .ok_or_else(|| TxnResultError::MissingResponse {
+                op: handle.to_string(),
+            })?;
+
+        let output = K::decode(response).map_err(|got| TxnResultError::UnexpectedResponse {
+            op: handle.to_string(),
+            got: got.to_string(),
+        })?;
+
+        Ok(Some(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predicate_into_expression() {
+        let p = Predicate::seq_eq("a", 1)
+            .and(Predicate::absent("b").or(Predicate::prefix_count("c/", 2)))
+            .and(Predicate::exists("d"));
+
+        let want = pb::BooleanExpression {
+            conditions: vec![
+                pb::TxnCondition::eq_seq("a", 1),
+                pb::TxnCondition::match_seq("d", ConditionResult::Gt, 0),
+            ],
+            operator: CombiningOperator::And as i32,
+            sub_expressions: vec![pb::BooleanExpression::from_conditions_or([
+                pb::TxnCondition::eq_seq("b", 0),
+                pb::TxnCondition::keys_with_prefix("c/", 2),
+            ])],
+        };
+
+        assert_eq!(want, p.into_expression());
+    }
+
+    #[test]
+    fn test_build() {
+        let mut txn = TxnBuilder::new();
+
+        let mut b = txn.branch().if_seq_eq("a", 1).if_absent("b");
+        let put = b.put("b", b"x".to_vec());
+        let get = b.get("a");
+
+        let mut b = txn.branch();
+        let incr = b.fetch_increase("c", 0, 1);
+
+        assert_eq!("operation:0[0]: Put(b)", put.to_string());
+        assert_eq!("operation:0[1]: Get(a)", get.to_string());
+        assert_eq!("operation:1[0]: FetchIncreaseU64(c)", incr.to_string());
+
+        let req = txn.build();
+
+        let want = pb::TxnRequest {
+            operations: vec![
+                pb::ConditionalOperation::new(
+                    Some(pb::BooleanExpression::from_conditions_and([
+                        pb::TxnCondition::eq_seq("a", 1),
+                        pb::TxnCondition::eq_seq("b", 0),
+                    ])),
+                    vec![pb::TxnOp::put("b", b"x".to_vec()), pb::TxnOp::get("a")],
+                ),
+                pb::ConditionalOperation::new(None, vec![pb::TxnOp::fetch_increase_u64("c", 0, 1)]),
+            ],
+            condition: vec![],
+            if_then: vec![],
+            else_then: vec![],
+        };
+
+        assert_eq!(want, req);
+    }
+
+    #[test]
+    fn test_decode_result() -> anyhow::Result<()> {
+        let mut txn = TxnBuilder::new();
+
+        let mut b = txn.branch().if_absent("a");
+        let b0 = b.handle();
+        let put = b.put("a", b"x".to_vec());
+
+        let mut b = txn.branch();
+        let b1 = b.handle();
+        let get = b.get("a");
+        let del = b.delete("b");
+
+        // The second branch is executed.
+
+        let mut reply = pb::TxnReply::new("operation:1");
+        reply.responses = vec![
+            pb::TxnOpResponse::get("a", Some(SeqV::new(3, b"y".to_vec()))),
+            pb::TxnOpResponse::delete("b", true, Some(pb::SeqV::new(2, b"z".to_vec()))),
+        ];
+        let res = TxnResult::new(reply);
+
+        assert_eq!(Some(1), res.executed_branch());
+        assert!(!res.is_executed(&b0));
+        assert!(res.is_executed(&b1));
+
+        assert_eq!(None, res.get(&put)?);
+        assert_eq!(Some(Some(SeqV::new(3, b"y".to_vec()))), res.get(&get)?);
+        assert_eq!(
+            Some(Change::new(Some(SeqV::new(2, b"z".to_vec())), None)),
+            res.get(&del)?
+        );
+
+        // No branch is executed.
+
+        let res = TxnResult::new(pb::TxnReply::new("then"));
+        assert_eq!(None, res.executed_branch());
+        assert_eq!(None, res.get(&put)?);
+        assert_eq!(None, res.get(&get)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_result_error() {
+        let mut txn = TxnBuilder::new();
+
+        let mut b = txn.branch();
+        let get = b.get("a");
+        let put = b.put("a", b"x".to_vec());
+
+        let mut reply = pb::TxnReply::new("operation:0");
+        reply.responses = vec![pb::TxnOpResponse::get("a", None)];
+        let res = TxnResult::new(reply);
+
+        assert_eq!(Some(None), res.get(&get).unwrap());
+        assert_eq!(
+            TxnResultError::MissingResponse {
+                op: "operation:0[1]: Put(a)".to_string()
+            },
+            res.get(&put).unwrap_err()
+        );
+
+        // Decode the `Get` response as a `Put`.
+
+        let handle = TxnOpHandle::<PutOp> { index: 0, ..put };
+        let err = res.get(&handle).unwrap_err();
+        assert!(matches!(err, TxnResultError::UnexpectedResponse { .. }));
+    }
+}
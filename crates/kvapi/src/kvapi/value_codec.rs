@@ -0,0 +1,47 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::kvapi;
+
+/// Error occurs when encoding or decoding a value with [`ValueCodec`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid value: {reason}")]
+pub struct ValueCodecError {
+    reason: String,
+}
+
+impl ValueCodecError {
+    pub fn new(reason: impl ToString) -> Self {
+        Self {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// Converts a [`kvapi::Value`] to and from the bytes stored in meta-service.
+///
+/// It is usually implemented by encoding the value into protobuf.
+pub trait ValueCodec: kvapi::Value
+where Self: Sized
+{
+    /// Encode the value into bytes.
+    fn encode_value(&self) -> Result<Vec<u8>, ValueCodecError>;
+
+    /// Decode bytes into a value.
+    fn decode_value(buf: &[u8]) -> Result<Self, ValueCodecError>;
+}
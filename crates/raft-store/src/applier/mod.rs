@@ -67,9 +67,14 @@ use num::FromPrimitive;
 use seq_marked::SeqValue;
 use state_machine_api::StateMachineApi;
 
+use crate::StateMachineFeature;
 use crate::state_machine_api_ext::StateMachineApiExt;
 
 pub(crate) mod applier_data;
+mod prefix_quota;
+
+#[cfg(test)]
+mod prefix_quota_test;
 
 /// Threshold in milliseconds for logging slow log entry application
 const SLOW_LOG_ENTRY_THRESHOLD_MS: u128 = 100;
@@ -219,7 +224,9 @@ where SM: StateMachineApi<SysData> + 'static
 
             Cmd::RemoveNode { node_id } => self.apply_remove_node(node_id),
 
-            Cmd::SetFeature { feature, enable } => self.apply_set_feature(feature, *enable),
+            Cmd::SetFeature { feature, enable } => {
+                self.apply_set_feature(feature, *enable).await?
+            }
 
             Cmd::UpsertKV(upsert_kv) => self.apply_upsert_kv(upsert_kv).await?,
 
@@ -277,6 +284,10 @@ where SM: StateMachineApi<SysData> + 'static
     async fn apply_upsert_kv(&mut self, upsert_kv: &UpsertKV) -> Result<AppliedState, io::Error> {
         debug!("apply_update_kv_cmd {}: {}", self.cmd_ctx, upsert_kv);
 
+        if let Some(e) = self.check_upsert_quota(upsert_kv).await? {
+            return Ok(AppliedState::QuotaExceeded(e));
+        }
+
         let (prev, result) = self.upsert_kv(upsert_kv).await?;
 
         let st = Change::new(prev, result).into();
@@ -285,7 +296,11 @@ where SM: StateMachineApi<SysData> + 'static
 
     /// Toggle a state machine functional feature.
     #[fastrace::trace]
-    fn apply_set_feature(&mut self, feature: &str, enable: bool) -> AppliedState {
+    async fn apply_set_feature(
+        &mut self,
+        feature: &str,
+        enable: bool,
+    ) -> Result<AppliedState, io::Error> {
         let prev = self.sm.with_sys_data(|sys_data| {
             let prev = sys_data.feature_enabled(feature);
 
@@ -298,12 +313,21 @@ where SM: StateMachineApi<SysData> + 'static
             prev
         });
 
+        if feature == StateMachineFeature::PrefixQuota.to_string() && prev != enable {
+            if enable {
+                self.load_quotas().await?;
+            } else {
+                self.sm
+                    .with_sys_data(|sys_data| sys_data.prefix_quotas_mut().clear());
+            }
+        }
+
         info!(
             "apply_set_feature done: {} from {} to {}",
             feature, prev, enable
         );
 
-        AppliedState::None
+        Ok(AppliedState::None)
     }
 
     /// Update or insert a kv entry.
@@ -328,6 +352,8 @@ where SM: StateMachineApi<SysData> + 'static
         let prev = Into::<Option<SeqV>>::into(prev);
         let result = Into::<Option<SeqV>>::into(result);
 
+        self.update_quotas(&upsert_kv.key, &prev, &result).await?;
+
         debug!(
             "applied UpsertKV {}: {}; prev: {:?}; result: {:?}",
             self.cmd_ctx, upsert_kv, prev, result
@@ -353,6 +379,10 @@ where SM: StateMachineApi<SysData> + 'static
             };
 
             if success {
+                if let Some(e) = self.check_txn_quota(&conditional.operations).await? {
+                    return Ok(AppliedState::QuotaExceeded(e));
+                }
+
                 let mut resp: TxnReply = TxnReply::new(format!("operation:{i}"));
 
                 for op in &conditional.operations {
@@ -377,6 +407,10 @@ where SM: StateMachineApi<SysData> + 'static
             (&req.else_then, "else")
         };
 
+        if let Some(e) = self.check_txn_quota(ops).await? {
+            return Ok(AppliedState::QuotaExceeded(e));
+        }
+
         let mut resp: TxnReply = TxnReply::new(path);
 
         for op in ops {
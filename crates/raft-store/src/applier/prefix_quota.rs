@@ -0,0 +1,298 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Maintain and enforce storage quotas of key prefixes.
+//!
+//! Quotas take effect only when [`StateMachineFeature::PrefixQuota`] is enabled,
+//! so that every node in the cluster applies a write with the same result.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use databend_meta_types::Operation;
+use databend_meta_types::QuotaExceeded;
+use databend_meta_types::SeqV;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::prefix_quota::PrefixQuota;
+use databend_meta_types::prefix_quota::PrefixUsage;
+use databend_meta_types::prefix_quota::QUOTA_KEY_PREFIX;
+use databend_meta_types::prefix_quota::QuotaState;
+use databend_meta_types::prefix_quota::parse_quota_key;
+use databend_meta_types::protobuf as pb;
+use databend_meta_types::sys_data::SysData;
+use databend_meta_types::txn_op::Request;
+use futures::stream::TryStreamExt;
+use log::info;
+use log::warn;
+use state_machine_api::StateMachineApi;
+
+use crate::StateMachineFeature;
+use crate::applier::Applier;
+
+/// Max length of a u64 encoded in JSON, the value written by `FetchIncreaseU64`.
+const U64_JSON_LEN: u64 = 20;
+
+/// A write to check against quotas before it is applied.
+struct QuotaWrite {
+    key: String,
+    value_size: u64,
+    /// The key is known to be absent, there is no need to read the previous value.
+    new_key: bool,
+}
+
+impl QuotaWrite {
+    fn new(key: impl ToString, value_size: u64) -> Self {
+        Self {
+            key: key.to_string(),
+            value_size,
+            new_key: false,
+        }
+    }
+}
+
+impl<SM> Applier<SM>
+where SM: StateMachineApi<SysData> + 'static
+{
+    /// Check if an `UpsertKV` would exceed a quota.
+    pub(crate) async fn check_upsert_quota(
+        &self,
+        upsert_kv: &UpsertKV,
+    ) -> Result<Option<QuotaExceeded>, io::Error> {
+        let Operation::Update(value) = &upsert_kv.value else {
+            return Ok(None);
+        };
+
+        let writes = vec![QuotaWrite::new(&upsert_kv.key, value.len() as u64)];
+        self.check_quota(writes).await
+    }
+
+    /// Check if executing the operations of a transaction would exceed a quota.
+    pub(crate) async fn check_txn_quota(
+        &self,
+        ops: &[pb::TxnOp],
+    ) -> Result<Option<QuotaExceeded>, io::Error> {
+        let mut writes = vec![];
+
+        for op in ops {
+            let Some(request) = &op.request else {
+                continue;
+            };
+
+            match request {
+                Request::Put(put) => {
+                    writes.push(QuotaWrite::new(&put.key, put.value.len() as u64));
+                }
+                Request::FetchIncreaseU64(f) => {
+                    writes.push(QuotaWrite::new(&f.key, U64_JSON_LEN));
+                }
+                Request::PutSequential(p) => {
+                    writes.push(QuotaWrite::new(&p.sequence_key, U64_JSON_LEN));
+                    // The sequence number is unknown before applying,
+                    // but the length of the key is fixed.
+                    writes.push(QuotaWrite {
+                        key: p.build_key(0),
+                        value_size: p.value.len() as u64,
+                        new_key: true,
+                    });
+                }
+                Request::Get(_) | Request::Delete(_) | Request::DeleteByPrefix(_) => {}
+            }
+        }
+
+        self.check_quota(writes).await
+    }
+
+    /// Check if applying `writes` would exceed a quota.
+    ///
+    /// The check is conservative:
+    /// deletions in the same command are not credited,
+    /// and repeated writes to the same key are counted more than once.
+    async fn check_quota(
+        &self,
+        writes: Vec<QuotaWrite>,
+    ) -> Result<Option<QuotaExceeded>, io::Error> {
+        let quotas = self.sm.with_sys_data(|s| s.prefix_quotas().clone());
+        if quotas.is_empty() {
+            return Ok(None);
+        }
+
+        let mut growth = BTreeMap::<&str, PrefixUsage>::new();
+
+        for w in writes.iter() {
+            if w.key.starts_with(QUOTA_KEY_PREFIX) {
+                continue;
+            }
+
+            let matched = quotas
+                .iter()
+                .filter(|(prefix, _)| w.key.starts_with(prefix.as_str()))
+                .collect::<Vec<_>>();
+
+            if matched.is_empty() {
+                continue;
+            }
+
+            let prev = if w.new_key {
+                None
+            } else {
+                self.get_maybe_expired_kv_with_timing(&w.key).await?
+            };
+
+            let size = w.key.len() as u64 + w.value_size;
+            let (keys, bytes) = match prev {
+                None => (1, size),
+                Some(seqv) => (
+                    0,
+                    size.saturating_sub(PrefixUsage::entry_size(&w.key, &seqv.data)),
+                ),
+            };
+
+            for (prefix, state) in matched {
+                let g = growth.entry(prefix.as_str()).or_default();
+                g.add(keys, bytes);
+
+                if state.quota.exceeded_by(&state.usage, g) {
+                    let err = QuotaExceeded::new(prefix, &w.key, state.quota.clone(), state.usage);
+                    warn!("{}; growth by this command: {}", err, g);
+                    return Ok(Some(err));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Update quotas after `key` is changed from `prev` to `result`.
+    ///
+    /// A change to a quota definition key updates the quota,
+    /// and a change to any other key updates the usage of every quota whose prefix covers it.
+    pub(crate) async fn update_quotas(
+        &mut self,
+        key: &str,
+        prev: &Option<SeqV>,
+        result: &Option<SeqV>,
+    ) -> Result<(), io::Error> {
+        if let Some(prefix) = parse_quota_key(key) {
+            return self.update_quota_definition(prefix, result).await;
+        }
+
+        let before = prev.as_ref().map(|v| PrefixUsage::entry_size(key, &v.data));
+        let after = result.as_ref().map(|v| PrefixUsage::entry_size(key, &v.data));
+
+        if before == after {
+            return Ok(());
+        }
+
+        self.sm.with_sys_data(|s| {
+            for (prefix, state) in s.prefix_quotas_mut().iter_mut() {
+                if !key.starts_with(prefix.as_str()) {
+                    continue;
+                }
+
+                if let Some(size) = before {
+                    state.usage.sub(1, size);
+                }
+                if let Some(size) = after {
+                    state.usage.add(1, size);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Load all quota definitions and compute their usage, when the quota feature is enabled.
+    pub(crate) async fn load_quotas(&mut self) -> Result<(), io::Error> {
+        let defs = {
+            let strm = self.list_kv_with_timing(QUOTA_KEY_PREFIX).await?;
+            strm.try_collect::<Vec<_>>().await?
+        };
+
+        for (key, seqv) in defs {
+            if let Some(prefix) = parse_quota_key(&key) {
+                self.update_quota_definition(prefix, &Some(seqv)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add, update or remove the quota of `prefix` according to the value of its definition key.
+    async fn update_quota_definition(
+        &mut self,
+        prefix: &str,
+        value: &Option<SeqV>,
+    ) -> Result<(), io::Error> {
+        let feature = StateMachineFeature::PrefixQuota.to_string();
+        if !self.sm.with_sys_data(|s| s.feature_enabled(&feature)) {
+            return Ok(());
+        }
+
+        let quota = match value {
+            None => None,
+            Some(seqv) => match serde_json::from_slice::<PrefixQuota>(&seqv.data) {
+                Ok(q) => Some(q),
+                Err(e) => {
+                    warn!(
+                        "invalid quota definition of prefix '{}', ignored: {}",
+                        prefix, e
+                    );
+                    None
+                }
+            },
+        };
+
+        let Some(quota) = quota else {
+            self.sm
+                .with_sys_data(|s| s.prefix_quotas_mut().remove(prefix));
+            info!("quota removed: prefix: '{}'", prefix);
+            return Ok(());
+        };
+
+        let usage = self
+            .sm
+            .with_sys_data(|s| s.prefix_quotas().get(prefix).map(|x| x.usage));
+
+        let usage = match usage {
+            Some(u) => u,
+            None => self.scan_prefix_usage(prefix).await?,
+        };
+
+        info!(
+            "quota set: prefix: '{}', quota: {}, usage: {}",
+            prefix, quota, usage
+        );
+
+        self.sm.with_sys_data(|s| {
+            s.prefix_quotas_mut()
+                .insert(prefix.to_string(), QuotaState::new(quota, usage))
+        });
+
+        Ok(())
+    }
+
+    async fn scan_prefix_usage(&self, prefix: &str) -> Result<PrefixUsage, io::Error> {
+        let mut strm = self.list_kv_with_timing(prefix).await?;
+
+        let mut usage = PrefixUsage::default();
+        while let Some((key, seqv)) = strm.try_next().await? {
+            if key.starts_with(QUOTA_KEY_PREFIX) {
+                continue;
+            }
+            usage.add(1, PrefixUsage::entry_size(&key, &seqv.data));
+        }
+
+        Ok(usage)
+    }
+}
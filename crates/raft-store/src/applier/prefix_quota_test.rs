@@ -0,0 +1,172 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use databend_meta_types::AppliedState;
+use databend_meta_types::Cmd;
+use databend_meta_types::TxnOp;
+use databend_meta_types::TxnRequest;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::prefix_quota::PrefixQuota;
+use databend_meta_types::prefix_quota::PrefixUsage;
+use databend_meta_types::prefix_quota::quota_key;
+
+use crate::StateMachineFeature;
+use crate::sm_v003::SMV003;
+
+fn set_feature(enable: bool) -> Cmd {
+    Cmd::SetFeature {
+        feature: StateMachineFeature::PrefixQuota.to_string(),
+        enable,
+    }
+}
+
+fn upsert(key: &str, value: &str) -> Cmd {
+    Cmd::UpsertKV(UpsertKV::update(key, value.as_bytes()))
+}
+
+fn define_quota(prefix: &str, quota: PrefixQuota) -> Cmd {
+    let value = serde_json::to_string(&quota).unwrap();
+    upsert(&quota_key(prefix), &value)
+}
+
+fn usage(sm: &SMV003, prefix: &str) -> Option<PrefixUsage> {
+    sm.sys_data().prefix_quotas().get(prefix).map(|x| x.usage)
+}
+
+fn is_quota_exceeded(st: &AppliedState) -> bool {
+    matches!(st, AppliedState::QuotaExceeded(_))
+}
+
+#[tokio::test]
+async fn test_prefix_quota_requires_feature() -> anyhow::Result<()> {
+    let sm = SMV003::default();
+    let mut a = sm.new_applier().await;
+
+    a.apply_cmd(&define_quota("t/", PrefixQuota::new(Some(0), None)))
+        .await?;
+    let st = a.apply_cmd(&upsert("t/a", "1")).await?;
+    assert!(!is_quota_exceeded(&st));
+    a.commit().await?;
+
+    assert!(sm.sys_data().prefix_quotas().is_empty());
+
+    // Enabling the feature loads the existing definition and counts the existing keys.
+    let mut a = sm.new_applier().await;
+    a.apply_cmd(&set_feature(true)).await?;
+    a.commit().await?;
+
+    assert_eq!(Some(PrefixUsage::new(1, 4)), usage(&sm, "t/"));
+
+    // Disabling the feature drops all quotas.
+    let mut a = sm.new_applier().await;
+    a.apply_cmd(&set_feature(false)).await?;
+    let st = a.apply_cmd(&upsert("t/b", "1")).await?;
+    assert!(!is_quota_exceeded(&st));
+    a.commit().await?;
+
+    assert!(sm.sys_data().prefix_quotas().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prefix_quota_max_keys() -> anyhow::Result<()> {
+    let sm = SMV003::default();
+    let mut a = sm.new_applier().await;
+
+    a.apply_cmd(&set_feature(true)).await?;
+    a.apply_cmd(&upsert("t/a", "1")).await?;
+    a.apply_cmd(&upsert("u/a", "1")).await?;
+    a.apply_cmd(&define_quota("t/", PrefixQuota::new(Some(2), None)))
+        .await?;
+    a.commit().await?;
+
+    assert_eq!(Some(PrefixUsage::new(1, 4)), usage(&sm, "t/"));
+
+    let mut a = sm.new_applier().await;
+
+    let st = a.apply_cmd(&upsert("t/b", "22")).await?;
+    assert!(!is_quota_exceeded(&st));
+
+    // The third key is rejected and not written
+    let st = a.apply_cmd(&upsert("t/c", "1")).await?;
+    let AppliedState::QuotaExceeded(e) = st else {
+        panic!("expect QuotaExceeded, got: {}", st);
+    };
+    assert_eq!("t/", e.prefix);
+    assert_eq!("t/c", e.key);
+    assert_eq!(PrefixUsage::new(2, 9), e.usage);
+    assert_eq!(None, a.get_maybe_expired_kv_with_timing("t/c").await?);
+
+    // Updating an existing key and writing to other prefix are allowed.
+    let st = a.apply_cmd(&upsert("t/a", "11")).await?;
+    assert!(!is_quota_exceeded(&st));
+    let st = a.apply_cmd(&upsert("u/b", "1")).await?;
+    assert!(!is_quota_exceeded(&st));
+
+    // Transaction is rejected as a whole
+    let txn = TxnRequest::unconditional(vec![
+        TxnOp::put("u/c", b"1".to_vec()),
+        TxnOp::put("t/c", b"1".to_vec()),
+    ]);
+    let st = a.apply_cmd(&Cmd::Transaction(txn)).await?;
+    assert!(is_quota_exceeded(&st));
+    assert_eq!(None, a.get_maybe_expired_kv_with_timing("u/c").await?);
+
+    // Deleting frees space
+    a.apply_cmd(&Cmd::UpsertKV(UpsertKV::delete("t/a"))).await?;
+    let st = a.apply_cmd(&upsert("t/c", "1")).await?;
+    assert!(!is_quota_exceeded(&st));
+    a.commit().await?;
+
+    assert_eq!(Some(PrefixUsage::new(2, 9)), usage(&sm, "t/"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prefix_quota_max_bytes() -> anyhow::Result<()> {
+    let sm = SMV003::default();
+    let mut a = sm.new_applier().await;
+
+    a.apply_cmd(&set_feature(true)).await?;
+    a.apply_cmd(&define_quota("t/", PrefixQuota::new(None, Some(10))))
+        .await?;
+
+    let st = a.apply_cmd(&upsert("t/a", "12345")).await?;
+    assert!(!is_quota_exceeded(&st));
+
+    // 3 bytes more, 11 exceeds 10
+    let st = a.apply_cmd(&upsert("t/a", "12345678")).await?;
+    assert!(is_quota_exceeded(&st));
+
+    // Shrinking is always allowed
+    let st = a.apply_cmd(&upsert("t/a", "1")).await?;
+    assert!(!is_quota_exceeded(&st));
+    a.commit().await?;
+
+    assert_eq!(Some(PrefixUsage::new(1, 4)), usage(&sm, "t/"));
+
+    // Removing the definition removes the quota
+    let mut a = sm.new_applier().await;
+    a.apply_cmd(&Cmd::UpsertKV(UpsertKV::delete(quota_key("t/"))))
+        .await?;
+    let st = a.apply_cmd(&upsert("t/a", "12345678")).await?;
+    assert!(!is_quota_exceeded(&st));
+    a.commit().await?;
+
+    assert_eq!(None, usage(&sm, "t/"));
+
+    Ok(())
+}
@@ -14,17 +14,25 @@
 
 //! Raft cluster configuration including networking, storage, and performance tuning.
 
+use std::collections::BTreeMap;
+use std::fmt;
 use std::io;
 use std::net::Ipv4Addr;
 use std::path::Path;
+use std::sync::Arc;
 
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_types::Endpoint;
+use databend_meta_types::GrpcCompression;
 use databend_meta_types::MetaStartupError;
+use databend_meta_types::encryption::KeyRing;
+use databend_meta_types::encryption::KeyRingCache;
 use databend_meta_types::raft_types::NodeId;
 
+use crate::immutable_compactor::CompactPolicy;
 use crate::ondisk::DATA_VERSION;
 use crate::raft_log_v004;
+use crate::raft_log_v004::RaftLogDurability;
 
 // Size unit constants
 const KB: u64 = 1024;
@@ -76,12 +84,42 @@ pub struct RaftConfig {
     /// Maximum WAL chunk size in bytes
     pub log_wal_chunk_max_size: u64,
 
+    /// When appended log entries are acknowledged as persisted: fsync, group_commit or replication.
+    ///
+    /// Default: fsync.
+    pub raft_log_durability: RaftLogDurability,
+
+    /// With `group_commit` durability, the milliseconds an append waits for others to share
+    /// an fsync.
+    ///
+    /// The delay is added to every append, even if there is no concurrent append to share with.
+    ///
+    /// Default: 2ms.
+    pub raft_log_group_commit_delay_ms: u64,
+
     /// Trigger snapshot after this many logs since last snapshot.
     ///
     /// Lower values create more frequent snapshots but increase I/O.
     /// Default: 1024 log entries.
     pub snapshot_logs_since_last: u64,
 
+    /// Trigger snapshot after this many bytes are written to raft-log since last snapshot.
+    ///
+    /// Keeps the WAL small when entries are few but large.
+    /// Default: None, disabled.
+    pub snapshot_log_bytes_since_last: Option<u64>,
+
+    /// Trigger snapshot if this many milliseconds passed since last snapshot
+    /// and there are new logs applied.
+    ///
+    /// Default: None, disabled.
+    pub snapshot_interval_ms: Option<u64>,
+
+    /// Trigger snapshot when the in-memory levels of the state machine hold this many keys.
+    ///
+    /// Default: None, disabled.
+    pub snapshot_in_memory_keys: Option<u64>,
+
     /// Leader heartbeat interval in milliseconds.
     ///
     /// Must be > 0. Typical values: 500-2000ms.
@@ -132,9 +170,46 @@ pub struct RaftConfig {
     /// Default: 1GB (1,073,741,824 bytes).
     pub snapshot_db_block_cache_size: u64,
 
+    /// Number of the latest snapshots to keep in the snapshot dir.
+    ///
+    /// Older snapshots are kept for inspection or for rewinding a node offline.
+    /// At least 1 snapshot is always kept.
+    /// Default: 3.
+    pub snapshot_keep_count: u64,
+
+    /// Whether to verify the checksum of the last snapshot when it is loaded at startup.
+    ///
+    /// It reads the entire snapshot, which delays startup for a large snapshot,
+    /// but refuses to start from a snapshot corrupted on disk.
+    /// Default: true.
+    pub snapshot_verify_on_load: bool,
+
     /// Interval in milliseconds to compact the in memory immutable levels.
     pub compact_immutables_ms: Option<u64>,
 
+    /// Do not compact the in-memory immutable levels if there are fewer levels than this.
+    ///
+    /// The compaction policy is checked every time the writable level is frozen.
+    /// Default: 2.
+    pub compact_min_levels: u64,
+
+    /// Always compact the in-memory immutable levels if there are more levels than this.
+    ///
+    /// Default: 20.
+    pub compact_max_levels: u64,
+
+    /// Compact the in-memory immutable levels if the largest level has more than
+    /// this many times keys of the smallest level.
+    ///
+    /// Default: 0, i.e., compact if any level is not empty.
+    pub compact_size_ratio: u64,
+
+    /// Max number of merges of two adjacent in-memory immutable levels in one compaction.
+    /// `0` means no limit.
+    ///
+    /// Default: 1.
+    pub compact_max_merges: u64,
+
     /// Single node metasrv. It creates a single node cluster if meta data is not initialized.
     /// Otherwise it opens the previous one.
     /// This is mainly for testing purpose.
@@ -150,6 +225,19 @@ pub struct RaftConfig {
     /// A learner does not vote in elections, and does not count towards quorum.
     pub learner: bool,
 
+    /// Placement labels of this node, e.g., `zone=us-east-1a`, `rack=r12`.
+    ///
+    /// The labels are stored in the cluster along with the node info when this node is added,
+    /// and are used by placement policies such as [`Self::preferred_leader_zone`].
+    pub node_labels: BTreeMap<String, String>,
+
+    /// The zone in which the leader is preferred to be.
+    ///
+    /// When set, a leader that is not in this zone transfers leadership to a healthy,
+    /// caught-up voter labeled with this zone.
+    /// Default: None, no preference.
+    pub preferred_leader_zone: Option<String>,
+
     /// Do not run databend-meta, but just remove a node from its cluster.
     ///
     /// The value is one or more addresses of a node in the cluster, to which this node sends a `leave` request.
@@ -177,6 +265,94 @@ pub struct RaftConfig {
     /// Used for both encoding and decoding limits on raft gRPC channels.
     /// Default: 32MB (33,554,432 bytes).
     pub raft_grpc_max_message_size: Option<usize>,
+
+    /// Max number of concurrent requests on one connection to the raft service.
+    ///
+    /// Default: None, no limit.
+    pub raft_concurrency_limit_per_connection: Option<usize>,
+
+    /// Shared secret of the cluster to authenticate the calls to the raft service.
+    ///
+    /// When set, a node sends it with every raft request,
+    /// and rejects every raft request without the same secret.
+    /// All nodes in a cluster must be configured with the same secret.
+    /// Default: None, raft requests are not authenticated.
+    pub raft_auth_secret: Option<Secret>,
+
+    /// Compression of the messages a node sends to other nodes, such as log entries and snapshots.
+    ///
+    /// A node accepts compressed raft messages regardless of this setting,
+    /// thus nodes in a cluster can be configured with different compressions.
+    /// Requests to a peer are compressed only if the version the peer reported accepts them,
+    /// thus it can be enabled before all nodes are upgraded.
+    /// Default: none.
+    pub raft_compression: GrpcCompression,
+
+    /// Path to the key file to encrypt the raft log, the snapshots and the data header at rest.
+    ///
+    /// The file contains one key per line in the form `<key_id>:<64 hex digits>`,
+    /// the last key encrypts new data and the others only decrypt existing data.
+    /// To rotate, append a new key and restart the node: the file is loaded only once,
+    /// then new log records and the next snapshot are encrypted with the new key.
+    /// Data written without encryption is always readable.
+    /// Default: None, data is stored in plaintext.
+    pub encryption_key_file: Option<String>,
+
+    /// The keys loaded from `encryption_key_file`, shared by the clones of this config.
+    #[serde(skip)]
+    pub encryption_key_ring: KeyRingCache,
+
+    /// Size limits of write requests, checked by the leader before proposing.
+    pub write_limit: WriteLimitConfig,
+}
+
+/// Size limits of write requests: `kv_api` and `transaction`.
+///
+/// A request that exceeds a limit is rejected by the leader before it is proposed,
+/// so that an oversized entry never gets into the raft log and blocks replication.
+/// Since every write is proposed by the leader, the limits of the current leader apply:
+/// configure all nodes with the same limits.
+/// `None` means no limit.
+#[derive(Clone, Debug, PartialEq, Eq, Default, serde::Serialize)]
+pub struct WriteLimitConfig {
+    /// Max length in bytes of a key in a request, including keys in conditions and prefixes.
+    pub max_key_len: Option<u64>,
+
+    /// Max size in bytes of a value to write.
+    pub max_value_size: Option<u64>,
+
+    /// Max number of operations in a transaction, in all branches.
+    pub max_txn_ops: Option<u64>,
+
+    /// Max size in bytes of an encoded transaction.
+    pub max_txn_bytes: Option<u64>,
+}
+
+/// A secret string that is never printed or serialized in plain text.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(s: impl ToString) -> Self {
+        Self(s.to_string())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "******")
+    }
+}
+
+impl serde::Serialize for Secret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        serializer.serialize_str("******")
+    }
 }
 
 pub fn get_default_raft_advertise_host() -> String {
@@ -203,7 +379,13 @@ impl Default for RaftConfig {
             log_wal_chunk_max_records: 100_000,
             log_wal_chunk_max_size: 256 * MB,
 
+            raft_log_durability: RaftLogDurability::Fsync,
+            raft_log_group_commit_delay_ms: 2,
+
             snapshot_logs_since_last: 1024,
+            snapshot_log_bytes_since_last: None,
+            snapshot_interval_ms: None,
+            snapshot_in_memory_keys: None,
             heartbeat_interval: 1000,
             install_snapshot_timeout: 4000,
             max_applied_log_to_keep: 1000,
@@ -213,17 +395,31 @@ impl Default for RaftConfig {
             snapshot_db_block_keys: 8000,
             snapshot_db_block_cache_item: 1024,
             snapshot_db_block_cache_size: GB,
+            snapshot_keep_count: 3,
+            snapshot_verify_on_load: true,
 
             compact_immutables_ms: None,
+            compact_min_levels: 2,
+            compact_max_levels: 20,
+            compact_size_ratio: 0,
+            compact_max_merges: 1,
             single: false,
             join: vec![],
             learner: false,
+            node_labels: BTreeMap::new(),
+            preferred_leader_zone: None,
             leave_via: vec![],
             leave_id: None,
             id: 0,
             cluster_name: "foo_cluster".to_string(),
             wait_leader_timeout: 70000,
             raft_grpc_max_message_size: None,
+            raft_concurrency_limit_per_connection: None,
+            raft_auth_secret: None,
+            raft_compression: GrpcCompression::None,
+            encryption_key_file: None,
+            encryption_key_ring: KeyRingCache::default(),
+            write_limit: WriteLimitConfig::default(),
         }
     }
 }
@@ -258,6 +454,28 @@ impl RaftConfig {
             )
     }
 
+    /// Returns the keys to encrypt the on-disk data, `None` if no key file is configured.
+    ///
+    /// The key file is loaded on the first call and the keys are shared by the clones of this config.
+    pub fn key_ring(&self) -> Result<Option<Arc<KeyRing>>, io::Error> {
+        let Some(path) = &self.encryption_key_file else {
+            return Ok(None);
+        };
+
+        let key_ring = self.encryption_key_ring.load(path)?;
+        Ok(Some(key_ring))
+    }
+
+    /// Build the policy to compact the in-memory immutable levels.
+    pub fn to_compact_policy(&self) -> CompactPolicy {
+        CompactPolicy {
+            min_levels: self.compact_min_levels,
+            max_levels: self.compact_max_levels,
+            size_ratio: self.compact_size_ratio,
+            max_merges: self.compact_max_merges,
+        }
+    }
+
     /// Build [`RaftLogV004`](crate::raft_log_v004::RaftLogV004) config from [`RaftConfig`].
     pub fn to_raft_log_config(&self) -> raft_log_v004::RaftLogConfig {
         let p = Path::new(&self.raft_dir)
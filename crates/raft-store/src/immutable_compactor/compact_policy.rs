@@ -0,0 +1,178 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use display_more::DisplaySliceExt;
+
+use crate::leveled_store::level::LevelStat;
+
+/// Decides whether the in-memory immutable levels need compaction,
+/// by the number of levels and the size ratio between levels.
+///
+/// The default policy merges the smallest two adjacent levels every time levels are frozen,
+/// the same as before the policy is configurable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactPolicy {
+    /// Do not compact if there are fewer levels than this.
+    pub min_levels: u64,
+
+    /// Always compact if there are more levels than this.
+    pub max_levels: u64,
+
+    /// Compact if the largest level has more than `size_ratio` times keys of the smallest level,
+    /// i.e., small levels should be merged.
+    pub size_ratio: u64,
+
+    /// Max number of merges of two adjacent levels in one compaction. `0` means no limit.
+    pub max_merges: u64,
+}
+
+impl Default for CompactPolicy {
+    fn default() -> Self {
+        Self {
+            min_levels: 2,
+            max_levels: 20,
+            size_ratio: 0,
+            max_merges: 1,
+        }
+    }
+}
+
+impl CompactPolicy {
+    /// Returns `Ok(())` if the levels need compaction, otherwise the reason not to compact.
+    pub fn need_compact(&self, stat: &[LevelStat]) -> Result<(), String> {
+        let n_levels = stat.len() as u64;
+
+        if n_levels < self.min_levels.max(2) {
+            return Err(format!(
+                "not enough levels({} < {}) to compact",
+                n_levels, self.min_levels
+            ));
+        }
+
+        if n_levels > self.max_levels {
+            return Ok(());
+        }
+
+        let min_size = stat.iter().map(|s| s.key_count()).min().unwrap_or(0);
+        let max_size = stat.iter().map(|s| s.key_count()).max().unwrap_or(0);
+
+        if max_size <= min_size.saturating_mul(self.size_ratio) {
+            return Err(format!(
+                "size difference between levels(min: {}, max: {}) is not large enough, stat: {}",
+                min_size,
+                max_size,
+                stat.display_n(64)
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The result of compacting immutable levels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactStat {
+    /// Number of levels before compaction.
+    pub levels_before: u64,
+
+    /// Number of levels after compaction.
+    pub levels_after: u64,
+}
+
+impl CompactStat {
+    /// Number of levels that are merged into another level.
+    pub fn levels_merged(&self) -> u64 {
+        self.levels_before.saturating_sub(self.levels_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(sizes: &[u64]) -> Vec<LevelStat> {
+        sizes
+            .iter()
+            .map(|n| LevelStat {
+                level_index: None,
+                user_count: *n,
+                expire_count: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_need_compact_default() {
+        let p = CompactPolicy::default();
+
+        assert!(p.need_compact(&stat(&[10])).is_err());
+        assert!(p.need_compact(&stat(&[10, 10])).is_ok());
+        assert!(p.need_compact(&stat(&[0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_need_compact() {
+        let p = CompactPolicy {
+            min_levels: 6,
+            max_levels: 20,
+            size_ratio: 4,
+            max_merges: 0,
+        };
+
+        // Too few levels
+        assert!(p.need_compact(&stat(&[1, 100, 1, 100, 1])).is_err());
+
+        // Similar sizes
+        assert!(p.need_compact(&stat(&[10, 20, 30, 40, 10, 10])).is_err());
+
+        // Size ratio exceeded
+        assert!(p.need_compact(&stat(&[10, 20, 30, 41, 10, 10])).is_ok());
+
+        // Too many levels
+        assert!(p.need_compact(&stat(&[10; 21])).is_ok());
+    }
+
+    #[test]
+    fn test_need_compact_tuned() {
+        let p = CompactPolicy {
+            min_levels: 2,
+            max_levels: 3,
+            size_ratio: 2,
+            max_merges: 0,
+        };
+
+        assert!(p.need_compact(&stat(&[10])).is_err());
+        assert!(p.need_compact(&stat(&[10, 20])).is_err());
+        assert!(p.need_compact(&stat(&[10, 21])).is_ok());
+        assert!(p.need_compact(&stat(&[10, 10, 10, 10])).is_ok());
+
+        // At least 2 levels are required to compact.
+        let p = CompactPolicy {
+            min_levels: 0,
+            max_levels: 0,
+            size_ratio: 2,
+            max_merges: 0,
+        };
+        assert!(p.need_compact(&stat(&[10])).is_err());
+    }
+
+    #[test]
+    fn test_compact_stat() {
+        let st = CompactStat {
+            levels_before: 8,
+            levels_after: 5,
+        };
+        assert_eq!(3, st.levels_merged());
+    }
+}
@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod compact_policy;
+
 use std::fmt;
 use std::sync::Arc;
 
+pub use compact_policy::CompactPolicy;
+pub use compact_policy::CompactStat;
 use display_more::DisplaySliceExt;
 use log::info;
 use seq_marked::InternalSeq;
@@ -116,36 +120,60 @@ impl ImmutableCompactor {
         &self.name
     }
 
-    pub fn compact(self, min_snapshot_seq: InternalSeq) {
-        //
-        let immutable_levels = self.leveled_map.immutable_levels();
+    /// Acquire the compactor permit to compact the immutable levels, without freezing the writable level.
+    pub async fn acquire(sm: &SMV003, name: impl ToString) -> Self {
+        let name = name.to_string();
+        let compactor_permit = sm.new_compactor_acquirer(name.clone()).acquire().await;
+
+        Self::new(sm.leveled_map().clone(), compactor_permit, name)
+    }
+
+    /// Compact the smallest adjacent immutable levels repeatedly,
+    /// until the policy is satisfied or `policy.max_merges` is reached.
+    pub fn compact(self, min_snapshot_seq: InternalSeq, policy: &CompactPolicy) -> CompactStat {
+        let mut immutable_levels = self.leveled_map.immutable_levels();
 
         let old_stat = immutable_levels.stat();
 
+        let mut compact_stat = CompactStat {
+            levels_before: old_stat.len() as u64,
+            levels_after: old_stat.len() as u64,
+        };
+
         info!(
             "{} Start compact immutables: {}",
             self,
             old_stat.display_n(64)
         );
 
-        if let Err(e) = immutable_levels.need_compact() {
-            info!("{} Skip compact immutables because: {}", self, e);
-            return;
-        }
+        loop {
+            if policy.max_merges > 0 && compact_stat.levels_merged() >= policy.max_merges {
+                info!(
+                    "{} Stop compact immutables: reached max merges {}",
+                    self, policy.max_merges
+                );
+                break;
+            }
+
+            if let Err(e) = immutable_levels.need_compact(policy) {
+                info!("{} Stop compact immutables because: {}", self, e);
+                break;
+            }
 
-        let new_immutable_levels = immutable_levels.compact_min_adjacent(min_snapshot_seq);
+            let new_immutable_levels = immutable_levels.compact_min_adjacent(min_snapshot_seq);
+
+            let old_stat = immutable_levels.stat();
+            let new_stat = new_immutable_levels.stat();
+
+            if old_stat == new_stat {
+                info!(
+                    "{} Done compact immutables: No compaction: {}",
+                    self,
+                    old_stat.display_n(64),
+                );
+                break;
+            }
 
-        let old_stat = immutable_levels.stat();
-        let new_stat = new_immutable_levels.stat();
-
-        if old_stat == new_stat {
-            info!(
-                "{} Done compact immutables: No compaction: {}",
-                self,
-                old_stat.display_n(64),
-            );
-            return;
-        } else {
             for i in 0..new_stat.len() {
                 let old = &old_stat[i];
                 let new = &new_stat[i];
@@ -162,9 +190,64 @@ impl ImmutableCompactor {
                     break;
                 }
             }
+
+            compact_stat.levels_after = new_stat.len() as u64;
+            immutable_levels = new_immutable_levels;
+        }
+
+        if compact_stat.levels_merged() > 0 {
+            self.leveled_map.replace_immutable_levels(immutable_levels);
         }
 
-        self.leveled_map
-            .replace_immutable_levels(new_immutable_levels);
+        compact_stat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use map_api::mvcc::ScopedSet;
+    use state_machine_api::UserKey;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_compact_max_merges() -> anyhow::Result<()> {
+        let sm = SMV003::default();
+        let lm = sm.leveled_map().clone();
+
+        for i in 0..4 {
+            let mut view = lm.to_view();
+            view.set(UserKey::new(format!("k{}", i)), Some((None, b"v".to_vec())));
+            view.commit().await?;
+            lm.freeze_writable_without_permit();
+        }
+
+        // The default policy merges one pair of levels.
+        let compactor = ImmutableCompactor::acquire(&sm, "test").await;
+        let stat = compactor.compact(InternalSeq::new(u64::MAX), &CompactPolicy::default());
+        assert_eq!(
+            CompactStat {
+                levels_before: 4,
+                levels_after: 3
+            },
+            stat
+        );
+
+        // No limit: merge until the policy is satisfied.
+        let policy = CompactPolicy {
+            max_merges: 0,
+            ..Default::default()
+        };
+        let compactor = ImmutableCompactor::acquire(&sm, "test").await;
+        let stat = compactor.compact(InternalSeq::new(u64::MAX), &policy);
+        assert_eq!(
+            CompactStat {
+                levels_before: 3,
+                levels_after: 1
+            },
+            stat
+        );
+
+        Ok(())
     }
 }
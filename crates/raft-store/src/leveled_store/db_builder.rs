@@ -18,7 +18,12 @@ use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
+use databend_meta_types::encryption::KeyRing;
+use databend_meta_types::snapshot_checksum::SnapshotChecksum;
 use databend_meta_types::snapshot_db::DB;
+use databend_meta_types::snapshot_db::encode_rotbl_user_data;
+use databend_meta_types::snapshot_db::entry_position_key;
+use databend_meta_types::snapshot_db::seal_entry;
 use databend_meta_types::sys_data::SysData;
 use futures::Stream;
 use futures_util::TryStreamExt;
@@ -37,6 +42,15 @@ use crate::state_machine::MetaSnapshotId;
 /// Builds a snapshot from series of key-value in `(String, SeqMarked)`
 pub(crate) struct DBBuilder {
     rotbl_builder: rotbl::v001::Builder<FsStorage>,
+
+    /// Checksum of the appended key-values, stored in the meta of the built DB.
+    checksum: SnapshotChecksum,
+
+    /// Encrypts the key-values and the meta if encryption at rest is enabled.
+    key_ring: Option<Arc<KeyRing>>,
+
+    /// Number of the appended key-values, the position of the next encrypted key-value.
+    count: u64,
 }
 
 impl DBBuilder {
@@ -44,6 +58,7 @@ impl DBBuilder {
         storage_path: P,
         rel_path: &str,
         rotbl_config: rotbl::v001::Config,
+        key_ring: Option<Arc<KeyRing>>,
     ) -> Result<Self, io::Error> {
         let storage = FsStorage::new(storage_path.as_ref().to_path_buf());
 
@@ -53,14 +68,37 @@ impl DBBuilder {
 
         let b = Self {
             rotbl_builder: inner,
+            checksum: SnapshotChecksum::new(),
+            key_ring,
+            count: 0,
         };
 
         Ok(b)
     }
 
     /// Append a key-value pair to the builder, the keys must be sorted.
+    ///
+    /// If encryption is enabled, the key-value is encrypted and stored at its position instead.
+    /// The checksum is computed before it is encrypted.
     pub fn append_kv(&mut self, k: String, v: SeqMarked) -> Result<(), io::Error> {
-        self.rotbl_builder.append_kv(k, v)
+        self.checksum.update(&k, &v);
+
+        let position = self.count;
+        self.count += 1;
+
+        match &self.key_ring {
+            None => self.rotbl_builder.append_kv(k, v),
+            Some(key_ring) => {
+                let sealed = seal_entry(key_ring, &k, v)?;
+                self.rotbl_builder
+                    .append_kv(entry_position_key(position), sealed)
+            }
+        }
+    }
+
+    /// Returns the checksum of the key-values appended so far.
+    pub fn checksum(&self) -> &SnapshotChecksum {
+        &self.checksum
     }
 
     /// This method is only used for test
@@ -85,6 +123,7 @@ impl DBBuilder {
     ) -> Result<DB, io::Error> {
         let config = self.rotbl_builder.config().clone();
         let storage_path = self.rotbl_builder.storage().base_dir_str().to_string();
+        let key_ring = self.key_ring.clone();
 
         let (current_rel_path, _r) = self.commit(sys_data)?;
 
@@ -93,28 +132,31 @@ impl DBBuilder {
         let (_, rel_path) =
             snapshot_config.move_to_final_path(&current_path, snapshot_id.to_string())?;
 
-        let db = DB::open_snapshot(&storage_path, &rel_path, snapshot_id.to_string(), config)
-            .map_err(|e| {
-                io::Error::new(
-                    e.kind(),
-                    format!(
-                        "{}; when:(open snapshot at: {}/{})",
-                        e, storage_path, rel_path
-                    ),
-                )
-            })?;
+        let db = DB::open_snapshot(
+            &storage_path,
+            &rel_path,
+            snapshot_id.to_string(),
+            config,
+            key_ring,
+        )
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "{}; when:(open snapshot at: {}/{})",
+                    e, storage_path, rel_path
+                ),
+            )
+        })?;
 
         Ok(db)
     }
 
-    /// Commit the building.
+    /// Commit the building, with the checksum of the key-values stored in the meta.
     pub(crate) fn commit(self, sys_data: SysData) -> Result<(String, Rotbl), io::Error> {
-        let meta = serde_json::to_string(&sys_data).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("serialize sys_data failed: {}", e),
-            )
-        })?;
+        let checksum = self.checksum.checksum();
+        let meta = encode_rotbl_user_data(&sys_data, Some(checksum), self.key_ring.as_deref())
+            .map_err(|e| io::Error::new(e.kind(), format!("serialize sys_data failed: {}", e)))?;
 
         // the first arg `seq` is not used.
         let rotbl_meta = RotblMeta::new(0, meta);
@@ -147,8 +189,9 @@ impl DBBuilder {
         let storage_path = self.storage_path().to_string();
 
         let snapshot_id = make_snapshot_id(&sys_data);
+        let key_ring = self.key_ring.clone();
         let (rel_path, r) = self.commit(sys_data)?;
-        let db = DB::new(storage_path, rel_path, snapshot_id, Arc::new(r))?;
+        let db = DB::new(storage_path, rel_path, snapshot_id, Arc::new(r), key_ring)?;
 
         Ok(db)
     }
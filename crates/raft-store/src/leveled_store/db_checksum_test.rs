@@ -0,0 +1,77 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the checksum stored in a DB built by `DBBuilder`.
+
+use std::io;
+
+use databend_meta_types::UpsertKV;
+use databend_meta_types::snapshot_db::DB;
+
+use crate::leveled_store::db_builder::DBBuilder;
+use crate::sm_v003::SMV003;
+use crate::sm_v003::open_snapshot::OpenSnapshot;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+async fn test_db_checksum_verify() -> anyhow::Result<()> {
+    let mut sm = {
+        let mut sm = SMV003::default();
+
+        let mut a = sm.new_applier().await;
+        a.upsert_kv(&UpsertKV::update("a", b"a0")).await?;
+        a.upsert_kv(&UpsertKV::update("b", b"b0")).await?;
+        a.upsert_kv(&UpsertKV::delete("a")).await?;
+        a.commit().await?;
+
+        sm
+    };
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+
+    let db = {
+        let lm = sm.levels_mut();
+        let db_builder = DBBuilder::new(path, "temp-db", rotbl::v001::Config::default(), None)?;
+        db_builder
+            .build_from_leveled_map(lm, |_| "1-1-1-1".to_string())
+            .await?
+    };
+
+    assert!(db.checksum.is_some());
+    assert_eq!(Some(1), db.verify().await?);
+
+    // The checksum is persisted in the meta.
+    let reopened = DB::open_snapshot(
+        path.to_string_lossy(),
+        "temp-db",
+        "1-1-1-1".to_string(),
+        rotbl::v001::Config::default(),
+        None,
+    )?;
+    assert_eq!(db.checksum, reopened.checksum);
+    assert_eq!(Some(1), reopened.verify().await?);
+
+    // Mismatched checksum
+    let mut bad = reopened.clone();
+    bad.checksum = bad.checksum.map(|c| c.wrapping_add(1));
+    let err = bad.verify().await.unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+    // No checksum, e.g., built by an older version
+    let mut old = reopened;
+    old.checksum = None;
+    assert_eq!(None, old.verify().await?);
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test a DB built by `DBBuilder` with encryption at rest.
+
+use std::fs;
+use std::ops::Bound;
+use std::sync::Arc;
+
+use databend_meta_types::UpsertKV;
+use databend_meta_types::encryption::KeyRing;
+use databend_meta_types::snapshot_db::DB;
+use futures_util::TryStreamExt;
+
+use crate::leveled_store::db_builder::DBBuilder;
+use crate::sm_v003::SMV003;
+use crate::sm_v003::open_snapshot::OpenSnapshot;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+async fn test_db_encrypted() -> anyhow::Result<()> {
+    let key_ring = Arc::new(KeyRing::parse(
+        "1:0000000000000000000000000000000000000000000000000000000000000001",
+    )?);
+
+    let mut sm = {
+        let mut sm = SMV003::default();
+
+        let mut a = sm.new_applier().await;
+        a.upsert_kv(&UpsertKV::update("a", b"secret_a")).await?;
+        a.upsert_kv(&UpsertKV::update("b", b"secret_b")).await?;
+        a.upsert_kv(&UpsertKV::update("c", b"secret_c")).await?;
+        a.upsert_kv(&UpsertKV::delete("b")).await?;
+        a.commit().await?;
+
+        sm
+    };
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+
+    let db = {
+        let lm = sm.levels_mut();
+        let db_builder = DBBuilder::new(
+            path,
+            "temp-db",
+            rotbl::v001::Config::default(),
+            Some(key_ring.clone()),
+        )?;
+        db_builder
+            .build_from_leveled_map(lm, |_| "1-1-1-1".to_string())
+            .await?
+    };
+
+    assert!(db.key_ring.is_some());
+    assert_eq!(Some(2), db.verify().await?);
+
+    let reopened = DB::open_snapshot(
+        path.to_string_lossy(),
+        "temp-db",
+        "1-1-1-1".to_string(),
+        rotbl::v001::Config::default(),
+        Some(key_ring),
+    )?;
+    assert_eq!(db.sys_data, reopened.sys_data);
+
+    let all = reopened.inner_range().try_collect::<Vec<_>>().await?;
+    assert_eq!(db.inner_range().try_collect::<Vec<_>>().await?, all);
+
+    let (k, v) = all[0].clone();
+
+    // Neither keys, values nor the sys data are stored in plaintext.
+    let content = fs::read(db.path())?;
+    for plain in [k.as_bytes(), b"secret_a", b"sequence"] {
+        assert!(!content.windows(plain.len()).any(|w| w == plain));
+    }
+
+    // Lookup and range by the decrypted keys.
+    assert_eq!(Some(v), reopened.get(&k).await?);
+    assert_eq!(None, reopened.get("kv--/no-such-key").await?);
+
+    let got = reopened
+        .range((Bound::Excluded(k.clone()), Bound::Unbounded))
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(all[1..].to_vec(), got);
+
+    // Can not be opened without the key.
+    let res = DB::open_snapshot(
+        path.to_string_lossy(),
+        "temp-db",
+        "1-1-1-1".to_string(),
+        rotbl::v001::Config::default(),
+        None,
+    );
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+/// Lookups in an encrypted DB with more key-values than a stride of the encrypted index.
+#[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+async fn test_db_encrypted_lookup_many_keys() -> anyhow::Result<()> {
+    let key_ring = Arc::new(KeyRing::parse(
+        "1:0000000000000000000000000000000000000000000000000000000000000001",
+    )?);
+
+    let mut sm = {
+        let mut sm = SMV003::default();
+
+        let mut a = sm.new_applier().await;
+        for i in 0..300 {
+            a.upsert_kv(&UpsertKV::update(format!("k/{:04}", i * 2), b"v"))
+                .await?;
+        }
+        a.commit().await?;
+
+        sm
+    };
+
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path();
+
+    let db = {
+        let lm = sm.levels_mut();
+        let db_builder = DBBuilder::new(
+            path,
+            "temp-db",
+            rotbl::v001::Config::default(),
+            Some(key_ring.clone()),
+        )?;
+        db_builder
+            .build_from_leveled_map(lm, |_| "1-1-1-1".to_string())
+            .await?
+    };
+
+    let all = db.inner_range().try_collect::<Vec<_>>().await?;
+    assert!(all.len() >= 300);
+
+    for (i, (k, v)) in all.iter().enumerate() {
+        assert_eq!(Some(v.clone()), db.get(k).await?, "key: {}", k);
+
+        // A key right after `k` is not found, and starts a range at the next key.
+        let after = format!("{}\0", k);
+        assert_eq!(None, db.get(&after).await?);
+
+        let got = db
+            .range((Bound::Included(after), Bound::Unbounded))
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(all[i + 1..].to_vec(), got);
+    }
+
+    assert_eq!(None, db.get("").await?);
+    assert_eq!(None, db.get("\u{10ffff}").await?);
+
+    Ok(())
+}
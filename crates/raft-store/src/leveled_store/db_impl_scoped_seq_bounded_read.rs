@@ -47,7 +47,7 @@ where
         let key = RotblCodec::encode_key(&key)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-        let res = self.0.rotbl.get(&key).await?;
+        let res = self.0.get(&key).await?;
 
         let Some(seq_marked) = res else {
             return Ok(SeqMarked::new_not_found());
@@ -80,7 +80,7 @@ where
         let rng = RotblCodec::encode_range(&range)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-        let strm = self.0.rotbl.range(rng);
+        let strm = self.0.range(rng);
 
         let strm = strm.map(|res_item: Result<(String, SeqMarked), io::Error>| {
             let (str_k, seq_marked) = res_item?;
@@ -162,6 +162,9 @@ mod tests {
             rel_path: path.to_string(),
             meta: Default::default(),
             sys_data,
+            checksum: None,
+            key_ring: None,
+            encrypted_index: Default::default(),
             rotbl: Arc::new(rotbl),
         };
         Ok(db)
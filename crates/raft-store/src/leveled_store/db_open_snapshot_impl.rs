@@ -16,6 +16,7 @@ use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use databend_meta_types::encryption::KeyRing;
 use databend_meta_types::snapshot_db::DB;
 use log::info;
 use openraft::SnapshotId;
@@ -30,6 +31,7 @@ impl OpenSnapshot for DB {
         rel_path: impl ToString,
         snapshot_id: SnapshotId,
         config: rotbl::v001::Config,
+        key_ring: Option<Arc<KeyRing>>,
     ) -> Result<Self, io::Error> {
         let storage_path = storage_path.to_string();
         let rel_path = rel_path.to_string();
@@ -40,7 +42,7 @@ impl OpenSnapshot for DB {
 
         info!("Opened snapshot at {storage_path}/{rel_path}");
 
-        let db = Self::new(storage_path, rel_path, snapshot_id, Arc::new(r))?;
+        let db = Self::new(storage_path, rel_path, snapshot_id, Arc::new(r), key_ring)?;
         Ok(db)
     }
 }
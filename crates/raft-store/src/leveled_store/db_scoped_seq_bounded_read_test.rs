@@ -63,7 +63,7 @@ async fn test_db_scoped_seq_bounded_read() -> anyhow::Result<()> {
         let temp_dir = tempfile::tempdir()?;
         let path = temp_dir.path();
 
-        let db_builder = DBBuilder::new(path, "temp-db", rotbl::v001::Config::default())?;
+        let db_builder = DBBuilder::new(path, "temp-db", rotbl::v001::Config::default(), None)?;
         db_builder
             .build_from_leveled_map(lm, |_| "1-1-1-1".to_string())
             .await?
@@ -283,6 +283,9 @@ mod tests {
             rel_path: path.to_string(),
             meta: Default::default(),
             sys_data,
+            checksum: None,
+            key_ring: None,
+            encrypted_index: Default::default(),
             rotbl: Arc::new(rotbl),
         };
         Ok(db)
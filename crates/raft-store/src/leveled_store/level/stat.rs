@@ -28,6 +28,11 @@ impl LevelStat {
         self.level_index = Some(index);
         self
     }
+
+    /// The number of keys in this level, including user keys and expire index keys.
+    pub fn key_count(&self) -> u64 {
+        self.user_count + self.expire_count
+    }
 }
 
 impl fmt::Display for LevelStat {
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use futures_util::TryStreamExt;
 use map_api::SeqMarked;
 use map_api::mvcc::ScopedGet;
@@ -80,6 +82,18 @@ async fn test_freeze() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_freeze_notifies() -> anyhow::Result<()> {
+    let l = LeveledMap::default();
+    let frozen = l.frozen_notify();
+
+    // The notification is kept until someone waits for it.
+    l.freeze_writable_without_permit();
+    tokio::time::timeout(Duration::from_secs(1), frozen.notified()).await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_level() -> anyhow::Result<()> {
     let l = LeveledMap::default();
@@ -28,6 +28,7 @@ use display_more::DisplaySliceExt;
 use log::info;
 use map_api::mvcc;
 use seq_marked::InternalSeq;
+use tokio::sync::Notify;
 
 use crate::leveled_store::immutable::Immutable;
 use crate::leveled_store::immutable_data::ImmutableData;
@@ -76,12 +77,18 @@ mod leveled_map_test;
 #[derive(Debug, Clone)]
 pub struct LeveledMap {
     pub data: Arc<Mutex<LeveledMapData>>,
+
+    /// Notified every time the writable level is frozen into a new immutable level.
+    ///
+    /// It is shared with the state machine that replaces this one by installing a snapshot.
+    pub(crate) frozen: Arc<Notify>,
 }
 
 impl Default for LeveledMap {
     fn default() -> Self {
         Self {
             data: Arc::new(Mutex::new(LeveledMapData::default())),
+            frozen: Arc::new(Notify::new()),
         }
     }
 }
@@ -95,6 +102,14 @@ impl LeveledMap {
         self.with_inner(|inner| inner.writable.with_sys_data(f))
     }
 
+    /// Returns the notification that is fired every time the writable level is frozen,
+    /// i.e., the number of immutable levels grows.
+    ///
+    /// A single permit is stored if no one is waiting, thus a freeze is not missed.
+    pub fn frozen_notify(&self) -> Arc<Notify> {
+        self.frozen.clone()
+    }
+
     /// Freeze the current writable level and create a new empty writable level.
     ///
     /// Need writer permit to reset the writable level, and compactor permit to add a new immutable level.
@@ -129,6 +144,8 @@ impl LeveledMap {
             inner.writable.stat(),
             levels.indexes().display_n(265)
         );
+
+        self.frozen.notify_one();
     }
 
     /// Return the kv count and expire count in the writable level.
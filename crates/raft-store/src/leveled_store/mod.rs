@@ -33,6 +33,10 @@ pub mod view;
 #[cfg(test)]
 pub(crate) mod testing_data;
 
+#[cfg(test)]
+mod db_checksum_test;
+#[cfg(test)]
+mod db_encryption_test;
 mod db_impl_scoped_seq_bounded_read;
 mod db_open_snapshot_impl;
 #[cfg(test)]
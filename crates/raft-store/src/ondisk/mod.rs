@@ -30,6 +30,7 @@ pub use data_version::DataVersion;
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_sled_store::SledTree;
 use databend_meta_sled_store::init_get_sled_db;
+use databend_meta_types::encryption;
 pub use header::Header;
 use log::info;
 use raft_log::codeq::error_context_ext::ErrorContextExt;
@@ -104,6 +105,9 @@ impl OnDisk {
         info!("Loaded header from fs: {:?}", header);
 
         if let Some(v) = header {
+            if Self::header_needs_encrypting(config)? {
+                Self::write_header_to_fs(config, &v)?;
+            }
             return Ok(OnDisk::new(v, config));
         }
 
@@ -199,11 +203,15 @@ impl OnDisk {
                 .context(|| format!("serializing header at {}", header_path.as_path().display(),))
         })?;
 
+        let key_ring = config.key_ring()?;
+        let buf = encryption::seal(key_ring.as_deref(), buf)
+            .context(|| format!("encrypting header at {}", header_path.as_path().display()))?;
+
         fs::write(&header_path, &buf).context(|| {
             format!(
                 "writing version file at {}: {}",
                 header_path.as_path().display(),
-                String::from_utf8_lossy(&buf)
+                header
             )
         })?;
 
@@ -226,6 +234,14 @@ impl OnDisk {
         let state = fs::read(&header_path)
             .context(|| format!("reading version file {}", header_path.as_path().display(),))?;
 
+        let key_ring = config.key_ring()?;
+        let state = encryption::unseal(key_ring.as_deref(), state).context(|| {
+            format!(
+                "decrypting version file {}",
+                header_path.as_path().display()
+            )
+        })?;
+
         let state = serde_json::from_slice::<Header>(&state).map_err(|e| {
             io::Error::new(io::ErrorKind::InvalidData, e)
                 .context(|| format!("parsing version file {}", header_path.as_path().display(),))
@@ -234,6 +250,20 @@ impl OnDisk {
         Ok(Some(state))
     }
 
+    /// Returns if the header file should be rewritten to be encrypted with the active key,
+    /// i.e., it is in plaintext or the key is rotated.
+    fn header_needs_encrypting(config: &RaftConfig) -> Result<bool, io::Error> {
+        let Some(key_ring) = config.key_ring()? else {
+            return Ok(false);
+        };
+
+        let header_path = Self::header_path(config);
+        let buf = fs::read(&header_path)
+            .context(|| format!("reading version file {}", header_path.as_path().display(),))?;
+
+        Ok(encryption::frame_key_id(&buf) != Some(key_ring.active_key_id()))
+    }
+
     /// Enable or disable logging crucial steps to stderr, when upgrading.
     pub fn log_stderr(&mut self, log_stderr: bool) {
         self.log_stderr = log_stderr;
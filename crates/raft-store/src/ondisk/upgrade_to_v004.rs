@@ -57,7 +57,7 @@ impl OnDisk {
         let raft_log_config = self.config.clone().to_raft_log_config();
         let raft_log_config = Arc::new(raft_log_config);
         let raft_log = RaftLogV004::open(raft_log_config)?;
-        let mut importer = importer::Importer::new(raft_log);
+        let mut importer = importer::Importer::new(raft_log, self.config.key_ring()?);
 
         let db = init_get_sled_db(self.config.raft_dir.clone(), 1024 * 1024 * 1024);
 
@@ -23,6 +23,7 @@ use tokio::sync::oneshot;
 
 use crate::raft_log_v004::IODesc;
 use crate::raft_log_v004::callback_data::CallbackData;
+use crate::raft_log_v004::callback_data::IOFlushedBatch;
 
 /// The callback to be called when the IO is completed.
 ///
@@ -42,6 +43,16 @@ impl Callback {
         }
     }
 
+    /// Create a callback that notifies every one of `batch` when the flush is done.
+    pub fn new_io_flushed_batch(batch: IOFlushedBatch, mut io_desc: IODesc) -> Self {
+        io_desc.set_flush_time();
+
+        Callback {
+            io_desc,
+            data: CallbackData::IOFlushedBatch(batch),
+        }
+    }
+
     pub fn new_oneshot(tx: oneshot::Sender<Result<(), io::Error>>, mut io_desc: IODesc) -> Self {
         io_desc.set_flush_time();
 
@@ -85,6 +96,7 @@ impl raft_log::Callback for Callback {
             }
             CallbackData::SyncOneshot(tx) => tx.send(res),
             CallbackData::IOFlushed(io_flushed) => io_flushed.io_completed(res),
+            CallbackData::IOFlushedBatch(batch) => batch.complete(&res),
         }
     }
 }
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use std::io;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::mpsc::SyncSender;
 
 use databend_meta_types::raft_types;
@@ -22,4 +24,36 @@ pub enum CallbackData {
     Oneshot(oneshot::Sender<Result<(), io::Error>>),
     SyncOneshot(SyncSender<Result<(), io::Error>>),
     IOFlushed(raft_types::IOFlushed),
+    /// Several appends that are flushed together.
+    IOFlushedBatch(IOFlushedBatch),
+}
+
+/// Several appends that are flushed together, each is completed at most once.
+///
+/// It is shared with the one that submits the flush,
+/// so that the appends can still be completed if the submit fails and the callback is dropped.
+#[derive(Clone)]
+pub struct IOFlushedBatch {
+    pending: Arc<Mutex<Vec<raft_types::IOFlushed>>>,
+}
+
+impl IOFlushedBatch {
+    pub fn new(batch: Vec<raft_types::IOFlushed>) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(batch)),
+        }
+    }
+
+    /// Complete every append that is not yet completed with `res`.
+    pub fn complete(&self, res: &Result<(), io::Error>) {
+        let batch = std::mem::take(&mut *self.pending.lock().unwrap());
+
+        for io_flushed in batch {
+            let res = match res {
+                Ok(()) => Ok(()),
+                Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+            };
+            io_flushed.io_completed(res);
+        }
+    }
 }
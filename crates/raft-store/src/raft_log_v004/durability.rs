@@ -0,0 +1,93 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// When appended log entries are acknowledged as persisted to raft.
+///
+/// Vote and other raft state are always fsync-ed before being acknowledged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RaftLogDurability {
+    /// Fsync every append before acknowledging it.
+    #[default]
+    Fsync,
+
+    /// Acknowledge the appends made within a short delay with a single fsync.
+    ///
+    /// It reduces the fsync calls under concurrent writes,
+    /// at the cost of the delay, which is added to every append even without concurrent writes.
+    GroupCommit,
+
+    /// Acknowledge an append without waiting for fsync, and rely on the replication to a quorum.
+    ///
+    /// Data may be lost if a quorum of nodes crash at the same time.
+    /// Do not use it in production.
+    Replication,
+}
+
+impl RaftLogDurability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RaftLogDurability::Fsync => "fsync",
+            RaftLogDurability::GroupCommit => "group_commit",
+            RaftLogDurability::Replication => "replication",
+        }
+    }
+}
+
+impl fmt::Display for RaftLogDurability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for RaftLogDurability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "" | "fsync" => Ok(RaftLogDurability::Fsync),
+            "group_commit" => Ok(RaftLogDurability::GroupCommit),
+            "replication" => Ok(RaftLogDurability::Replication),
+            _ => Err(format!(
+                "invalid raft-log durability: {:?}; expect one of: fsync, group_commit, replication",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raft_log_durability_from_str() {
+        assert_eq!(Ok(RaftLogDurability::Fsync), "".parse());
+        assert_eq!(Ok(RaftLogDurability::Fsync), "fsync".parse());
+        assert_eq!(Ok(RaftLogDurability::GroupCommit), "Group_Commit".parse());
+        assert_eq!(Ok(RaftLogDurability::Replication), "replication".parse());
+        assert!("none".parse::<RaftLogDurability>().is_err());
+
+        for d in [
+            RaftLogDurability::Fsync,
+            RaftLogDurability::GroupCommit,
+            RaftLogDurability::Replication,
+        ] {
+            assert_eq!(Ok(d), d.to_string().parse());
+        }
+    }
+}
@@ -13,7 +13,9 @@
 // limitations under the License.
 
 use std::io;
+use std::sync::Arc;
 
+use databend_meta_types::encryption::KeyRing;
 use databend_meta_types::raft_types::LogId;
 use raft_log::api::raft_log_writer::RaftLogWriter;
 
@@ -21,6 +23,7 @@ use crate::key_spaces::RaftStoreEntry;
 use crate::raft_log_v004::RaftLogV004;
 use crate::raft_log_v004::codec_wrapper::Cw;
 use crate::raft_log_v004::log_store_meta::LogStoreMeta;
+use crate::raft_log_v004::sealed::Sealed;
 use crate::raft_log_v004::util;
 
 /// Import series of [`RaftStoreEntry`] record into [`RaftLogV004`].
@@ -29,13 +32,17 @@ use crate::raft_log_v004::util;
 pub struct Importer {
     pub raft_log: RaftLogV004,
     pub max_log_id: Option<LogId>,
+
+    /// Encrypts the imported log entries if encryption at rest is enabled.
+    key_ring: Option<Arc<KeyRing>>,
 }
 
 impl Importer {
-    pub fn new(raft_log: RaftLogV004) -> Self {
+    pub fn new(raft_log: RaftLogV004, key_ring: Option<Arc<KeyRing>>) -> Self {
         Importer {
             raft_log,
             max_log_id: None,
+            key_ring,
         }
     }
 
@@ -53,9 +60,9 @@ impl Importer {
             //////////////////////////// V004 log ////////////////////////////
             RaftStoreEntry::LogEntry(log_entry) => {
                 let log_id = log_entry.log_id;
-                let payload = log_entry.payload;
+                let payload = Sealed::seal(self.key_ring.as_deref(), log_entry.payload)?;
 
-                self.raft_log.append([(Cw(log_id), Cw(payload))])?;
+                self.raft_log.append([(Cw(log_id), payload)])?;
                 self.max_log_id = std::cmp::max(self.max_log_id, Some(log_id));
             }
 
@@ -0,0 +1,218 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline inspection and repair of the raft-log of a stopped node.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use databend_meta_types::encryption::KeyRing;
+use databend_meta_types::raft_types::Entry;
+use databend_meta_types::raft_types::LogId;
+use databend_meta_types::raft_types::NodeId;
+use databend_meta_types::raft_types::Vote;
+use display_more::DisplayOptionExt;
+use log::info;
+use openraft::LogIdOptionExt;
+use raft_log::api::raft_log_writer::RaftLogWriter;
+use raft_log::codeq::error_context_ext::ErrorContextExt;
+
+use crate::config::RaftConfig;
+use crate::raft_log_v004::Cw;
+use crate::raft_log_v004::RaftLogStat;
+use crate::raft_log_v004::RaftLogV004;
+use crate::raft_log_v004::util;
+
+/// The persisted raft state in a raft-log, besides the log entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaftLogSummary {
+    /// The node id saved in [`LogStoreMeta`](crate::raft_log_v004::LogStoreMeta).
+    pub node_id: Option<NodeId>,
+    pub vote: Option<Vote>,
+    pub committed: Option<LogId>,
+    pub purged: Option<LogId>,
+    pub last: Option<LogId>,
+}
+
+impl fmt::Display for RaftLogSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "node_id: {}, vote: {}, committed: {}, purged: {}, last: {}",
+            self.node_id.display(),
+            self.vote.display(),
+            self.committed.display(),
+            self.purged.display(),
+            self.last.display()
+        )
+    }
+}
+
+/// Opens the raft-log of a stopped node to dump and repair it.
+///
+/// The node must not be running.
+pub struct RaftLogInspector {
+    log: RaftLogV004,
+
+    /// Decrypts the log entries if they are encrypted.
+    key_ring: Option<Arc<KeyRing>>,
+}
+
+impl RaftLogInspector {
+    /// Open the raft-log in the `raft_dir` of `config`.
+    ///
+    /// It fails if the last WAL chunk ends with an incomplete or corrupted record,
+    /// which is usually caused by a crash during writing.
+    /// Use [`Self::open_repair`] to truncate the damaged tail.
+    pub fn open(config: &RaftConfig) -> Result<Self, io::Error> {
+        Self::do_open(config, false).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "{}; the WAL tail may be damaged, open with repair to truncate it",
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Open the raft-log and truncate the incomplete record at the end of the last WAL chunk.
+    ///
+    /// The truncated record was never acknowledged, thus no committed data is lost.
+    pub fn open_repair(config: &RaftConfig) -> Result<Self, io::Error> {
+        Self::do_open(config, true)
+    }
+
+    fn do_open(config: &RaftConfig, truncate_incomplete_record: bool) -> Result<Self, io::Error> {
+        let key_ring = config.key_ring()?;
+
+        let mut log_config = config.to_raft_log_config();
+        log_config.truncate_incomplete_record = Some(truncate_incomplete_record);
+
+        // Do not create an empty raft-log if there is none.
+        if !Path::new(&log_config.dir).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("raft-log dir not found: {}", log_config.dir),
+            ));
+        }
+
+        let dir = log_config.dir.clone();
+        let log = RaftLogV004::open(Arc::new(log_config))
+            .context(|| format!("open raft-log at {}", dir))?;
+
+        Ok(Self { log, key_ring })
+    }
+
+    pub fn summary(&self) -> RaftLogSummary {
+        let state = self.log.log_state();
+
+        RaftLogSummary {
+            node_id: state.user_data.as_ref().and_then(|x| x.node_id),
+            vote: state.vote().map(Cw::to_inner),
+            committed: state.committed().map(Cw::to_inner),
+            purged: state.purged().map(Cw::to_inner),
+            last: state.last().map(Cw::to_inner),
+        }
+    }
+
+    /// Returns the statistics of the WAL chunks and the payload cache.
+    pub fn stat(&self) -> RaftLogStat {
+        self.log.stat()
+    }
+
+    /// Read the log entries in range `[start, end)`, with the payload decoded.
+    ///
+    /// Purged entries are skipped.
+    pub fn entries(&self, start: u64, end: u64) -> Result<Vec<Entry>, io::Error> {
+        let summary = self.summary();
+
+        let start = std::cmp::max(start, summary.purged.next_index());
+        let end = std::cmp::min(end, summary.last.next_index());
+
+        if start >= end {
+            return Ok(vec![]);
+        }
+
+        self.log
+            .read(start, end)
+            .map(|res| {
+                let (log_id, payload) = res?;
+                Ok(Entry {
+                    log_id: log_id.unpack(),
+                    payload: payload.unseal(self.key_ring.as_deref())?,
+                })
+            })
+            .collect()
+    }
+
+    /// Remove the log entries after `index` and returns the last log id kept.
+    ///
+    /// The committed log id is moved back to the last kept one if it is greater.
+    /// When restarted, the node fetches the removed entries, or a snapshot, from the leader.
+    pub async fn truncate_after(&mut self, index: u64) -> Result<Option<LogId>, io::Error> {
+        let summary = self.summary();
+
+        if index + 1 < summary.purged.next_index() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "can not truncate after {}: purged upto {}",
+                    index,
+                    summary.purged.display()
+                ),
+            ));
+        }
+
+        if summary.last.next_index() <= index + 1 {
+            info!(
+                "raft-log: nothing to truncate after {}, last: {}",
+                index,
+                summary.last.display()
+            );
+            return Ok(summary.last);
+        }
+
+        let new_last = if summary.purged.index() == Some(index) {
+            summary.purged
+        } else {
+            self.entries(index, index + 1)?.first().map(|x| x.log_id)
+        };
+
+        info!(
+            "raft-log: truncate after {}, new last: {}",
+            index,
+            new_last.display()
+        );
+
+        self.log.truncate(index + 1)?;
+
+        if summary.committed > new_last {
+            if let Some(new_last) = new_last {
+                info!(
+                    "raft-log: move committed from {} back to {}",
+                    summary.committed.display(),
+                    new_last
+                );
+                self.log.commit(Cw(new_last))?;
+            }
+        }
+
+        util::blocking_flush(&mut self.log).await?;
+
+        Ok(new_last)
+    }
+}
@@ -15,12 +15,15 @@
 pub mod callback;
 pub mod callback_data;
 pub mod codec_wrapper;
+pub mod durability;
 pub mod importer;
+pub mod inspector;
 pub mod io_desc;
 pub mod io_phase;
 pub mod log_store_meta;
 pub mod raft_log_io_error;
 pub mod raft_log_types;
+pub mod sealed;
 pub mod util;
 
 pub const TREE_RAFT_LOG: &str = "raft_log";
@@ -31,10 +34,15 @@ pub type RaftLogStat = raft_log::Stat<RaftLogTypes>;
 
 pub use callback::Callback;
 pub use callback_data::CallbackData;
+pub use callback_data::IOFlushedBatch;
 pub use codec_wrapper::Cw;
+pub use durability::RaftLogDurability;
 pub use importer::Importer;
+pub use inspector::RaftLogInspector;
+pub use inspector::RaftLogSummary;
 pub use io_desc::IODesc;
 pub use io_phase::IOPhase;
 pub use log_store_meta::LogStoreMeta;
 pub use raft_log_io_error::RaftLogIOError;
 pub use raft_log_types::RaftLogTypes;
+pub use sealed::Sealed;
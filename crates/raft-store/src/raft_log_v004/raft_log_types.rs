@@ -12,14 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::ops::Deref;
-
 use databend_meta_types::raft_types;
 use deepsize::DeepSizeOf;
 
 use crate::raft_log_v004::callback::Callback;
 use crate::raft_log_v004::codec_wrapper::Cw;
 use crate::raft_log_v004::log_store_meta::LogStoreMeta;
+use crate::raft_log_v004::sealed::Sealed;
 
 /// Defines the types used by RaftLog implementation
 #[derive(PartialEq, Eq, Default, Clone, Debug)]
@@ -27,7 +26,7 @@ pub struct RaftLogTypes;
 
 impl raft_log::Types for RaftLogTypes {
     type LogId = Cw<raft_types::LogId>;
-    type LogPayload = Cw<raft_types::EntryPayload>;
+    type LogPayload = Sealed<raft_types::EntryPayload>;
     type Vote = Cw<raft_types::Vote>;
     type Callback = Callback;
     type UserData = LogStoreMeta;
@@ -37,10 +36,13 @@ impl raft_log::Types for RaftLogTypes {
     }
 
     fn payload_size(payload: &Self::LogPayload) -> u64 {
-        let size = match payload.deref() {
-            raft_types::EntryPayload::Blank => 0,
-            raft_types::EntryPayload::Normal(log_entry) => log_entry.deep_size_of(),
-            raft_types::EntryPayload::Membership(_) => size_of::<raft_types::Membership>(),
+        let size = match payload {
+            Sealed::Plain(raft_types::EntryPayload::Blank) => 0,
+            Sealed::Plain(raft_types::EntryPayload::Normal(log_entry)) => log_entry.deep_size_of(),
+            Sealed::Plain(raft_types::EntryPayload::Membership(_)) => {
+                size_of::<raft_types::Membership>()
+            }
+            Sealed::Encrypted(frame) => frame.len(),
         };
 
         size as u64
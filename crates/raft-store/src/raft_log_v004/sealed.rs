@@ -0,0 +1,180 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::type_name;
+use std::io;
+use std::io::Read;
+
+use databend_meta_types::encryption;
+use databend_meta_types::encryption::KeyRing;
+use raft_log::codeq::Decode;
+use raft_log::codeq::Encode;
+use raft_log::codeq::OffsetWriter;
+use serde::de::DeserializeOwned;
+
+use crate::raft_log_v004::codec_wrapper::Cw;
+
+/// A value in the raft-log that is stored in plaintext, or encrypted in a frame, see [`encryption`].
+///
+/// The codec of the raft-log has no context to decrypt with,
+/// thus the store that owns the keys encrypts a value with [`Sealed::seal`] before appending it,
+/// and decrypts it with [`Sealed::unseal`] after reading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sealed<T: 'static> {
+    /// A value stored in msgpack, the same as [`Cw`].
+    Plain(T),
+
+    /// A complete encrypted frame of the msgpack encoded value.
+    Encrypted(Vec<u8>),
+}
+
+impl<T> Encode for Sealed<T>
+where T: serde::Serialize
+{
+    fn encode<W: io::Write>(&self, mut w: W) -> Result<usize, io::Error> {
+        match self {
+            Sealed::Plain(v) => {
+                let mut ow = OffsetWriter::new(&mut w);
+
+                rmp_serde::encode::write_named(&mut ow, v).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{e}; when:(encode: {})", type_name::<T>()),
+                    )
+                })?;
+
+                Ok(ow.offset())
+            }
+            Sealed::Encrypted(frame) => {
+                w.write_all(frame)?;
+                Ok(frame.len())
+            }
+        }
+    }
+}
+
+impl<T> Decode for Sealed<T>
+where T: DeserializeOwned
+{
+    fn decode<R: io::Read>(mut r: R) -> Result<Self, io::Error> {
+        let mut first = [0u8; 1];
+        r.read_exact(&mut first).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("{e}; when:(decode: {})", type_name::<T>()),
+            )
+        })?;
+
+        if first[0] != encryption::FRAME_MARKER {
+            let v = Cw::<T>::decode(first.as_slice().chain(r))?;
+            return Ok(Sealed::Plain(v.unpack()));
+        }
+
+        let frame = encryption::read_frame_after_marker(r).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("{e}; when:(read encrypted frame: {})", type_name::<T>()),
+            )
+        })?;
+
+        Ok(Sealed::Encrypted(frame))
+    }
+}
+
+impl<T: 'static> Sealed<T> {
+    /// Encrypt `v` if a key ring is provided, otherwise store it in plaintext.
+    pub fn seal(key_ring: Option<&KeyRing>, v: T) -> Result<Self, io::Error>
+    where T: serde::Serialize {
+        let Some(key_ring) = key_ring else {
+            return Ok(Sealed::Plain(v));
+        };
+
+        let mut buf = Vec::new();
+        Cw(v).encode(&mut buf)?;
+
+        Ok(Sealed::Encrypted(key_ring.encrypt(&buf)?))
+    }
+
+    /// Returns the value, decrypted if it is encrypted.
+    pub fn unseal(self, key_ring: Option<&KeyRing>) -> Result<T, io::Error>
+    where T: DeserializeOwned {
+        match self {
+            Sealed::Plain(v) => Ok(v),
+            Sealed::Encrypted(frame) => {
+                let plain = encryption::unseal(key_ring, frame)?;
+                let v = Cw::<T>::decode(plain.as_slice())?;
+                Ok(v.unpack())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use databend_meta_types::encryption::KeyRing;
+    use databend_meta_types::raft_types::LogId;
+    use databend_meta_types::raft_types::new_log_id;
+    use raft_log::codeq::Decode;
+    use raft_log::codeq::Encode;
+
+    use super::Sealed;
+
+    #[test]
+    fn test_sealed_codec() -> Result<(), io::Error> {
+        let kr1 =
+            KeyRing::parse("1:0000000000000000000000000000000000000000000000000000000000000001")?;
+        let kr2 = KeyRing::parse(
+            "1:0000000000000000000000000000000000000000000000000000000000000001\n\
+             2:0000000000000000000000000000000000000000000000000000000000000002",
+        )?;
+
+        let mut buf = Vec::new();
+
+        // Written before encryption is enabled
+        Sealed::seal(None, new_log_id(1, 2, 3))?.encode(&mut buf)?;
+        let before = buf.len();
+        let n = Sealed::seal(Some(&kr1), new_log_id(4, 5, 6))?.encode(&mut buf)?;
+        assert_eq!(buf.len() - before, n);
+        // Written after key rotation
+        Sealed::seal(Some(&kr2), new_log_id(7, 8, 9))?.encode(&mut buf)?;
+
+        let mut r = buf.as_slice();
+
+        let l1 = Sealed::<LogId>::decode(&mut r)?;
+        let l2 = Sealed::<LogId>::decode(&mut r)?;
+        let l3 = Sealed::<LogId>::decode(&mut r)?;
+        assert_eq!(0, r.len());
+
+        assert_eq!(Sealed::Plain(new_log_id(1, 2, 3)), l1);
+        assert!(matches!(l2, Sealed::Encrypted(_)));
+
+        // Decoding does not need the key, only unsealing does.
+        assert!(l2.clone().unseal(None).is_err());
+
+        assert_eq!(new_log_id(1, 2, 3), l1.unseal(None)?);
+        assert_eq!(new_log_id(4, 5, 6), l2.unseal(Some(&kr2))?);
+        assert_eq!(new_log_id(7, 8, 9), l3.unseal(Some(&kr2))?);
+
+        // A truncated encrypted record
+        let mut enc = Vec::new();
+        Sealed::seal(Some(&kr1), new_log_id(4, 5, 6))?.encode(&mut enc)?;
+        let mut r = &enc[..enc.len() - 1];
+        let err = Sealed::<LogId>::decode(&mut r).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rewind the on-disk data of a stopped node to one of its retained snapshots.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use databend_meta_types::snapshot_db::DB;
+use display_more::DisplaySliceExt;
+use log::info;
+use raft_log::codeq::error_context_ext::ErrorContextExt;
+
+use crate::config::RaftConfig;
+use crate::key_spaces::RaftStoreEntry;
+use crate::ondisk::DATA_VERSION;
+use crate::raft_log_v004::Cw;
+use crate::raft_log_v004::Importer;
+use crate::raft_log_v004::RaftLogV004;
+use crate::sm_v003::SnapshotInfo;
+use crate::sm_v003::snapshot_loader::SnapshotLoader;
+use crate::snapshot_config::SnapshotConfig;
+
+/// Suffix appended to the files and dirs that are replaced by a rewind.
+///
+/// They are no longer loaded by the node, and are left for the operator to inspect or remove.
+pub const REWIND_BACKUP_SUFFIX: &str = "before-rewind";
+
+/// Rewind the state of a stopped node to the snapshot `snapshot_id`.
+///
+/// After rewinding, the node starts with the state in this snapshot:
+/// - The raft-log is replaced with an empty one that has purged and committed up to
+///   the last log id of the snapshot. The vote and node id are preserved, so that the node
+///   never votes twice in a term.
+/// - The snapshots newer than `snapshot_id` are renamed so that they are not loaded.
+///
+/// Nothing is deleted: the replaced raft-log dir and snapshots are kept with the
+/// suffix [`REWIND_BACKUP_SUFFIX`]. If it is interrupted, run it again.
+///
+/// The node must not be running, and the membership in the snapshot must have exactly one voter.
+/// A voter in a multi-voter cluster that forgets entries it has acknowledged may help elect a
+/// leader without the committed entries, which breaks raft safety; such a rewind is refused.
+pub async fn rewind_to_snapshot(
+    config: &RaftConfig,
+    snapshot_id: &str,
+) -> Result<SnapshotInfo, io::Error> {
+    let snapshot_config = SnapshotConfig::new(DATA_VERSION, config.clone());
+    let loader = SnapshotLoader::<DB>::new(snapshot_config.clone());
+
+    let infos = loader.list_snapshots().await.map_err(io::Error::other)?;
+
+    let Some(pos) = infos
+        .iter()
+        .position(|x| x.snapshot_id.to_string() == snapshot_id)
+    else {
+        let ids = infos
+            .iter()
+            .map(|x| x.snapshot_id.to_string())
+            .collect::<Vec<_>>();
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "snapshot {} not found in {}, available: {}",
+                snapshot_id,
+                snapshot_config.snapshot_dir(),
+                ids.display()
+            ),
+        ));
+    };
+
+    let target = infos[pos].clone();
+
+    let voters = target
+        .meta
+        .last_membership
+        .membership()
+        .voter_ids()
+        .collect::<Vec<_>>();
+    if voters.len() != 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "refuse to rewind to snapshot {}: only a single voter cluster can be rewound, voters: {}",
+                target.snapshot_id,
+                voters.display()
+            ),
+        ));
+    }
+
+    info!("rewind to snapshot: {}", target);
+
+    rewind_raft_log(config, &target, backup_suffix()).await?;
+
+    for newer in &infos[pos + 1..] {
+        let path = snapshot_config.snapshot_path(&newer.snapshot_id.to_string());
+        let backup = format!("{}.{}", path, REWIND_BACKUP_SUFFIX);
+
+        info!("rewind: move newer snapshot {} to {}", path, backup);
+        fs::rename(&path, &backup).context(|| format!("rename {} to {}", path, backup))?;
+    }
+
+    info!("rewound to snapshot: {}", target);
+
+    Ok(target)
+}
+
+/// Replace the raft-log with one that contains no log entries after the snapshot `target`.
+async fn rewind_raft_log(
+    config: &RaftConfig,
+    target: &SnapshotInfo,
+    backup_suffix: String,
+) -> Result<(), io::Error> {
+    let raft_log_config = config.to_raft_log_config();
+    let log_dir = raft_log_config.dir.clone();
+
+    let (node_id, vote) = {
+        let log = RaftLogV004::open(Arc::new(raft_log_config.clone()))?;
+        let state = log.log_state();
+
+        let node_id = state.user_data.as_ref().and_then(|x| x.node_id);
+        let vote = state.vote().map(Cw::to_inner);
+        (node_id, vote)
+    };
+
+    let tmp_dir = format!("{}.rewinding", log_dir);
+    if Path::new(&tmp_dir).exists() {
+        fs::remove_dir_all(&tmp_dir).context(|| format!("remove {}", tmp_dir))?;
+    }
+    fs::create_dir_all(&tmp_dir).context(|| format!("create {}", tmp_dir))?;
+
+    {
+        let mut tmp_config = raft_log_config;
+        tmp_config.dir = tmp_dir.clone();
+
+        let raft_log = RaftLogV004::open(Arc::new(tmp_config))?;
+        let mut importer = Importer::new(raft_log, config.key_ring()?);
+
+        let last = target.last_log_id();
+
+        importer.import_raft_store_entry(RaftStoreEntry::NodeId(node_id))?;
+        importer.import_raft_store_entry(RaftStoreEntry::Vote(vote))?;
+        importer.import_raft_store_entry(RaftStoreEntry::Purged(last))?;
+        importer.import_raft_store_entry(RaftStoreEntry::Committed(last))?;
+
+        importer.flush().await?;
+    }
+
+    let backup = format!("{}.{}", log_dir, backup_suffix);
+
+    info!("rewind: move raft-log {} to {}", log_dir, backup);
+    fs::rename(&log_dir, &backup).context(|| format!("rename {} to {}", log_dir, backup))?;
+    fs::rename(&tmp_dir, &log_dir).context(|| format!("rename {} to {}", tmp_dir, log_dir))?;
+
+    Ok(())
+}
+
+/// The raft-log dir may be rewound more than once, distinguish the backups by time.
+fn backup_suffix() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+    format!("{}-{}", REWIND_BACKUP_SUFFIX, millis)
+}
@@ -421,7 +421,7 @@ async fn move_bottom_to_db(
 }
 
 async fn compact(lm: &mut LeveledMap, base_path: &str, rel_path: &str) -> Result<(), io::Error> {
-    let db_builder = DBBuilder::new(base_path, rel_path, rotbl::v001::Config::default())?;
+    let db_builder = DBBuilder::new(base_path, rel_path, rotbl::v001::Config::default(), None)?;
 
     let db = db_builder
         .build_from_leveled_map(lm, |_sys_data| "1-1-1-1.snap".to_string())
@@ -15,6 +15,7 @@
 #[allow(clippy::module_inception)]
 mod sm_v003;
 mod sm_v003_kv_api;
+mod snapshot_info;
 mod snapshot_store_error;
 mod snapshot_store_v003;
 mod writer_v003;
@@ -38,6 +39,7 @@ pub mod writer_acquirer;
 
 pub use sm_v003::OnChange;
 pub use sm_v003::SMV003;
+pub use snapshot_info::SnapshotInfo;
 pub use snapshot_store_error::SnapshotStoreError;
 pub use snapshot_store_v003::SnapshotStoreV003;
 pub use snapshot_store_v003::SnapshotStoreV004;
@@ -12,16 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
+use databend_meta_types::encryption::KeyRing;
 use openraft::SnapshotId;
 
 /// A trait for opening a snapshot.
 pub trait OpenSnapshot {
     /// Open a snapshot at `<storage_path>/<rel_path>`
+    ///
+    /// `key_ring` decrypts the snapshot if it is encrypted.
     fn open_snapshot(
         storage_path: impl ToString,
         rel_path: impl ToString,
         snapshot_id: SnapshotId,
         config: rotbl::v001::Config,
+        key_ring: Option<Arc<KeyRing>>,
     ) -> Result<Self, std::io::Error>
     where
         Self: Sized;
@@ -174,6 +174,7 @@ impl SMV003 {
                     writable: Default::default(),
                     immutable: Arc::new(ImmutableData::new(Default::default(), Some(db))),
                 })),
+                frozen: self.leveled_map.frozen.clone(),
             },
             compaction_semaphore: self.compaction_semaphore.clone(),
             write_semaphore: self.write_semaphore.clone(),
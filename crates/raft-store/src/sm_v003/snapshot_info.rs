@@ -0,0 +1,64 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use databend_meta_types::raft_types::LogId;
+use databend_meta_types::raft_types::SnapshotMeta;
+use databend_meta_types::snapshot_db::DB;
+use display_more::DisplayOptionExt;
+
+use crate::state_machine::MetaSnapshotId;
+
+/// Summary of a snapshot persisted in the snapshot dir.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    pub snapshot_id: MetaSnapshotId,
+    pub meta: SnapshotMeta,
+
+    /// Number of keys in the snapshot.
+    pub key_num: u64,
+
+    /// Size of the snapshot file in bytes.
+    pub file_size: u64,
+}
+
+impl SnapshotInfo {
+    pub fn new(snapshot_id: MetaSnapshotId, db: &DB) -> Self {
+        Self {
+            snapshot_id,
+            meta: db.snapshot_meta().clone(),
+            key_num: db.stat().key_num,
+            file_size: db.file_size(),
+        }
+    }
+
+    /// The last log id included in the snapshot.
+    pub fn last_log_id(&self) -> Option<LogId> {
+        self.meta.last_log_id
+    }
+}
+
+impl fmt::Display for SnapshotInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SnapshotInfo{{ id: {}, last_log_id: {}, key_num: {}, file_size: {} }}",
+            self.snapshot_id,
+            self.last_log_id().display(),
+            self.key_num,
+            self.file_size
+        )
+    }
+}
@@ -17,6 +17,7 @@ use std::io;
 use std::marker::PhantomData;
 use std::str::FromStr;
 
+use databend_meta_types::snapshot_db::DB;
 use log::error;
 use log::info;
 use log::warn;
@@ -24,6 +25,7 @@ use openraft::SnapshotId;
 
 use crate::sm_v003::SnapshotStoreError;
 use crate::sm_v003::open_snapshot::OpenSnapshot;
+use crate::sm_v003::snapshot_info::SnapshotInfo;
 use crate::snapshot_config::SnapshotConfig;
 use crate::state_machine::MetaSnapshotId;
 
@@ -73,11 +75,17 @@ where SD: OpenSnapshot
 
         let (storage_path, rel_path) = self.snapshot_config.snapshot_dir_fn(snapshot_id);
 
+        let raft_config = self.snapshot_config.raft_config();
+        let key_ring = raft_config.key_ring().map_err(|e| {
+            SnapshotStoreError::read(e).with_meta("loading encryption key", storage_path.clone())
+        })?;
+
         let d = SD::open_snapshot(
             storage_path.clone(),
             rel_path,
             snapshot_id.clone(),
-            self.snapshot_config.raft_config().to_rotbl_config(),
+            raft_config.to_rotbl_config(),
+            key_ring,
         )
         .map_err(|e| {
             error!("failed to open snapshot file({}): {}", storage_path, e);
@@ -138,7 +146,9 @@ where SD: OpenSnapshot
         Ok((snapshot_ids, invalid_files))
     }
 
-    /// Keep several latest snapshots and cleanup the rest.
+    /// Keep the latest [`RaftConfig::snapshot_keep_count`] snapshots and cleanup the rest.
+    ///
+    /// [`RaftConfig::snapshot_keep_count`]: crate::config::RaftConfig::snapshot_keep_count
     pub async fn clean_old_snapshots(&self) -> Result<(), SnapshotStoreError> {
         let dir = self
             .snapshot_config
@@ -171,7 +181,7 @@ where SD: OpenSnapshot
         }
 
         // Keep the last several snapshots, remove others
-        let n = 3;
+        let n = std::cmp::max(self.snapshot_config.raft_config().snapshot_keep_count, 1) as usize;
         if snapshot_ids.len() <= n {
             info!(
                 "no need to clean snapshots(keeps {} snapshots): {:?}",
@@ -210,3 +220,19 @@ where SD: OpenSnapshot
         SnapshotStoreError::read(e).with_context(context)
     }
 }
+
+impl SnapshotLoader<DB> {
+    /// Return the summary of every valid snapshot in the snapshot directory, older first.
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>, SnapshotStoreError> {
+        let (snapshot_ids, _invalid_files) = self.load_snapshot_ids().await?;
+
+        let mut infos = Vec::with_capacity(snapshot_ids.len());
+
+        for id in snapshot_ids {
+            let db = self.load_snapshot(&id.to_string()).await?;
+            infos.push(SnapshotInfo::new(id, &db));
+        }
+
+        Ok(infos)
+    }
+}
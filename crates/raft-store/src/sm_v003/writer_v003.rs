@@ -52,6 +52,7 @@ impl<SP: SpawnApi> WriterV003<SP> {
             storage_path.clone(),
             &temp_rel_path,
             snapshot_config.raft_config().to_rotbl_config(),
+            snapshot_config.raft_config().key_ring()?,
         )?;
 
         let writer = WriterV003 {
@@ -16,6 +16,7 @@
 
 use std::fmt;
 
+use databend_meta_version::Version;
 use serde::Deserialize;
 use serde::Serialize;
 use strum::IntoEnumIterator;
@@ -31,12 +32,27 @@ pub enum StateMachineFeature {
     Dummy,
     /// Enable another dummy feature
     DummyFeature2,
+    /// Track and enforce storage quotas of key prefixes.
+    ///
+    /// See [`databend_meta_types::prefix_quota`].
+    PrefixQuota,
 }
 
 impl StateMachineFeature {
     pub fn all() -> Vec<Self> {
         Self::iter().collect()
     }
+
+    /// The minimum `databend-meta` version that can apply the state machine changes of this feature.
+    ///
+    /// A feature can be enabled only when every node in the cluster is at least this version.
+    pub fn min_version(&self) -> Version {
+        match self {
+            Self::Dummy => Version::min(),
+            Self::DummyFeature2 => Version::min(),
+            Self::PrefixQuota => Version::new(260205, 4, 0),
+        }
+    }
 }
 
 impl fmt::Display for StateMachineFeature {
@@ -50,11 +66,13 @@ impl fmt::Display for StateMachineFeature {
 
 #[cfg(test)]
 mod tests {
+    use databend_meta_version::version;
+
     use super::StateMachineFeature;
 
     #[test]
     fn test_display() {
-        let expected = ["dummy", "dummy_feature2"];
+        let expected = ["dummy", "dummy_feature2", "prefix_quota"];
         for (i, feat) in StateMachineFeature::all().into_iter().enumerate() {
             let feat_str = feat.to_string();
             let expected_str = expected[i];
@@ -78,7 +96,22 @@ mod tests {
         let all_features = StateMachineFeature::all();
         assert_eq!(all_features, vec![
             StateMachineFeature::Dummy,
-            StateMachineFeature::DummyFeature2
+            StateMachineFeature::DummyFeature2,
+            StateMachineFeature::PrefixQuota,
         ]);
     }
+
+    #[test]
+    fn test_min_version() {
+        // Every feature must be enabled-able in a cluster of the current version.
+        for feat in StateMachineFeature::all() {
+            assert!(
+                feat.min_version() <= *version(),
+                "{} requires {}, newer than current {}",
+                feat,
+                feat.min_version(),
+                version()
+            );
+        }
+    }
 }
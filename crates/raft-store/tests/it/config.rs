@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_raft_store::config::Secret;
 use databend_meta_types::MetaStartupError;
 
 #[test]
@@ -55,3 +56,25 @@ fn test_raft_config() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_raft_config_secret_is_redacted() -> anyhow::Result<()> {
+    let raft_config = RaftConfig {
+        raft_auth_secret: Some(Secret::new("my-secret")),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        "my-secret",
+        raft_config.raft_auth_secret.as_ref().unwrap().expose()
+    );
+
+    let debug = format!("{:?}", raft_config);
+    assert!(!debug.contains("my-secret"));
+    assert!(debug.contains("raft_auth_secret: Some(******)"));
+
+    let json = serde_json::to_string(&raft_config)?;
+    assert!(!json.contains("my-secret"));
+
+    Ok(())
+}
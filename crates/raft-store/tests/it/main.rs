@@ -17,5 +17,6 @@
 #![allow(clippy::collapsible_if)]
 
 mod config;
+mod raft_log_v004_inspector;
 mod raft_log_v004_recovery;
 mod types;
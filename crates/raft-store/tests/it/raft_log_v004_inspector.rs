@@ -0,0 +1,153 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test offline inspection and repair of the raft log.
+
+use std::fs;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_raft_store::raft_log_v004::Cw;
+use databend_meta_raft_store::raft_log_v004::LogStoreMeta;
+use databend_meta_raft_store::raft_log_v004::RaftLogInspector;
+use databend_meta_raft_store::raft_log_v004::RaftLogV004;
+use databend_meta_raft_store::raft_log_v004::Sealed;
+use databend_meta_raft_store::raft_log_v004::util::blocking_flush;
+use databend_meta_types::Cmd;
+use databend_meta_types::LogEntry;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::raft_types::EntryPayload;
+use databend_meta_types::raft_types::Vote;
+use databend_meta_types::raft_types::new_log_id;
+use raft_log::api::raft_log_writer::RaftLogWriter;
+
+fn new_config(raft_dir: &std::path::Path) -> RaftConfig {
+    RaftConfig {
+        raft_dir: raft_dir.to_str().unwrap().to_string(),
+        ..Default::default()
+    }
+}
+
+/// Write 10 entries, commit upto index 8, and return the path of the WAL file.
+async fn fill_raft_log(config: &RaftConfig) -> anyhow::Result<std::path::PathBuf> {
+    let log_config = config.to_raft_log_config();
+    fs::create_dir_all(&log_config.dir)?;
+
+    let mut log = RaftLogV004::open(Arc::new(log_config.clone()))?;
+
+    log.save_user_data(Some(LogStoreMeta { node_id: Some(3) }))?;
+    log.save_vote(Cw(Vote::new(1, 0)))?;
+
+    for i in 1..=10 {
+        let payload = if i == 5 {
+            EntryPayload::Normal(LogEntry::new(Cmd::UpsertKV(UpsertKV::update("a", b"A"))))
+        } else {
+            EntryPayload::Blank
+        };
+        log.append([(Cw(new_log_id(1, 0, i)), Sealed::Plain(payload))])?;
+    }
+    log.commit(Cw(new_log_id(1, 0, 8)))?;
+
+    blocking_flush(&mut log).await?;
+
+    for entry in fs::read_dir(&log_config.dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|x| x == "wal") {
+            return Ok(path);
+        }
+    }
+    anyhow::bail!("No WAL file found in {}", log_config.dir)
+}
+
+#[tokio::test]
+async fn test_raft_log_inspector_dump_and_truncate() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let config = new_config(temp_dir.path());
+
+    fill_raft_log(&config).await?;
+
+    let mut inspector = RaftLogInspector::open(&config)?;
+
+    let summary = inspector.summary();
+    assert_eq!(Some(3), summary.node_id);
+    assert_eq!(Some(Vote::new(1, 0)), summary.vote);
+    assert_eq!(Some(new_log_id(1, 0, 8)), summary.committed);
+    assert_eq!(None, summary.purged);
+    assert_eq!(Some(new_log_id(1, 0, 10)), summary.last);
+
+    let entries = inspector.entries(4, 100)?;
+    assert_eq!(7, entries.len());
+    assert_eq!(new_log_id(1, 0, 5), entries[1].log_id);
+    assert!(matches!(
+        &entries[1].payload,
+        EntryPayload::Normal(LogEntry {
+            cmd: Cmd::UpsertKV(_),
+            ..
+        })
+    ));
+
+    let last = inspector.truncate_after(6).await?;
+    assert_eq!(Some(new_log_id(1, 0, 6)), last);
+
+    drop(inspector);
+
+    let inspector = RaftLogInspector::open(&config)?;
+    let summary = inspector.summary();
+    assert_eq!(Some(new_log_id(1, 0, 6)), summary.committed);
+    assert_eq!(Some(new_log_id(1, 0, 6)), summary.last);
+    assert_eq!(6, inspector.entries(0, 100)?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raft_log_inspector_damaged_tail() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let config = new_config(temp_dir.path());
+
+    let wal = fill_raft_log(&config).await?;
+
+    // Simulate an incomplete write of the last record.
+    {
+        let size = fs::metadata(&wal)?.len();
+        let f = OpenOptions::new().write(true).open(&wal)?;
+        f.set_len(size - 3)?;
+        f.sync_all()?;
+    }
+
+    let res = RaftLogInspector::open(&config);
+    assert!(res.is_err(), "damaged tail is detected");
+
+    let inspector = RaftLogInspector::open_repair(&config)?;
+    let summary = inspector.summary();
+    assert!(summary.last.is_some());
+
+    drop(inspector);
+
+    RaftLogInspector::open(&config)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raft_log_inspector_no_raft_log() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let config = new_config(temp_dir.path());
+
+    let res = RaftLogInspector::open(&config);
+    assert_eq!(std::io::ErrorKind::NotFound, res.err().unwrap().kind());
+
+    Ok(())
+}
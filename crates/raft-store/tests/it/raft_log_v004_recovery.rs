@@ -27,6 +27,7 @@ use std::sync::Arc;
 use databend_meta_raft_store::raft_log_v004::Cw;
 use databend_meta_raft_store::raft_log_v004::RaftLogConfig;
 use databend_meta_raft_store::raft_log_v004::RaftLogV004;
+use databend_meta_raft_store::raft_log_v004::Sealed;
 use databend_meta_raft_store::raft_log_v004::util::blocking_flush;
 use databend_meta_types::raft_types::EntryPayload;
 use databend_meta_types::raft_types::new_log_id;
@@ -88,7 +89,7 @@ async fn test_truncation_recovery(bytes_to_truncate: u64) -> anyhow::Result<()>
 
         for i in 0..num_entries {
             let log_id = new_log_id(1, 0, i);
-            log.append([(Cw(log_id), Cw(EntryPayload::Blank))])?;
+            log.append([(Cw(log_id), Sealed::Plain(EntryPayload::Blank))])?;
         }
 
         blocking_flush(&mut log).await?;
@@ -68,6 +68,11 @@ pub trait ClientMetricsApi: Send + Sync + 'static {
 
     /// Record a gRPC client creation failure.
     fn record_make_client_fail(endpoint: &str);
+
+    /// Record the number of attempts a gRPC request took, including retries.
+    ///
+    /// It does nothing by default.
+    fn record_request_attempts(_request: &str, _attempts: u32) {}
 }
 
 pub enum TrackingData {
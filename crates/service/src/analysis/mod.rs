@@ -16,3 +16,4 @@
 
 pub mod count_prefix;
 pub mod request_histogram;
+pub mod snapshot_inspector;
@@ -0,0 +1,169 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only inspection of a snapshot without starting a meta-service node.
+
+use std::future;
+use std::io;
+use std::path::Path;
+
+use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_raft_store::leveled_store::ScopedSeqBoundedRead;
+use databend_meta_raft_store::leveled_store::db_exporter::DBExporter;
+use databend_meta_raft_store::sm_v003::SnapshotStoreV004;
+use databend_meta_raft_store::sm_v003::open_snapshot::OpenSnapshot;
+use databend_meta_raft_store::utils::prefix_right_bound;
+use databend_meta_raft_store::utils::seq_marked_to_seqv;
+use databend_meta_runtime_api::SpawnApi;
+use databend_meta_types::SeqV;
+use databend_meta_types::protobuf::KeysCount;
+use databend_meta_types::protobuf::KeysLayoutRequest;
+use databend_meta_types::raft_types::SnapshotMeta;
+use databend_meta_types::snapshot_db::DB;
+use databend_meta_types::sys_data::SysData;
+use futures::TryStreamExt;
+use futures::stream::BoxStream;
+use map_api::mvcc::ScopedSeqBoundedGet;
+use map_api::mvcc::ScopedSeqBoundedRange;
+use state_machine_api::UserKey;
+
+use crate::analysis::count_prefix::count_prefix;
+
+/// Opens a snapshot read-only and provides access to the data in it.
+///
+/// Nothing is written to the snapshot file or the raft dir.
+/// Expired keys are returned as they are stored, since no time is applied when inspecting.
+pub struct SnapshotInspector {
+    db: DB,
+}
+
+impl SnapshotInspector {
+    /// Open a snapshot file, e.g., `<raft_dir>/df_meta/V004/snapshot/<snapshot_id>.snap`.
+    ///
+    /// `config` provides the encryption key file and the rotbl settings.
+    pub fn open_file(path: impl AsRef<Path>, config: &RaftConfig) -> Result<Self, io::Error> {
+        let path = path.as_ref();
+
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid snapshot file path: {}", path.display()),
+            )
+        };
+
+        let storage_path = path.parent().ok_or_else(invalid)?;
+        let file_name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or_else(invalid)?;
+        let snapshot_id = file_name.strip_suffix(".snap").unwrap_or(file_name);
+
+        let db = DB::open_snapshot(
+            storage_path.display(),
+            file_name,
+            snapshot_id.to_string(),
+            config.to_rotbl_config(),
+            config.key_ring()?,
+        )?;
+
+        Ok(Self { db })
+    }
+
+    /// Open the latest snapshot in the `raft_dir` of `config`.
+    pub async fn open_raft_dir<SP: SpawnApi>(config: &RaftConfig) -> Result<Self, io::Error> {
+        let ss_store = SnapshotStoreV004::<SP>::new(config.clone());
+
+        // Do not let the loader create the snapshot dir.
+        let snapshot_dir = ss_store.snapshot_config().snapshot_dir();
+        if !Path::new(&snapshot_dir).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("snapshot dir not found: {}", snapshot_dir),
+            ));
+        }
+
+        let last = ss_store
+            .new_loader()
+            .load_last_snapshot()
+            .await
+            .map_err(io::Error::other)?;
+
+        let Some((_id, db)) = last else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no snapshot in: {}", snapshot_dir),
+            ));
+        };
+
+        Ok(Self { db })
+    }
+
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    pub fn snapshot_meta(&self) -> &SnapshotMeta {
+        self.db.snapshot_meta()
+    }
+
+    pub fn sys_data(&self) -> &SysData {
+        self.db.sys_data()
+    }
+
+    /// Export the [`SysData`] as a JSON string.
+    pub fn sys_data_json(&self) -> Result<String, io::Error> {
+        serde_json::to_string_pretty(self.sys_data())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Get the value of a user key.
+    pub async fn get(&self, key: &str) -> Result<Option<SeqV>, io::Error> {
+        let user_key = UserKey::new(key);
+        let marked = ScopedSeqBoundedRead(&self.db)
+            .get(user_key.clone(), u64::MAX)
+            .await?;
+
+        Ok(seq_marked_to_seqv(user_key, marked).map(|(_k, v)| v))
+    }
+
+    /// List the user keys with `prefix`, in key order.
+    pub async fn list(
+        &self,
+        prefix: &str,
+    ) -> Result<BoxStream<'static, Result<(String, SeqV), io::Error>>, io::Error> {
+        let read = ScopedSeqBoundedRead(&self.db);
+
+        let strm = if let Some(right) = prefix_right_bound(prefix) {
+            read.range(UserKey::new(prefix)..UserKey::new(right), u64::MAX)
+                .await?
+        } else {
+            read.range(UserKey::new(prefix).., u64::MAX).await?
+        };
+
+        // Skip tombstone
+        let strm =
+            strm.try_filter_map(|(k, marked)| future::ready(Ok(seq_marked_to_seqv(k, marked))));
+
+        Ok(Box::pin(strm))
+    }
+
+    /// Count the user keys by hierarchical prefix, separated by `/`.
+    pub async fn count_prefix(
+        &self,
+        layout_request: KeysLayoutRequest,
+    ) -> Result<BoxStream<'static, Result<KeysCount, io::Error>>, io::Error> {
+        let keys_stream = DBExporter::new(&self.db).export_user_keys().await?;
+        Ok(count_prefix(keys_stream, layout_request))
+    }
+}
@@ -16,6 +16,7 @@ use std::io;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::Weak;
+use std::sync::atomic::AtomicU64;
 use std::time::Instant;
 use std::time::SystemTime;
 
@@ -74,9 +75,15 @@ use tonic::metadata::MetadataMap;
 use tonic::server::NamedService;
 use watcher::watch_stream::WatchStreamSender;
 
+use crate::api::grpc::request_limiter::RequestLimiter;
+use crate::api::grpc::request_limiter::RequestPermit;
+use crate::api::grpc::request_limiter::RpcKind;
+use crate::configs::GrpcConfig;
+use crate::configs::WatchConfig;
 use crate::meta_node::meta_handle::MetaHandle;
 use crate::meta_service::watcher::DispatcherHandle;
 use crate::meta_service::watcher::WatchTypes;
+use crate::meta_service::watcher::WatcherGuard;
 use crate::metrics::InFlightRead;
 use crate::metrics::InFlightWrite;
 use crate::metrics::network_metrics;
@@ -92,6 +99,8 @@ type InFlightReadGuard = Counted<InFlightRead, ()>;
 struct InFlightRequest {
     /// Guard to track in-flight request count.
     _guard: InFlightReadGuard,
+    /// Permit from the request limiter, released when the stream completes.
+    _permit: RequestPermit,
     /// Label for `incr_stream_sent_item` metrics.
     metrics_label: &'static str,
     /// Logs stream throughput stats when dropped.
@@ -100,9 +109,10 @@ struct InFlightRequest {
 
 impl InFlightRequest {
     /// Create from request reference (extracts thread tracking automatically).
-    fn new(metrics_label: &'static str, log_label: String) -> Self {
+    fn new(metrics_label: &'static str, log_label: String, permit: RequestPermit) -> Self {
         Self {
             _guard: InFlightRead::guard(),
+            _permit: permit,
             metrics_label,
             throughput_logger: ThroughputLogger::new(log_label),
         }
@@ -117,8 +127,12 @@ impl InFlightRequest {
             // Rust 2021 closures only capture fields that are used;
             // without these references, guards would be dropped when track() returns.
             let _guard = &self._guard;
+            let _permit = &self._permit;
 
             network_metrics::incr_stream_sent_item(metrics_label);
+            if let Ok(ref it) = item {
+                network_metrics::incr_sent_bytes(it.encoded_len() as u64);
+            }
             self.throughput_logger.incr_count();
             item
         })
@@ -162,6 +176,10 @@ impl Drop for ThroughputLogger {
 pub struct MetaServiceImpl<SP: SpawnApi> {
     token: GrpcToken,
     version: Version,
+    limiter: RequestLimiter,
+    watch_config: WatchConfig,
+    /// Number of watch streams served by this node.
+    watchers: Arc<AtomicU64>,
     /// MetaServiceImpl is not dropped if there is an alive connection.
     ///
     /// Thus make the reference to [`MetaNode`] a Weak reference so that it does not prevent [`MetaNode`] to be dropped
@@ -179,10 +197,17 @@ impl<SP: SpawnApi> Drop for MetaServiceImpl<SP> {
 }
 
 impl<SP: SpawnApi> MetaServiceImpl<SP> {
-    pub fn create(version: Version, meta_handle: Weak<MetaHandle<SP>>) -> Self {
+    pub fn create(
+        version: Version,
+        meta_handle: Weak<MetaHandle<SP>>,
+        config: &GrpcConfig,
+    ) -> Self {
         Self {
             token: GrpcToken::create(),
             version,
+            limiter: RequestLimiter::new(config.request_limit.clone()),
+            watch_config: config.watch.clone(),
+            watchers: Arc::new(AtomicU64::new(0)),
             meta_handle,
         }
     }
@@ -207,10 +232,30 @@ impl<SP: SpawnApi> MetaServiceImpl<SP> {
         Ok(claim)
     }
 
-    #[fastrace::trace]
-    async fn handle_kv_api(&self, request: Request<RaftRequest>) -> Result<RaftReply, Status> {
-        let req: MetaGrpcReq = request.try_into()?;
+    /// Acquire a permit from the request limiter for the client sending this request.
+    ///
+    /// A client is identified by the user name, if authenticated, and the IP address.
+    fn acquire_permit<T>(
+        &self,
+        kind: RpcKind,
+        request: &Request<T>,
+        claim: Option<&GrpcClaim>,
+    ) -> Result<RequestPermit, Status> {
+        let ip = request
+            .remote_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let client = match claim {
+            Some(claim) => format!("{}@{}", claim.username, ip),
+            None => ip,
+        };
+
+        self.limiter.acquire(kind, &client)
+    }
 
+    #[fastrace::trace]
+    async fn handle_kv_api(&self, req: MetaGrpcReq) -> Result<RaftReply, Status> {
         let meta_handle = self.try_get_meta_handle()?;
         let id = meta_handle.id;
 
@@ -229,6 +274,13 @@ impl<SP: SpawnApi> MetaServiceImpl<SP> {
                     id, req, res
                 );
                 let res = res?;
+
+                let limit_exceeded = res.as_ref().err().and_then(|e| e.write_limit_exceeded());
+                if let Some(e) = limit_exceeded {
+                    network_metrics::incr_request_result(false);
+                    return Err(Status::invalid_argument(e.to_string()));
+                }
+
                 // TODO: the MetaApiError should be converted to Status
                 RaftReply::from(res)
             }
@@ -315,6 +367,16 @@ impl<SP: SpawnApi> MetaServiceImpl<SP> {
             Ok((endpoint, txn_reply)) => (endpoint, txn_reply),
             Err(err) => {
                 network_metrics::incr_request_result(false);
+
+                // Not retryable: the client must free space or raise the quota.
+                if let Some(e) = err.quota_exceeded() {
+                    return Err(Status::resource_exhausted(e.to_string()));
+                }
+
+                if let Some(e) = err.write_limit_exceeded() {
+                    return Err(Status::invalid_argument(e.to_string()));
+                }
+
                 error!("txn request failed: {:?}", err);
                 return Err(Status::internal(err.to_string()));
             }
@@ -376,9 +438,16 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
                 .try_create_token(claim)
                 .map_err(|e| Status::internal(e.to_string()))?;
 
+            let features = databend_meta_version::spec()
+                .server_features_at(self.version)
+                .into_iter()
+                .map(|f| f.as_str().to_string())
+                .collect();
+
             let resp = HandshakeResponse {
                 protocol_version: self.version.to_digit(),
                 payload: token.into_bytes(),
+                features,
             };
             let output = futures::stream::once(async { Ok(resp) });
 
@@ -393,16 +462,19 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
     }
 
     async fn kv_api(&self, request: Request<RaftRequest>) -> Result<Response<RaftReply>, Status> {
-        self.check_token(request.metadata())?;
+        let claim = self.check_token(request.metadata())?;
+
+        let req: MetaGrpcReq = GrpcHelper::parse(&request.get_ref().data)?;
+        let _permit = self.acquire_permit(RpcKind::of_kv_api(&req), &request, Some(&claim))?;
 
         network_metrics::incr_recv_bytes(request.get_ref().encoded_len() as u64);
         let query_id = get_query_id(&request).map(|s| s.to_owned());
 
-        SP::trace_request(func_path!(), request, |request| async move {
+        SP::trace_request(func_path!(), request, |_request| async move {
             let fu = async move {
                 let _guard = InFlightWrite::guard();
 
-                let reply = self.handle_kv_api(request).await?;
+                let reply = self.handle_kv_api(req).await?;
 
                 network_metrics::incr_sent_bytes(reply.encoded_len() as u64);
 
@@ -420,15 +492,19 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<RaftRequest>,
     ) -> Result<Response<Self::KvReadV1Stream>, Status> {
-        self.check_token(request.metadata())?;
+        let claim = self.check_token(request.metadata())?;
+        let permit = self.acquire_permit(RpcKind::Read, &request, Some(&claim))?;
 
         network_metrics::incr_recv_bytes(request.get_ref().encoded_len() as u64);
         let query_id = get_query_id(&request).map(|s| s.to_owned());
 
         SP::trace_request(func_path!(), request, |request| async move {
             let req: MetaGrpcReadReq = GrpcHelper::parse_req(request)?;
-            let in_flight =
-                InFlightRequest::new(req.type_name(), format!("ReadRequest: {:?}", req));
+            let in_flight = InFlightRequest::new(
+                req.type_name(),
+                format!("ReadRequest: {:?}", req),
+                permit,
+            );
 
             let fut = async {
                 let (endpoint, strm) = self.handle_kv_read_v1(req).await?;
@@ -456,7 +532,8 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<KvListRequest>,
     ) -> Result<Response<Self::KvListStream>, Status> {
-        self.check_token(request.metadata())?;
+        let claim = self.check_token(request.metadata())?;
+        let permit = self.acquire_permit(RpcKind::Read, &request, Some(&claim))?;
 
         network_metrics::incr_recv_bytes(request.get_ref().encoded_len() as u64);
         let query_id = get_query_id(&request).map(|s| s.to_owned());
@@ -469,6 +546,7 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
                     request.get_ref().prefix,
                     request.get_ref().limit.display()
                 ),
+                permit,
             );
             let req = request.into_inner();
 
@@ -495,12 +573,13 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<Streaming<KvGetManyRequest>>,
     ) -> Result<Response<Self::KvGetManyStream>, Status> {
-        self.check_token(request.metadata())?;
+        let claim = self.check_token(request.metadata())?;
+        let permit = self.acquire_permit(RpcKind::Read, &request, Some(&claim))?;
 
         let query_id = get_query_id(&request).map(|s| s.to_owned());
 
         SP::trace_request(func_path!(), request, |request| async move {
-            let in_flight = InFlightRequest::new("kv_get_many", "KvGetMany".to_string());
+            let in_flight = InFlightRequest::new("kv_get_many", "KvGetMany".to_string(), permit);
             let input = request
                 .into_inner()
                 .inspect_ok(|req| network_metrics::incr_recv_bytes(req.encoded_len() as u64));
@@ -521,7 +600,9 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<TxnRequest>,
     ) -> Result<Response<TxnReply>, Status> {
-        self.check_token(request.metadata())?;
+        let claim = self.check_token(request.metadata())?;
+        let kind = RpcKind::of_txn(request.get_ref());
+        let _permit = self.acquire_permit(kind, &request, Some(&claim))?;
 
         let query_id = get_query_id(&request).map(|s| s.to_owned());
 
@@ -553,8 +634,9 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
     /// The exported data is a series of JSON encoded strings of `RaftStoreEntry`.
     async fn export(
         &self,
-        _request: Request<databend_meta_types::protobuf::Empty>,
+        request: Request<databend_meta_types::protobuf::Empty>,
     ) -> Result<Response<Self::ExportStream>, Status> {
+        let permit = self.acquire_permit(RpcKind::Export, &request, None)?;
         let guard = InFlightRead::guard();
 
         let meta_handle = self.try_get_meta_handle()?;
@@ -567,7 +649,9 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         // - Convert TryChunkError<_, io::Error> to Status;
         let s = strm
             .map(move |x| {
-                let _g = &guard; // hold the guard until the stream is done.
+                // hold the guard and permit until the stream is done.
+                let _g = &guard;
+                let _p = &permit;
                 x
             })
             .try_chunks(chunk_size)
@@ -588,6 +672,7 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<pb::ExportRequest>,
     ) -> Result<Response<Self::ExportV1Stream>, Status> {
+        let permit = self.acquire_permit(RpcKind::Export, &request, None)?;
         let guard = InFlightRead::guard();
 
         let meta_handle = self.try_get_meta_handle()?;
@@ -600,7 +685,9 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         // - Convert TryChunkError<_, io::Error> to Status;
         let s = strm
             .map(move |x| {
-                let _g = &guard; // hold the guard until the stream is done.
+                // hold the guard and permit until the stream is done.
+                let _g = &guard;
+                let _p = &permit;
                 x
             })
             .try_chunks(chunk_size)
@@ -617,6 +704,7 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<KeysLayoutRequest>,
     ) -> Result<Response<Self::SnapshotKeysLayoutStream>, Status> {
+        let permit = self.acquire_permit(RpcKind::Export, &request, None)?;
         let guard = InFlightRead::guard();
 
         let layout_request = request.into_inner();
@@ -628,7 +716,9 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
             .await??;
 
         let s = strm.map(move |x| {
-            let _g = &guard; // hold the guard until the stream is done.
+            // hold the guard and permit until the stream is done.
+            let _g = &guard;
+            let _p = &permit;
             x.map_err(|e| Status::internal(e.to_string()))
         });
 
@@ -642,19 +732,37 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
         &self,
         request: Request<WatchRequest>,
     ) -> Result<Response<Self::WatchStream>, Status> {
+        let permit = self.acquire_permit(RpcKind::Watch, &request, None)?;
+
         let watch = request.into_inner();
 
+        let guard = WatcherGuard::try_new(
+            &self.watchers,
+            self.watch_config.max_watchers,
+            &watch.key,
+        )?;
+
         let meta_handle = self.try_get_meta_handle()?;
-        let stream = meta_handle.handle_watch(watch).await??;
+        let stream = meta_handle
+            .handle_watch(watch, self.watch_config.clone())
+            .await??;
+
+        let stream = stream.map(move |x| {
+            // hold the permit and guard until the stream is done.
+            let _p = &permit;
+            let _g = &guard;
+            x
+        });
 
-        Ok(Response::new(stream as Self::WatchStream))
+        Ok(Response::new(Box::pin(stream) as Self::WatchStream))
     }
 
     async fn member_list(
         &self,
         request: Request<MemberListRequest>,
     ) -> Result<Response<MemberListReply>, Status> {
-        self.check_token(request.metadata())?;
+        let claim = self.check_token(request.metadata())?;
+        let _permit = self.acquire_permit(RpcKind::Read, &request, Some(&claim))?;
 
         let _guard = InFlightRead::guard();
 
@@ -685,6 +793,7 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
             binary_version: status.binary_version,
             data_version: status.data_version.to_string(),
             endpoint: status.endpoint.unwrap_or_default(),
+            labels: status.labels,
 
             raft_log_size: status.raft_log.wal_total_size,
 
@@ -718,6 +827,19 @@ impl<SP: SpawnApi> MetaService for MetaServiceImpl<SP> {
             voters: status.voters.iter().map(|n| n.to_string()).collect(),
             non_voters: status.non_voters.iter().map(|n| n.to_string()).collect(),
             last_seq: status.last_seq,
+            prefix_quotas: status
+                .prefix_quotas
+                .into_iter()
+                .map(|(prefix, st)| {
+                    let q = pb::PrefixQuotaStatus {
+                        max_keys: st.quota.max_keys,
+                        max_bytes: st.quota.max_bytes,
+                        keys: st.usage.keys,
+                        bytes: st.usage.bytes,
+                    };
+                    (prefix, q)
+                })
+                .collect(),
         };
         Ok(Response::new(resp))
     }
@@ -13,3 +13,5 @@
 // limitations under the License.
 
 pub mod grpc_service;
+pub mod request_limiter;
+pub mod wire_counted;
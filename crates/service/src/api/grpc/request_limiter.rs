@@ -0,0 +1,464 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-client rate limits and in-flight limits of the gRPC API.
+
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::ops::Bound;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use databend_meta_client::MetaGrpcReq;
+use databend_meta_types::TxnRequest;
+use log::warn;
+use tonic::Status;
+use tonic::metadata::MetadataValue;
+
+use crate::configs::RequestLimitConfig;
+use crate::configs::RpcLimit;
+use crate::metrics::network_metrics;
+
+/// The response metadata key of the hint in milliseconds for the client to wait before retrying.
+pub const RETRY_AFTER_MS_KEY: &str = "retry-after-ms";
+
+/// The retry hint when a request is rejected because of too many in-flight requests.
+const IN_FLIGHT_RETRY_AFTER: Duration = Duration::from_millis(100);
+
+/// The number of independently locked shards of client states.
+const SHARDS: usize = 16;
+
+/// When the number of clients tracked by a shard exceeds this, idle clients are evicted.
+const MAX_TRACKED_CLIENTS_PER_SHARD: usize = 256;
+
+/// The number of client states examined for eviction on each request.
+///
+/// Eviction is incremental so that a request never scans all tracked clients.
+const EVICT_SCAN: usize = 8;
+
+/// The kind of RPC a limit applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RpcKind {
+    Read,
+    Write,
+    Watch,
+    Export,
+}
+
+impl RpcKind {
+    /// The kind of a request sent via the `kv_api` RPC.
+    pub fn of_kv_api(req: &MetaGrpcReq) -> Self {
+        match req {
+            MetaGrpcReq::UpsertKV(_) => RpcKind::Write,
+        }
+    }
+
+    /// The kind of a transaction: a transaction without write operations in any branch is a read.
+    pub fn of_txn(txn: &TxnRequest) -> Self {
+        if txn.is_read_only() {
+            RpcKind::Read
+        } else {
+            RpcKind::Write
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RpcKind::Read => "read",
+            RpcKind::Write => "write",
+            RpcKind::Watch => "watch",
+            RpcKind::Export => "export",
+        }
+    }
+}
+
+impl fmt::Display for RpcKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A token bucket that refills `rate_per_sec` tokens per second, up to `capacity` tokens.
+#[derive(Debug)]
+struct TokenBucket {
+    rate_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate_per_sec: u64, burst: u64, now: Instant) -> Self {
+        let capacity = if burst == 0 { rate_per_sec } else { burst };
+        let capacity = capacity.max(1) as f64;
+
+        Self {
+            rate_per_sec: rate_per_sec as f64,
+            capacity,
+            tokens: capacity,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.capacity);
+        self.updated_at = now;
+    }
+
+    /// Take a token, or return how long to wait until a token is available.
+    fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        if self.rate_per_sec <= 0.0 {
+            return Err(Duration::MAX);
+        }
+
+        let wait = (1.0 - self.tokens) / self.rate_per_sec;
+        Err(Duration::from_secs_f64(wait))
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+}
+
+/// The limit state of one kind of RPC from one client.
+#[derive(Debug)]
+struct ClientState {
+    bucket: Option<TokenBucket>,
+    in_flight: Arc<AtomicU64>,
+}
+
+impl ClientState {
+    fn new(limit: &RpcLimit, now: Instant) -> Self {
+        Self {
+            bucket: limit
+                .rate_per_sec
+                .map(|rate| TokenBucket::new(rate, limit.burst, now)),
+            in_flight: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// An idle state is equivalent to a newly created one and can be evicted.
+    fn is_idle(&mut self, now: Instant) -> bool {
+        self.in_flight.load(Ordering::Relaxed) == 0
+            && self.bucket.as_mut().is_none_or(|b| b.is_full(now))
+    }
+}
+
+/// Keeps a request counted as in-flight until dropped.
+#[derive(Debug)]
+pub struct RequestPermit {
+    in_flight: Option<Arc<AtomicU64>>,
+}
+
+impl RequestPermit {
+    fn unlimited() -> Self {
+        Self { in_flight: None }
+    }
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        if let Some(in_flight) = self.in_flight.take() {
+            in_flight.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+type ClientKey = (RpcKind, String);
+
+/// A subset of the client states, protected by its own lock.
+#[derive(Debug, Default)]
+struct Shard {
+    clients: BTreeMap<ClientKey, ClientState>,
+
+    /// The key after which the next eviction scan starts.
+    evict_cursor: Option<ClientKey>,
+}
+
+impl Shard {
+    /// Examine at most `EVICT_SCAN` client states following the cursor and remove the idle ones.
+    fn evict_idle(&mut self, now: Instant) {
+        let start = match self.evict_cursor.take() {
+            Some(k) => Bound::Excluded(k),
+            None => Bound::Unbounded,
+        };
+
+        let mut keys: Vec<ClientKey> = self
+            .clients
+            .range((start, Bound::Unbounded))
+            .take(EVICT_SCAN)
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        // Reached the end: wrap around to the beginning.
+        if keys.len() < EVICT_SCAN {
+            let more: Vec<ClientKey> = self
+                .clients
+                .keys()
+                .take(EVICT_SCAN - keys.len())
+                .filter(|k| !keys.contains(k))
+                .cloned()
+                .collect();
+            keys.extend(more);
+        }
+
+        self.evict_cursor = keys.last().cloned();
+
+        for k in keys {
+            if self.clients.get_mut(&k).is_some_and(|st| st.is_idle(now)) {
+                self.clients.remove(&k);
+            }
+        }
+    }
+}
+
+/// Limits the request rate and the in-flight requests for each client and kind of RPC.
+#[derive(Debug)]
+pub struct RequestLimiter {
+    config: RequestLimitConfig,
+    shards: Vec<Mutex<Shard>>,
+}
+
+impl RequestLimiter {
+    pub fn new(config: RequestLimitConfig) -> Self {
+        Self {
+            config,
+            shards: (0..SHARDS).map(|_| Mutex::new(Shard::default())).collect(),
+        }
+    }
+
+    fn shard(&self, key: &ClientKey) -> &Mutex<Shard> {
+        let mut h = DefaultHasher::new();
+        key.hash(&mut h);
+        &self.shards[(h.finish() as usize) % self.shards.len()]
+    }
+
+    fn limit(&self, kind: RpcKind) -> &RpcLimit {
+        match kind {
+            RpcKind::Read => &self.config.read,
+            RpcKind::Write => &self.config.write,
+            RpcKind::Watch => &self.config.watch,
+            RpcKind::Export => &self.config.export,
+        }
+    }
+
+    /// Acquire a permit for a request of `kind` from `client`.
+    ///
+    /// Returns `ResourceExhausted` with a retry hint in metadata if a limit is exceeded.
+    pub fn acquire(&self, kind: RpcKind, client: &str) -> Result<RequestPermit, Status> {
+        let limit = self.limit(kind);
+        if limit.is_unlimited() {
+            return Ok(RequestPermit::unlimited());
+        }
+
+        let now = Instant::now();
+        let key = (kind, client.to_string());
+
+        let mut shard = self.shard(&key).lock().unwrap();
+
+        if shard.clients.len() >= MAX_TRACKED_CLIENTS_PER_SHARD {
+            shard.evict_idle(now);
+        }
+
+        let st = shard
+            .clients
+            .entry(key)
+            .or_insert_with(|| ClientState::new(limit, now));
+
+        if let Some(max) = limit.max_in_flight {
+            if st.in_flight.load(Ordering::Relaxed) >= max {
+                return Err(exhausted(
+                    kind,
+                    client,
+                    format!("in-flight requests reach limit {}", max),
+                    IN_FLIGHT_RETRY_AFTER,
+                ));
+            }
+        }
+
+        if let Some(bucket) = &mut st.bucket {
+            bucket.try_acquire(now).map_err(|wait| {
+                exhausted(
+                    kind,
+                    client,
+                    format!("rate exceeds limit {}/s", bucket.rate_per_sec),
+                    wait,
+                )
+            })?;
+        }
+
+        st.in_flight.fetch_add(1, Ordering::Relaxed);
+
+        Ok(RequestPermit {
+            in_flight: Some(st.in_flight.clone()),
+        })
+    }
+}
+
+fn exhausted(kind: RpcKind, client: &str, reason: String, retry_after: Duration) -> Status {
+    warn!(
+        "reject {} request from {}: {}; retry after {:?}",
+        kind, client, reason, retry_after
+    );
+
+    network_metrics::incr_request_limited(kind.as_str());
+
+    let retry_after_ms = retry_after.as_millis().min(u64::MAX as u128) as u64;
+
+    let mut status = Status::resource_exhausted(format!(
+        "{} request from {} is rejected: {}; retry after {} ms",
+        kind, client, reason, retry_after_ms
+    ));
+    status
+        .metadata_mut()
+        .insert(RETRY_AFTER_MS_KEY, MetadataValue::from(retry_after_ms));
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use databend_meta_types::TxnOp;
+
+    use super::*;
+
+    fn limiter(read: RpcLimit) -> RequestLimiter {
+        RequestLimiter::new(RequestLimitConfig {
+            read,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut b = TokenBucket::new(4, 2, now);
+
+        assert!(b.try_acquire(now).is_ok());
+        assert!(b.try_acquire(now).is_ok());
+
+        let wait = b.try_acquire(now).unwrap_err();
+        assert_eq!(Duration::from_millis(250), wait);
+
+        assert!(b.try_acquire(now + Duration::from_millis(250)).is_ok());
+        assert!(!b.is_full(now + Duration::from_millis(250)));
+        assert!(b.is_full(now + Duration::from_millis(750)));
+
+        // burst defaults to rate
+        let b = TokenBucket::new(10, 0, now);
+        assert_eq!(10.0, b.capacity);
+    }
+
+    #[test]
+    fn test_request_limiter_rate() {
+        let l = limiter(RpcLimit {
+            rate_per_sec: Some(1),
+            burst: 2,
+            max_in_flight: None,
+        });
+
+        assert!(l.acquire(RpcKind::Read, "a").is_ok());
+        assert!(l.acquire(RpcKind::Read, "a").is_ok());
+
+        let err = l.acquire(RpcKind::Read, "a").unwrap_err();
+        assert_eq!(tonic::Code::ResourceExhausted, err.code());
+        assert!(err.metadata().get(RETRY_AFTER_MS_KEY).is_some());
+
+        // Other clients and other kinds are not affected.
+        assert!(l.acquire(RpcKind::Read, "b").is_ok());
+        assert!(l.acquire(RpcKind::Write, "a").is_ok());
+    }
+
+    #[test]
+    fn test_request_limiter_in_flight() {
+        let l = limiter(RpcLimit {
+            rate_per_sec: None,
+            burst: 0,
+            max_in_flight: Some(1),
+        });
+
+        let permit = l.acquire(RpcKind::Read, "a").unwrap();
+
+        let err = l.acquire(RpcKind::Read, "a").unwrap_err();
+        assert_eq!(tonic::Code::ResourceExhausted, err.code());
+        assert_eq!(
+            "100",
+            err.metadata().get(RETRY_AFTER_MS_KEY).unwrap().to_str().unwrap()
+        );
+
+        drop(permit);
+        assert!(l.acquire(RpcKind::Read, "a").is_ok());
+    }
+
+    #[test]
+    fn test_shard_evict_idle() {
+        let now = Instant::now();
+        let limit = RpcLimit {
+            rate_per_sec: None,
+            burst: 0,
+            max_in_flight: Some(1),
+        };
+
+        let mut shard = Shard::default();
+        for i in 0..20 {
+            let st = ClientState::new(&limit, now);
+            if i % 2 == 0 {
+                st.in_flight.fetch_add(1, Ordering::Relaxed);
+            }
+            shard
+                .clients
+                .insert((RpcKind::Read, format!("c{:02}", i)), st);
+        }
+
+        // Each call examines only `EVICT_SCAN` entries.
+        shard.evict_idle(now);
+        assert_eq!(20 - EVICT_SCAN / 2, shard.clients.len());
+
+        // Repeated calls go around and evict all idle clients.
+        for _ in 0..4 {
+            shard.evict_idle(now);
+        }
+        assert_eq!(10, shard.clients.len());
+        assert!(
+            shard
+                .clients
+                .values()
+                .all(|st| st.in_flight.load(Ordering::Relaxed) == 1)
+        );
+    }
+
+    #[test]
+    fn test_rpc_kind_of_txn() {
+        let read = TxnRequest::default();
+        assert_eq!(RpcKind::Read, RpcKind::of_txn(&read));
+
+        let write = TxnRequest::new(vec![], vec![TxnOp::put("k", b"v".to_vec())]);
+        assert_eq!(RpcKind::Write, RpcKind::of_txn(&write));
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Count the bytes transferred on a connection, i.e., after compression.
+//!
+//! Compared with the metrics of message sizes, it tells how effective the gRPC compression is.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::ReadBuf;
+use tonic::transport::server::Connected;
+
+type OnBytes = Arc<dyn Fn(u64) + Send + Sync>;
+
+/// A connection that reports the number of bytes read from and written to it.
+pub struct WireCounted<T> {
+    inner: T,
+    on_recv: OnBytes,
+    on_sent: OnBytes,
+}
+
+impl<T> WireCounted<T> {
+    pub fn new(
+        inner: T,
+        on_recv: impl Fn(u64) + Send + Sync + 'static,
+        on_sent: impl Fn(u64) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            on_recv: Arc::new(on_recv),
+            on_sent: Arc::new(on_sent),
+        }
+    }
+}
+
+impl<T: Connected> Connected for WireCounted<T> {
+    type ConnectInfo = T::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.inner.connect_info()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for WireCounted<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+
+        let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = res {
+            let n = buf.filled().len() - before;
+            if n > 0 {
+                (self.on_recv)(n as u64);
+            }
+        }
+        res
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for WireCounted<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            (self.on_sent)(n as u64);
+        }
+        res
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(n)) = res {
+            (self.on_sent)(n as u64);
+        }
+        res
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wire_counted() -> anyhow::Result<()> {
+        let recv = Arc::new(AtomicU64::new(0));
+        let sent = Arc::new(AtomicU64::new(0));
+
+        let (a, mut b) = tokio::io::duplex(64);
+
+        let mut a = {
+            let recv = recv.clone();
+            let sent = sent.clone();
+            WireCounted::new(
+                a,
+                move |n| {
+                    recv.fetch_add(n, Ordering::Relaxed);
+                },
+                move |n| {
+                    sent.fetch_add(n, Ordering::Relaxed);
+                },
+            )
+        };
+
+        a.write_all(b"hello").await?;
+        b.write_all(b"foo").await?;
+
+        let mut buf = [0u8; 3];
+        a.read_exact(&mut buf).await?;
+
+        assert_eq!(3, recv.load(Ordering::Relaxed));
+        assert_eq!(5, sent.load(Ordering::Relaxed));
+        Ok(())
+    }
+}
@@ -20,12 +20,14 @@ use anyerror::AnyError;
 use databend_base::shutdown::Graceful;
 use databend_meta_runtime_api::JoinHandle;
 use databend_meta_runtime_api::SpawnApi;
+use databend_meta_types::GrpcCompression;
 use databend_meta_types::MetaNetworkError;
 use databend_meta_types::protobuf::FILE_DESCRIPTOR_SET;
 use databend_meta_types::protobuf::meta_service_server::MetaServiceServer;
 use databend_meta_version::Version;
 use databend_meta_version::version;
 use fastrace::prelude::*;
+use futures::TryStreamExt;
 use futures::future::BoxFuture;
 use futures::future::Either;
 use futures::future::select;
@@ -38,9 +40,11 @@ use tonic::transport::ServerTlsConfig;
 use tonic::transport::server::TcpIncoming;
 
 use crate::api::grpc::grpc_service::MetaServiceImpl;
+use crate::api::grpc::wire_counted::WireCounted;
 use crate::configs::MetaServiceConfig;
 use crate::meta_node::meta_handle::MetaHandle;
 use crate::meta_service::MetaNode;
+use crate::metrics::network_metrics;
 use crate::util::DropDebug;
 
 pub struct GrpcServer<SP: SpawnApi> {
@@ -136,7 +140,12 @@ impl<SP: SpawnApi> GrpcServer<SP> {
         // Configure HTTP/2 settings to handle stream reset accumulation.
         // The default limit (20) can be too low under high concurrency.
         // Setting to None disables the limit entirely.
-        let builder = Server::builder().http2_max_pending_accept_reset_streams(Some(4096));
+        let mut builder = Server::builder().http2_max_pending_accept_reset_streams(Some(4096));
+
+        if let Some(limit) = self.config.grpc.request_limit.concurrency_limit_per_connection {
+            info!("gRPC concurrency limit per connection: {}", limit);
+            builder = builder.concurrency_limit_per_connection(limit);
+        }
 
         let tls_conf = Self::tls_config(&self.config.grpc)
             .await
@@ -154,12 +163,25 @@ impl<SP: SpawnApi> GrpcServer<SP> {
 
         info!("start gRPC listening: {}", addr);
 
-        let grpc_impl = MetaServiceImpl::create(self.version, Arc::downgrade(&meta_handle));
+        let grpc_impl = MetaServiceImpl::create(
+            self.version,
+            Arc::downgrade(&meta_handle),
+            &self.config.grpc,
+        );
         let max_msg_size = self.config.grpc.max_message_size();
-        let grpc_srv = MetaServiceServer::new(grpc_impl)
+        let mut grpc_srv = MetaServiceServer::new(grpc_impl)
             .max_decoding_message_size(max_msg_size)
             .max_encoding_message_size(max_msg_size);
 
+        for encoding in GrpcCompression::ACCEPTED {
+            grpc_srv = grpc_srv.accept_compressed(encoding);
+        }
+
+        if let Some(encoding) = self.config.grpc.compression.encoding() {
+            info!("gRPC response compression: {}", self.config.grpc.compression);
+            grpc_srv = grpc_srv.send_compressed(encoding);
+        }
+
         let router = builder.add_service(reflect_srv).add_service(grpc_srv);
 
         let id = self.config.raft_config.id;
@@ -177,6 +199,14 @@ impl<SP: SpawnApi> GrpcServer<SP> {
         let fu = async move {
             let _d = DropDebug::new(format!("GrpcServer(id={}) spawned service task", id));
 
+            let incoming = incoming.map_ok(|io| {
+                WireCounted::new(
+                    io,
+                    network_metrics::incr_wire_recv_bytes,
+                    network_metrics::incr_wire_sent_bytes,
+                )
+            });
+
             let res = router
                 .serve_with_incoming_shutdown(incoming, shutdown_fut)
                 .await;
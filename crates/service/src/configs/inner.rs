@@ -15,6 +15,7 @@
 use std::net::SocketAddr;
 
 use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_types::GrpcCompression;
 use databend_meta_types::MetaStartupError;
 use databend_meta_types::node::Node;
 
@@ -40,6 +41,108 @@ impl TlsConfig {
     }
 }
 
+/// Limits of one kind of RPC requests from one client.
+///
+/// A client is identified by the user name and the IP address of the connection.
+#[derive(Clone, Debug, PartialEq, Eq, Default, serde::Serialize)]
+pub struct RpcLimit {
+    /// Max number of requests per second a client can send, with a token-bucket.
+    ///
+    /// `None` means no rate limit.
+    pub rate_per_sec: Option<u64>,
+
+    /// Max number of requests a client can send in a burst.
+    ///
+    /// It is the capacity of the token-bucket. Defaults to `rate_per_sec` if it is 0.
+    pub burst: u64,
+
+    /// Max number of requests from a client being processed concurrently.
+    ///
+    /// For streaming RPCs a request is in flight until the response stream is dropped.
+    /// `None` means no limit.
+    pub max_in_flight: Option<u64>,
+}
+
+impl RpcLimit {
+    /// Returns `true` if neither rate limit nor in-flight limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.rate_per_sec.is_none() && self.max_in_flight.is_none()
+    }
+
+    /// A rate of 0 would never refill the token-bucket: use `None` for no limit instead.
+    fn validate(&self, name: &str) -> Result<(), MetaStartupError> {
+        if self.rate_per_sec == Some(0) {
+            return Err(MetaStartupError::InvalidConfig(format!(
+                "request limit {}: rate_per_sec must be greater than 0",
+                name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Request limits of the gRPC API, by kind of RPC.
+///
+/// A request that exceeds a limit is rejected with `ResourceExhausted`,
+/// with a retry hint in the response metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Default, serde::Serialize)]
+pub struct RequestLimitConfig {
+    /// Limits of read RPCs: `kv_read_v1`, `kv_list`, `kv_get_many` and `member_list`.
+    pub read: RpcLimit,
+
+    /// Limits of write RPCs: `kv_api` and `transaction`.
+    pub write: RpcLimit,
+
+    /// Limits of `watch` RPC.
+    pub watch: RpcLimit,
+
+    /// Limits of `export`, `export_v1` and `snapshot_keys_layout` RPCs.
+    pub export: RpcLimit,
+
+    /// Max number of concurrent requests on one connection.
+    ///
+    /// `None` means no limit.
+    pub concurrency_limit_per_connection: Option<usize>,
+}
+
+impl RequestLimitConfig {
+    pub fn validate(&self) -> Result<(), MetaStartupError> {
+        self.read.validate("read")?;
+        self.write.validate("write")?;
+        self.watch.validate("watch")?;
+        self.export.validate("export")?;
+        Ok(())
+    }
+}
+
+/// Limits of watch streams and the policy for a consumer that stops reading.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct WatchConfig {
+    /// Max number of watch streams on this node.
+    ///
+    /// The max number of watch streams of a single client is limited by
+    /// [`RequestLimitConfig::watch`]`.max_in_flight`.
+    /// `None` means no limit.
+    pub max_watchers: Option<u64>,
+
+    /// Number of events buffered for a watch stream.
+    ///
+    /// When an event arrives at a full buffer, the watch stream is considered lagged:
+    /// it is terminated with a `DataLoss` status
+    /// and the client has to re-establish the watch and resync.
+    /// The initial flush waits for the consumer instead and is never considered lagged.
+    pub buffer_size: usize,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            max_watchers: None,
+            buffer_size: 64,
+        }
+    }
+}
+
 /// Configuration for the gRPC API server.
 ///
 /// This struct holds settings for the gRPC endpoint that serves client requests,
@@ -68,6 +171,19 @@ pub struct GrpcConfig {
     /// Used for both encoding and decoding limits on the gRPC API server.
     /// Default: 32MB (33,554,432 bytes).
     pub max_message_size: Option<usize>,
+
+    /// Rate limits and in-flight limits of the requests.
+    pub request_limit: RequestLimitConfig,
+
+    /// Limits of watch streams.
+    pub watch: WatchConfig,
+
+    /// Compression of the responses sent to clients, such as large range and watch streams.
+    ///
+    /// A response is compressed only if the client accepts the compression.
+    /// Compressed requests are accepted regardless of this setting.
+    /// Default: none.
+    pub compression: GrpcCompression,
 }
 
 impl Default for GrpcConfig {
@@ -78,6 +194,9 @@ impl Default for GrpcConfig {
             advertise_host: None,
             tls: TlsConfig::default(),
             max_message_size: None,
+            request_limit: RequestLimitConfig::default(),
+            watch: WatchConfig::default(),
+            compression: GrpcCompression::None,
         }
     }
 }
@@ -104,6 +223,9 @@ impl GrpcConfig {
             advertise_host: Some(host),
             tls: TlsConfig::default(),
             max_message_size: None,
+            request_limit: RequestLimitConfig::default(),
+            watch: WatchConfig::default(),
+            compression: GrpcCompression::None,
         }
     }
 
@@ -156,6 +278,8 @@ impl MetaServiceConfig {
                 MetaStartupError::InvalidConfig(format!("{} while parsing {}", e, addr))
             })?;
         }
+
+        self.grpc.request_limit.validate()?;
         Ok(())
     }
 
@@ -166,5 +290,7 @@ impl MetaServiceConfig {
             self.raft_config.raft_api_advertise_host_endpoint(),
         )
         .with_grpc_advertise_address(self.grpc.advertise_address())
+        .with_labels(self.raft_config.node_labels.clone())
+        .with_binary_version(Some(databend_meta_version::version()))
     }
 }
@@ -14,7 +14,11 @@
 
 mod inner;
 
+pub use databend_meta_raft_store::config::WriteLimitConfig;
 pub use inner::AdminConfig;
 pub use inner::GrpcConfig;
 pub use inner::MetaServiceConfig;
+pub use inner::RequestLimitConfig;
+pub use inner::RpcLimit;
 pub use inner::TlsConfig;
+pub use inner::WatchConfig;
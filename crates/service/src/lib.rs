@@ -32,6 +32,7 @@ pub mod meta_node;
 pub mod meta_service;
 pub mod metrics;
 pub mod network;
+pub mod raft_auth;
 pub mod raft_client;
 pub(crate) mod request_handling;
 pub mod store;
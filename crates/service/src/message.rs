@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+
 use anyerror::AnyError;
 use databend_meta_client::MetaGrpcReadReq;
 use databend_meta_kvapi::kvapi::GetKVReply;
@@ -42,6 +44,19 @@ pub struct JoinRequest {
     /// Valid role: "voter", "learner".
     /// A learner node does not participate in voting.
     pub role: Option<String>,
+
+    /// Placement labels of the joining node, such as `zone` and `rack`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
+    /// The binary version of the joining node, such as `260205.3.0`.
+    ///
+    /// The leader rejects the node if it can not work with the cluster.
+    /// `None` if the joining node is older than this field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_version: Option<String>,
 }
 
 impl JoinRequest {
@@ -58,6 +73,16 @@ impl JoinRequest {
         }
     }
 
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn with_binary_version(mut self, version: impl ToString) -> Self {
+        self.binary_version = Some(version.to_string());
+        self
+    }
+
     pub fn with_role_voter(self) -> Self {
         self.with_role("voter")
     }
@@ -0,0 +1,158 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Leader placement policy that keeps the leader in a preferred zone.
+
+use std::collections::BTreeMap;
+
+use databend_meta_types::node::Node;
+use databend_meta_types::raft_types::LogId;
+use databend_meta_types::raft_types::NodeId;
+
+/// Max number of log entries a voter may lag behind the leader to be considered caught up.
+pub(crate) const CAUGHT_UP_MAX_LAG: u64 = 64;
+
+/// Decides whether the leader should hand over leadership to a voter in the preferred zone.
+pub(crate) struct LeaderPreference<'a> {
+    pub(crate) preferred_zone: &'a str,
+    pub(crate) nodes: &'a BTreeMap<NodeId, Node>,
+}
+
+impl<'a> LeaderPreference<'a> {
+    pub(crate) fn new(preferred_zone: &'a str, nodes: &'a BTreeMap<NodeId, Node>) -> Self {
+        Self {
+            preferred_zone,
+            nodes,
+        }
+    }
+
+    /// Whether the node is labeled with the preferred zone.
+    pub(crate) fn is_preferred(&self, node_id: &NodeId) -> bool {
+        self.nodes.get(node_id).and_then(|n| n.zone()) == Some(self.preferred_zone)
+    }
+
+    /// Choose a voter to transfer leadership to.
+    ///
+    /// Returns `None` if the leader is already in the preferred zone,
+    /// or there is no voter in the preferred zone that is healthy and caught up.
+    /// A voter is healthy and caught up if the leader has replicated logs to it
+    /// and it lags behind `last_log_index` by at most [`CAUGHT_UP_MAX_LAG`] entries.
+    ///
+    /// Among the candidates the one with the greatest matching log index is chosen,
+    /// ties are broken by the smaller node id.
+    pub(crate) fn choose_transfer_target(
+        &self,
+        leader_id: NodeId,
+        voter_ids: impl IntoIterator<Item = NodeId>,
+        replication: &BTreeMap<NodeId, Option<LogId>>,
+        last_log_index: u64,
+    ) -> Option<NodeId> {
+        if self.is_preferred(&leader_id) {
+            return None;
+        }
+
+        let mut best: Option<(u64, NodeId)> = None;
+
+        for id in voter_ids {
+            if id == leader_id || !self.is_preferred(&id) {
+                continue;
+            }
+
+            let Some(Some(matching)) = replication.get(&id) else {
+                continue;
+            };
+
+            if matching.index + CAUGHT_UP_MAX_LAG < last_log_index {
+                continue;
+            }
+
+            let better = match best {
+                None => true,
+                Some((best_index, _)) => matching.index > best_index,
+            };
+
+            if better {
+                best = Some((matching.index, id));
+            }
+        }
+
+        best.map(|(_, id)| id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use databend_meta_types::Endpoint;
+    use databend_meta_types::node::Node;
+    use databend_meta_types::raft_types::new_log_id;
+
+    use super::*;
+
+    fn nodes() -> BTreeMap<NodeId, Node> {
+        let n = |id: NodeId, zone: &str| {
+            let node = Node::new(id, Endpoint::new("localhost", 1000 + id as u16));
+            if zone.is_empty() {
+                node
+            } else {
+                node.with_label(Node::LABEL_ZONE, zone)
+            }
+        };
+
+        BTreeMap::from([
+            (1, n(1, "a")),
+            (2, n(2, "b")),
+            (3, n(3, "b")),
+            (4, n(4, "")),
+        ])
+    }
+
+    #[test]
+    fn test_choose_transfer_target() {
+        let nodes = nodes();
+        let pref = LeaderPreference::new("b", &nodes);
+
+        let repl = BTreeMap::from([
+            (1, Some(new_log_id(1, 1, 100))),
+            (2, Some(new_log_id(1, 1, 90))),
+            (3, Some(new_log_id(1, 1, 100))),
+            (4, Some(new_log_id(1, 1, 100))),
+        ]);
+
+        // Leader in a non-preferred zone: choose the most up to date voter in zone b.
+        assert_eq!(
+            Some(3),
+            pref.choose_transfer_target(1, [1, 2, 3, 4], &repl, 100)
+        );
+
+        // Leader already in the preferred zone.
+        assert_eq!(None, pref.choose_transfer_target(2, [1, 2, 3, 4], &repl, 100));
+
+        // Only voters are candidates.
+        assert_eq!(Some(2), pref.choose_transfer_target(1, [1, 2, 4], &repl, 100));
+
+        // A lagging voter is not a candidate.
+        assert_eq!(None, pref.choose_transfer_target(1, [1, 2, 4], &repl, 200));
+
+        // A voter without replication progress is not a candidate.
+        let repl = BTreeMap::from([(1, Some(new_log_id(1, 1, 100))), (3, None)]);
+        assert_eq!(None, pref.choose_transfer_target(1, [1, 2, 3], &repl, 100));
+
+        // Unknown zone.
+        let pref = LeaderPreference::new("c", &nodes);
+        let repl = BTreeMap::from([(2, Some(new_log_id(1, 1, 100)))]);
+        assert_eq!(None, pref.choose_transfer_target(1, [1, 2], &repl, 100));
+    }
+}
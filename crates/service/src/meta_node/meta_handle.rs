@@ -23,6 +23,7 @@ use std::time::Duration;
 use databend_base::futures::ElapsedFutureExt;
 use databend_meta_client::MetaGrpcReadReq;
 use databend_meta_kvapi::kvapi::UpsertKVReply;
+use databend_meta_raft_store::StateMachineFeature;
 use databend_meta_raft_store::leveled_store::db_exporter::DBExporter;
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_types::AppliedState;
@@ -56,6 +57,7 @@ use tonic::Status;
 
 use crate::analysis::count_prefix::count_prefix;
 use crate::analysis::request_histogram;
+use crate::configs::WatchConfig;
 use crate::message::ForwardRequest;
 use crate::message::ForwardRequestBody;
 use crate::meta_node::errors::MetaNodeStopped;
@@ -346,10 +348,11 @@ impl<SP: SpawnApi> MetaHandle<SP> {
     pub async fn handle_watch(
         &self,
         watch: WatchRequest,
+        config: WatchConfig,
     ) -> Result<Result<BoxStream<'static, Result<WatchResponse, Status>>, Status>, MetaNodeStopped>
     {
         self.request(move |meta_node| {
-            let fu = async move { meta_node.handle_watch(watch).await };
+            let fu = async move { meta_node.handle_watch(watch, config).await };
 
             Box::pin(fu)
         })
@@ -455,6 +458,18 @@ impl<SP: SpawnApi> MetaHandle<SP> {
         .await
     }
 
+    /// Enable every state machine feature that all nodes in the cluster support.
+    pub async fn handle_finalize_upgrade(
+        &self,
+    ) -> Result<Result<Vec<StateMachineFeature>, MetaAPIError>, MetaNodeStopped> {
+        self.request(move |meta_node| {
+            let fu = async move { meta_node.finalize_upgrade().await };
+
+            Box::pin(fu)
+        })
+        .await
+    }
+
     pub async fn handle_trigger_transfer_leader(
         &self,
         to: NodeId,
@@ -40,22 +40,26 @@ use databend_meta_types::AppliedState;
 use databend_meta_types::Cmd;
 use databend_meta_types::Endpoint;
 use databend_meta_types::ForwardRPCError;
+use databend_meta_types::GrpcCompression;
 use databend_meta_types::GrpcHelper;
+use databend_meta_types::IncompatibleVersion;
 use databend_meta_types::LogEntry;
 use databend_meta_types::MetaAPIError;
+use databend_meta_types::MetaDataError;
 use databend_meta_types::MetaError;
 use databend_meta_types::MetaManagementError;
 use databend_meta_types::MetaNetworkError;
 use databend_meta_types::MetaOperationError;
 use databend_meta_types::MetaStartupError;
 use databend_meta_types::node::Node;
+use databend_meta_types::prefix_quota::QuotaState;
 use databend_meta_types::protobuf::KvGetManyRequest;
 use databend_meta_types::protobuf::StreamItem;
 use databend_meta_types::protobuf::WatchRequest;
 use databend_meta_types::protobuf::WatchResponse;
-use databend_meta_types::protobuf::raft_service_client::RaftServiceClient;
 use databend_meta_types::protobuf::raft_service_server::RaftServiceServer;
 use databend_meta_types::protobuf::watch_request::FilterType;
+use databend_meta_types::raft_types::Fatal;
 use databend_meta_types::raft_types::ForwardToLeader;
 use databend_meta_types::raft_types::InitializeError;
 use databend_meta_types::raft_types::MembershipNode;
@@ -65,6 +69,7 @@ use databend_meta_types::raft_types::TypeConfig;
 use databend_meta_types::raft_types::WatchReceiver;
 use databend_meta_types::raft_types::new_log_id;
 use databend_meta_types::snapshot_db::DBStat;
+use databend_meta_version::Version;
 use fastrace::func_name;
 use fastrace::prelude::*;
 use futures::Stream;
@@ -81,14 +86,17 @@ use maplit::btreemap;
 use openraft::Config;
 use openraft::Raft;
 use openraft::ServerState;
-use openraft::SnapshotPolicy;
+use prost::Message;
 use state_machine_api::UserKey;
 use tokio::sync::Mutex;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 use tokio::sync::watch;
 use tokio::time::Instant;
 use tokio::time::sleep;
+use tokio::time::timeout;
 use tonic::Status;
+use tonic::transport::server::TcpIncoming;
 use watcher::EventFilter;
 use watcher::dispatch::Command;
 use watcher::dispatch::Dispatcher;
@@ -100,13 +108,18 @@ use watcher::watch_stream::WatchStreamSender;
 
 use crate::analysis::request_histogram;
 use crate::api::grpc::grpc_service::try_remove_sender;
+use crate::api::grpc::wire_counted::WireCounted;
 use crate::configs::MetaServiceConfig;
+use crate::configs::WatchConfig;
 use crate::message::ForwardRequest;
 use crate::message::ForwardRequestBody;
 use crate::message::ForwardResponse;
 use crate::message::JoinRequest;
 use crate::message::LeaveRequest;
+use crate::meta_node::leader_preference::LeaderPreference;
 use crate::meta_node::meta_node_status::MetaNodeStatus;
+use crate::meta_node::snapshot_policy::SinceLastSnapshot;
+use crate::meta_node::snapshot_policy::SnapshotPolicy;
 use crate::meta_service::MetaForwarder;
 use crate::meta_service::MetaNodeBuilder;
 use crate::meta_service::RaftServiceImpl;
@@ -115,8 +128,11 @@ use crate::meta_service::meta_leader::MetaLeader;
 use crate::meta_service::runtime_config::RuntimeConfig;
 use crate::meta_service::watcher::DispatcherHandle;
 use crate::meta_service::watcher::WatchTypes;
+use crate::meta_service::watcher::relay_watch_responses;
 use crate::metrics::network_metrics;
+use crate::metrics::raft_metrics;
 use crate::metrics::server_metrics;
+use crate::raft_auth::RaftAuth;
 use crate::request_handling::Forwarder;
 use crate::request_handling::Handler;
 use crate::store::RaftStore;
@@ -178,7 +194,8 @@ impl<SP: SpawnApi> MetaNode<SP> {
             election_timeout_min: election_timeouts.0,
             election_timeout_max: election_timeouts.1,
             install_snapshot_timeout: config.install_snapshot_timeout,
-            snapshot_policy: SnapshotPolicy::LogsSinceLast(config.snapshot_logs_since_last),
+            // Snapshot is triggered by the node, see `snapshot_policy_loop()`.
+            snapshot_policy: openraft::SnapshotPolicy::Never,
             max_in_snapshot_log_to_keep: config.max_applied_log_to_keep,
             snapshot_max_chunk_size: config.snapshot_chunk_size,
             // Allow Leader to reset replication if a follower clears its log.
@@ -211,10 +228,20 @@ impl<SP: SpawnApi> MetaNode<SP> {
             max_msg_size / (1024 * 1024)
         );
 
-        let raft_server = RaftServiceServer::new(raft_service_impl)
+        let mut raft_server = RaftServiceServer::new(raft_service_impl)
             .max_decoding_message_size(max_msg_size)
             .max_encoding_message_size(max_msg_size);
 
+        for encoding in GrpcCompression::ACCEPTED {
+            raft_server = raft_server.accept_compressed(encoding);
+        }
+
+        let compression = meta_node.raft_store.config.raft_compression;
+        if let Some(encoding) = compression.encoding() {
+            info!("RaftService response compression: {}", compression);
+            raft_server = raft_server.send_compressed(encoding);
+        }
+
         let ipv4_addr = host.parse::<Ipv4Addr>();
         let ip_port = match ipv4_addr {
             Ok(addr) => format!("{}:{}", addr, port),
@@ -234,14 +261,40 @@ impl<SP: SpawnApi> MetaNode<SP> {
         let socket_addr = ip_port.parse::<std::net::SocketAddr>()?;
         let node_id = meta_node.raft_store.id;
 
-        let srv = tonic::transport::Server::builder()
-            // .concurrency_limit_per_connection()
+        let mut builder = tonic::transport::Server::builder();
+
+        if let Some(limit) = meta_node
+            .raft_store
+            .config
+            .raft_concurrency_limit_per_connection
+        {
+            info!("RaftService concurrency limit per connection: {}", limit);
+            builder = builder.concurrency_limit_per_connection(limit);
+        }
+
+        let srv = builder
             // .timeout(Duration::from_secs(60))
             .add_service(raft_server);
 
+        let incoming = TcpIncoming::bind(socket_addr).map_err(|e| {
+            MetaNetworkError::BindError(
+                AnyError::new(&e).add_context(|| format!("bind raft service to {}", socket_addr)),
+            )
+        })?;
+
+        // Count the bytes on the wire, after compression,
+        // to compare with the `recv_bytes` and `sent_bytes` of messages.
+        let incoming = incoming.map_ok(|io| {
+            WireCounted::new(
+                io,
+                raft_metrics::network::incr_wire_recv_bytes,
+                raft_metrics::network::incr_wire_sent_bytes,
+            )
+        });
+
         let h = SP::spawn(
             async move {
-                srv.serve_with_shutdown(socket_addr, async move {
+                srv.serve_with_incoming_shutdown(incoming, async move {
                     let _ = running_rx.changed().await;
                     info!(
                         "running_rx for Raft server received, shutting down: id={} {} ",
@@ -479,6 +532,214 @@ impl<SP: SpawnApi> MetaNode<SP> {
         Ok(())
     }
 
+    /// Spawn a task to keep the leader in the preferred zone, if `preferred_leader_zone` is configured.
+    pub async fn subscribe_leader_preference(
+        mn: Arc<Self>,
+        metrics_rx: WatchReceiver<RaftMetrics>,
+    ) {
+        let Some(zone) = mn.raft_store.config.preferred_leader_zone.clone() else {
+            return;
+        };
+
+        info!(
+            "Start a task keeping the leader in preferred zone: {}",
+            zone
+        );
+
+        let fut = Self::leader_preference_loop(mn.clone(), zone, metrics_rx);
+
+        let h = SP::spawn(
+            fut.in_span(Span::enter_with_local_parent("leader-preference")),
+            Some("leader-preference".into()),
+        );
+
+        {
+            let mut jh = mn.join_handles.lock().await;
+            jh.push(h);
+        }
+    }
+
+    /// When this node is the leader but not in the preferred zone,
+    /// transfer leadership to a healthy and caught-up voter in the preferred zone.
+    async fn leader_preference_loop(
+        meta_node: Arc<Self>,
+        preferred_zone: String,
+        mut metrics_rx: WatchReceiver<RaftMetrics>,
+    ) -> Result<(), AnyError> {
+        const CHECK_INTERVAL: Duration = Duration::from_millis(1_000);
+
+        // Wait for the previous transfer to take effect before trying another one.
+        const TRANSFER_BACKOFF: Duration = Duration::from_secs(10);
+
+        let id = meta_node.raft_store.id;
+        let mut last_transfer: Option<Instant> = None;
+
+        loop {
+            let loop_start = Instant::now();
+
+            if let Err(changed_err) = metrics_rx.changed().await {
+                info!(
+                    "{}; when:(watching metrics_rx); quit leader_preference_loop()",
+                    changed_err
+                );
+                break;
+            }
+
+            let mm = metrics_rx.borrow_watched().clone();
+
+            let in_backoff = last_transfer.is_some_and(|t| t.elapsed() < TRANSFER_BACKOFF);
+
+            if mm.state == ServerState::Leader && !in_backoff {
+                let nodes = meta_node
+                    .raft_store
+                    .get_sm_v003()
+                    .with_sys_data(|s| s.nodes_ref().clone());
+
+                let preference = LeaderPreference::new(&preferred_zone, &nodes);
+
+                let target = preference.choose_transfer_target(
+                    id,
+                    mm.membership_config.membership().voter_ids(),
+                    &mm.replication.clone().unwrap_or_default(),
+                    mm.last_log_index.unwrap_or_default(),
+                );
+
+                if let Some(to) = target {
+                    info!(
+                        "leader {} is not in preferred zone {}; transfer leadership to {}",
+                        id, preferred_zone, to
+                    );
+
+                    last_transfer = Some(Instant::now());
+                    server_metrics::incr_leader_preference_transfer();
+
+                    // A failed transfer, e.g., the target is lagging or a leader change is in
+                    // progress, is retried after `TRANSFER_BACKOFF`.
+                    if let Err(e) = meta_node.raft.trigger().transfer_leader(to).await {
+                        warn!("transfer leader to {} failed: {}; retry later", to, e);
+                    }
+                }
+            }
+
+            let elapsed = loop_start.elapsed();
+            if elapsed < CHECK_INTERVAL {
+                sleep(CHECK_INTERVAL - elapsed).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Spawn a task to trigger snapshot building according to the snapshot policy.
+    pub async fn subscribe_snapshot_policy(mn: Arc<Self>, metrics_rx: WatchReceiver<RaftMetrics>) {
+        let policy = SnapshotPolicy::new(&mn.raft_store.config);
+
+        info!("Start a task triggering snapshot by policy: {:?}", policy);
+
+        let fut = Self::snapshot_policy_loop(mn.clone(), policy, metrics_rx);
+
+        let h = SP::spawn(
+            fut.in_span(Span::enter_with_local_parent("snapshot-policy")),
+            Some("snapshot-policy".into()),
+        );
+
+        {
+            let mut jh = mn.join_handles.lock().await;
+            jh.push(h);
+        }
+    }
+
+    /// Evaluate the snapshot policy when raft metrics change or periodically,
+    /// and trigger a snapshot with the reason logged and counted in metrics.
+    async fn snapshot_policy_loop(
+        meta_node: Arc<Self>,
+        policy: SnapshotPolicy,
+        mut metrics_rx: WatchReceiver<RaftMetrics>,
+    ) -> Result<(), AnyError> {
+        const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+        // Do not trigger again before the previously triggered snapshot is built.
+        const TRIGGER_BACKOFF: Duration = Duration::from_secs(10);
+
+        let mut last_snapshot = metrics_rx.borrow_watched().snapshot;
+        let mut last_snapshot_at = Instant::now();
+        let mut last_snapshot_wal_offset = meta_node.wal_offset_for_policy(&policy).await;
+        let mut last_trigger: Option<Instant> = None;
+
+        loop {
+            let loop_start = Instant::now();
+
+            // Wake up without metrics change, for the interval based trigger.
+            if let Ok(Err(changed_err)) = timeout(CHECK_INTERVAL, metrics_rx.changed()).await {
+                info!(
+                    "{}; when:(watching metrics_rx); quit snapshot_policy_loop()",
+                    changed_err
+                );
+                break;
+            }
+
+            let mm = metrics_rx.borrow_watched().clone();
+            let wal_offset = meta_node.wal_offset_for_policy(&policy).await;
+
+            if mm.snapshot != last_snapshot {
+                last_snapshot = mm.snapshot;
+                last_snapshot_at = Instant::now();
+                last_snapshot_wal_offset = wal_offset;
+                last_trigger = None;
+            }
+
+            let in_backoff = last_trigger.is_some_and(|t| t.elapsed() < TRIGGER_BACKOFF);
+
+            if !in_backoff {
+                let last_applied = mm.last_applied.map(|x| x.index + 1).unwrap_or_default();
+                let snapshot_next = mm.snapshot.map(|x| x.index + 1).unwrap_or_default();
+
+                let since = SinceLastSnapshot {
+                    logs: last_applied.saturating_sub(snapshot_next),
+                    log_bytes: wal_offset.saturating_sub(last_snapshot_wal_offset),
+                    elapsed: last_snapshot_at.elapsed(),
+                    in_memory_keys: meta_node.get_in_memory_key_count(),
+                };
+
+                if let Some(reason) = policy.decide(&since) {
+                    info!(
+                        "trigger snapshot, reason: {}; since last snapshot({:?}): {}",
+                        reason, mm.snapshot, since
+                    );
+
+                    last_trigger = Some(Instant::now());
+                    server_metrics::incr_snapshot_trigger(reason.as_str());
+
+                    match meta_node.raft.trigger().snapshot().await {
+                        Ok(()) => {}
+                        Err(Fatal::Stopped) => {
+                            info!("raft stopped; quit snapshot_policy_loop()");
+                            break;
+                        }
+                        Err(e) => {
+                            error!("trigger snapshot failed, retry after backoff: {}", e);
+                        }
+                    }
+                }
+            }
+
+            let elapsed = loop_start.elapsed();
+            if elapsed < CHECK_INTERVAL {
+                sleep(CHECK_INTERVAL - elapsed).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the end offset of the raft log WAL, or 0 if the policy has no log bytes trigger.
+    async fn wal_offset_for_policy(&self, policy: &SnapshotPolicy) -> u64 {
+        if policy.max_log_bytes.is_none() {
+            return 0;
+        }
+        self.get_raft_log_stat().await.open_chunk.global_end
+    }
+
     /// Parse metrics string and return structured JSON value.
     fn parse_metrics_to_json(metrics_str: &str) -> serde_json::Value {
         use std::collections::BTreeMap;
@@ -806,7 +1067,7 @@ impl<SP: SpawnApi> MetaNode<SP> {
         for addr in addrs {
             info!("leave cluster via {}...", addr);
 
-            let conn_res = RaftServiceClient::connect(format!("http://{}", addr)).await;
+            let conn_res = RaftAuth::from_config(conf).connect(addr).await;
             let mut raft_client = match conn_res {
                 Ok(c) => c,
                 Err(e) => {
@@ -954,13 +1215,15 @@ impl<SP: SpawnApi> MetaNode<SP> {
                 return Err(MetaAPIError::NetworkError(net_err));
             }
         };
-        let mut raft_client = RaftServiceClient::new(chan);
+        let mut raft_client = RaftAuth::from_config(&config.raft_config).new_client(chan);
 
         let join_req = JoinRequest::new(
             config.raft_config.id,
             advertise_endpoint.clone(),
             config.grpc.advertise_address(),
-        );
+        )
+        .with_labels(config.raft_config.node_labels.clone())
+        .with_binary_version(databend_meta_version::version());
 
         let join_req = if config.raft_config.learner {
             join_req.with_role_learner()
@@ -1055,9 +1318,78 @@ impl<SP: SpawnApi> MetaNode<SP> {
         }
 
         let mn = Self::open(raft_conf).await?;
+        Self::spawn_report_binary_version(mn.clone(), conf.raft_config.id).await;
         Ok(mn)
     }
 
+    /// Spawn a task to record the binary version of this node in the cluster,
+    /// if the recorded one is absent or differs, e.g., after an upgrade.
+    ///
+    /// The task quits when the version is recorded or the node is shut down.
+    async fn spawn_report_binary_version(mn: Arc<Self>, node_id: NodeId) {
+        let fut = Self::report_binary_version_loop(mn.clone(), node_id);
+
+        let h = SP::spawn(
+            fut.in_span(Span::enter_with_local_parent("report-binary-version")),
+            Some("report-binary-version".into()),
+        );
+
+        {
+            let mut jh = mn.join_handles.lock().await;
+            jh.push(h);
+        }
+    }
+
+    async fn report_binary_version_loop(
+        meta_node: Arc<Self>,
+        node_id: NodeId,
+    ) -> Result<(), AnyError> {
+        const CHECK_INTERVAL: Duration = Duration::from_millis(1_000);
+
+        let version = databend_meta_version::version().to_string();
+        let mut running_rx = meta_node.running_rx.clone();
+
+        loop {
+            // This node is added to the cluster by the leader after joining.
+            let recorded = meta_node
+                .raft_store
+                .get_sm_v003()
+                .with_sys_data(|s| s.nodes_ref().get(&node_id).cloned());
+
+            if let Some(node) = recorded {
+                if node.binary_version.as_ref() == Some(&version) {
+                    return Ok(());
+                }
+
+                info!(
+                    "update binary version of node {}: {:?} -> {}",
+                    node_id, node.binary_version, version
+                );
+
+                let cmd = Cmd::AddNode {
+                    node_id,
+                    node: node.with_binary_version(Some(&version)),
+                    overriding: true,
+                };
+
+                match meta_node.write(LogEntry::new(cmd)).await {
+                    Ok(_) => return Ok(()),
+                    Err(e) => {
+                        warn!(
+                            "{}; when:(update binary version of node {}); retry later",
+                            e, node_id
+                        );
+                    }
+                }
+            }
+
+            if timeout(CHECK_INTERVAL, running_rx.changed()).await.is_ok() {
+                info!("node shutting down; quit report_binary_version_loop()");
+                return Ok(());
+            }
+        }
+    }
+
     /// Boot up the first node to create a cluster.
     /// For every cluster this func should be called exactly once.
     #[fastrace::trace]
@@ -1106,18 +1438,37 @@ impl<SP: SpawnApi> MetaNode<SP> {
             },
         }
 
-        if self.get_node(&node_id).await.is_none() {
-            info!(
-                "This node not found in state-machine; add node: {}:{:?}",
-                node_id, node
-            );
-            self.add_node(node_id, node.clone()).await.map_err(|e| {
-                MetaStartupError::AddNodeError {
-                    source: AnyError::new(&e),
-                }
-            })?;
-        } else {
-            info!("This node already in state-machine; No need to add");
+        match self.get_node(&node_id).await {
+            None => {
+                info!(
+                    "This node not found in state-machine; add node: {}:{:?}",
+                    node_id, node
+                );
+                self.add_node(node_id, node.clone()).await.map_err(|e| {
+                    MetaStartupError::AddNodeError {
+                        source: AnyError::new(&e),
+                    }
+                })?;
+            }
+            Some(recorded) if recorded.binary_version != node.binary_version => {
+                info!(
+                    "This node already in state-machine; update binary version: {:?} -> {:?}",
+                    recorded.binary_version, node.binary_version
+                );
+                let cmd = Cmd::AddNode {
+                    node_id,
+                    node: recorded.with_binary_version(node.binary_version.clone()),
+                    overriding: true,
+                };
+                self.write(LogEntry::new(cmd)).await.map_err(|e| {
+                    MetaStartupError::AddNodeError {
+                        source: AnyError::new(&e),
+                    }
+                })?;
+            }
+            Some(_) => {
+                info!("This node already in state-machine; No need to add");
+            }
         }
 
         info!("Done initializing node as single node cluster: {:?}", node);
@@ -1160,6 +1511,17 @@ impl<SP: SpawnApi> MetaNode<SP> {
         self.raft_store.log().read().await.stat()
     }
 
+    /// The number of keys in the in-memory levels of the state machine, which are not yet in a snapshot.
+    fn get_in_memory_key_count(&self) -> u64 {
+        let sm = self.raft_store.get_sm_v003();
+        let lm = sm.leveled_map();
+
+        let immutables = lm.immutable_levels().stat();
+        let immutable_keys = immutables.iter().map(|s| s.key_count()).sum::<u64>();
+
+        immutable_keys + lm.writable_stat().key_count()
+    }
+
     async fn get_snapshot_key_count(&self) -> u64 {
         self.raft_store
             .try_get_snapshot_key_count()
@@ -1191,6 +1553,12 @@ impl<SP: SpawnApi> MetaNode<SP> {
             .get_node_raft_endpoint(&self.raft_store.id)
             .await;
 
+        let labels = self
+            .get_node(&self.raft_store.id)
+            .await
+            .map(|n| n.labels)
+            .unwrap_or_default();
+
         let raft_log_status = self.get_raft_log_stat().await.into();
         let snapshot_key_count = self.get_snapshot_key_count().await;
         let snapshot_key_space_stat = self.get_snapshot_key_space_stat().await;
@@ -1204,13 +1572,16 @@ impl<SP: SpawnApi> MetaNode<SP> {
         };
 
         let last_seq = self.get_last_seq().await;
+        let prefix_quotas = self.get_prefix_quotas().await;
 
         MetaNodeStatus {
             id: self.raft_store.id,
             binary_version: binary_version.to_string(),
             data_version: DATA_VERSION,
             endpoint: endpoint.map(|x| x.to_string()),
+            labels,
             raft_log: raft_log_status,
+            raft_log_durability: self.raft_store.config.raft_log_durability,
             snapshot_key_count,
             snapshot_key_space_stat,
             state: format!("{:?}", metrics.state),
@@ -1225,6 +1596,7 @@ impl<SP: SpawnApi> MetaNode<SP> {
             voters,
             non_voters: learners,
             last_seq,
+            prefix_quotas,
         }
     }
 
@@ -1233,6 +1605,11 @@ impl<SP: SpawnApi> MetaNode<SP> {
         sm.sys_data().curr_seq()
     }
 
+    async fn get_prefix_quotas(&self) -> BTreeMap<String, QuotaState> {
+        let sm = self.raft_store.get_sm_v003();
+        sm.with_sys_data(|s| s.prefix_quotas().clone())
+    }
+
     #[fastrace::trace]
     pub async fn get_grpc_advertise_addrs(&self) -> Vec<String> {
         // Maybe stale get: from local state machine
@@ -1416,11 +1793,19 @@ impl<SP: SpawnApi> MetaNode<SP> {
     }
 
     /// Propose a log entry to set a feature.
+    ///
+    /// A feature can be enabled only when every node in the cluster supports it,
+    /// see [`Self::check_nodes_support`].
     pub async fn set_feature(
         &self,
         feature: StateMachineFeature,
         enable: bool,
     ) -> Result<(), MetaAPIError> {
+        if enable {
+            self.check_nodes_support(feature)
+                .map_err(MetaDataError::IncompatibleVersion)?;
+        }
+
         let cmd = Cmd::SetFeature {
             feature: feature.to_string(),
             enable,
@@ -1430,6 +1815,85 @@ impl<SP: SpawnApi> MetaNode<SP> {
         Ok(())
     }
 
+    /// Enable every state machine feature that all the nodes in the cluster support.
+    ///
+    /// It is meant to be called after a rolling upgrade of every node.
+    /// Already enabled features are skipped.
+    /// Returns the features enabled by this call.
+    pub async fn finalize_upgrade(&self) -> Result<Vec<StateMachineFeature>, MetaAPIError> {
+        let enabled = self.raft_store.get_sm_v003().sys_data().features().clone();
+
+        let mut newly_enabled = vec![];
+
+        for feature in StateMachineFeature::all() {
+            if enabled.contains(&feature.to_string()) {
+                continue;
+            }
+
+            if let Err(e) = self.check_nodes_support(feature) {
+                info!("finalize_upgrade: skip feature {}: {}", feature, e);
+                continue;
+            }
+
+            self.set_feature(feature, true).await?;
+            newly_enabled.push(feature);
+        }
+
+        info!("finalize_upgrade: enabled features: {:?}", newly_enabled);
+
+        Ok(newly_enabled)
+    }
+
+    /// Check if every voter, learner and added node runs a binary version that supports `feature`.
+    ///
+    /// An added node that is not yet in the membership is a joining node, and is checked too.
+    /// The versions are read from the local state machine.
+    /// A node that has not reported its version is considered too old.
+    /// A node joining after the feature is enabled is checked by the leader, see `MetaLeader::join`.
+    pub fn check_nodes_support(
+        &self,
+        feature: StateMachineFeature,
+    ) -> Result<(), IncompatibleVersion> {
+        let min = feature.min_version();
+        if min == Version::min() {
+            return Ok(());
+        }
+
+        let sys_data = self.raft_store.get_sm_v003().sys_data();
+        let membership = sys_data.last_membership_ref().membership();
+
+        let node_ids = membership
+            .voter_ids()
+            .chain(membership.learner_ids())
+            .chain(sys_data.nodes_ref().keys().copied())
+            .collect::<BTreeSet<_>>();
+
+        for node_id in node_ids {
+            let binary_version = sys_data
+                .nodes_ref()
+                .get(&node_id)
+                .and_then(|n| n.binary_version.clone());
+
+            let reason = format!(
+                "state machine feature {} requires version >= {}",
+                feature, min
+            );
+
+            let Some(binary_version) = binary_version else {
+                return Err(IncompatibleVersion::new(node_id, "unknown", reason));
+            };
+
+            let supported =
+                semver::Version::parse(&binary_version).is_ok_and(|v| Version::from(v) >= min);
+
+            if !supported {
+                return Err(IncompatibleVersion::new(node_id, binary_version, reason));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Submit a write request to the known leader. Returns the response after applying the request.
     #[fastrace::trace]
     pub async fn write(&self, req: LogEntry) -> Result<AppliedState, MetaAPIError> {
@@ -1477,9 +1941,16 @@ impl<SP: SpawnApi> MetaNode<SP> {
         }
     }
 
+    /// Create a watch stream.
+    ///
+    /// Responses from the dispatcher are relayed to a buffer of `config.buffer_size`.
+    /// If a response arrives when the buffer is full,
+    /// the stream is terminated with a `DataLoss` status after the buffered responses.
+    /// The initial flush, if requested, is not subject to it: it waits for the consumer.
     pub(crate) async fn handle_watch(
         &self,
         watch: WatchRequest,
+        config: WatchConfig,
     ) -> Result<BoxStream<'static, Result<WatchResponse, Status>>, Status> {
         info!("{}: Received WatchRequest: {}", func_name!(), watch);
 
@@ -1540,11 +2011,27 @@ impl<SP: SpawnApi> MetaNode<SP> {
                 }
             };
 
-            let stream = WatchStream::new(rx, Box::new(on_drop));
+            // Relay responses to a bounded buffer so that a slow consumer does not block the dispatcher.
+            let (buf_tx, buf_rx) = mpsc::channel(config.buffer_size.max(1));
+            let (lagged_tx, lagged_rx) = oneshot::channel();
+
+            #[allow(unused_must_use)]
+            SP::spawn(
+                relay_watch_responses(rx, buf_tx, lagged_tx, flush, sender_str.clone()),
+                Some("watch-relay".into()),
+            );
+
+            let stream = WatchStream::new(buf_rx, Box::new(on_drop));
+
+            // After the buffered responses, terminate a lagged stream with an error.
+            let lagged =
+                futures::stream::once(lagged_rx).filter_map(|res| future::ready(res.ok().map(Err)));
+            let stream = stream.chain(lagged);
 
             let stream = stream.map(move |item| {
                 if let Ok(ref resp) = item {
                     network_metrics::incr_watch_sent(resp);
+                    network_metrics::incr_sent_bytes(resp.encoded_len() as u64);
                 }
                 item
             });
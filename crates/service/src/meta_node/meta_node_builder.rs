@@ -105,6 +105,8 @@ impl<SP: SpawnApi> MetaNodeBuilder<SP> {
         });
 
         MetaNode::subscribe_metrics(meta_node.clone(), raft.metrics()).await;
+        MetaNode::subscribe_leader_preference(meta_node.clone(), raft.metrics()).await;
+        MetaNode::subscribe_snapshot_policy(meta_node.clone(), raft.metrics()).await;
 
         let endpoint = if let Some(a) = self.raft_service_endpoint.take() {
             a
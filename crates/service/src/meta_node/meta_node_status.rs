@@ -15,8 +15,10 @@
 use std::collections::BTreeMap;
 
 use databend_meta_raft_store::ondisk::DataVersion;
+use databend_meta_raft_store::raft_log_v004::RaftLogDurability;
 use databend_meta_raft_store::raft_log_v004::RaftLogStat;
 use databend_meta_types::node::Node;
+use databend_meta_types::prefix_quota::QuotaState;
 use databend_meta_types::raft_types::LogId;
 use databend_meta_types::raft_types::NodeId;
 
@@ -33,9 +35,16 @@ pub struct MetaNodeStatus {
     /// The raft service endpoint for internal communication
     pub endpoint: Option<String>,
 
+    /// Placement labels of this node, such as `zone` and `rack`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
     /// The status about local raft-log
     pub raft_log: RaftLogStatus,
 
+    /// When appended log entries are acknowledged as persisted.
+    pub raft_log_durability: RaftLogDurability,
+
     /// Total number of keys in current snapshot
     pub snapshot_key_count: u64,
 
@@ -83,6 +92,10 @@ pub struct MetaNodeStatus {
     ///
     /// `seq` is a monotonically incremental integer for every value that is inserted or updated.
     pub last_seq: u64,
+
+    /// The quota and usage of each key prefix that has a quota defined.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub prefix_quotas: BTreeMap<String, QuotaState>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
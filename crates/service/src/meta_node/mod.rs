@@ -15,9 +15,11 @@
 //! MetaNode is the container of all meta service logic, without the service layer.
 
 pub mod errors;
+pub(crate) mod leader_preference;
 pub mod meta_handle;
 #[allow(clippy::module_inception)]
 pub mod meta_node;
 pub mod meta_node_builder;
 pub mod meta_node_status;
 pub mod meta_worker;
+pub(crate) mod snapshot_policy;
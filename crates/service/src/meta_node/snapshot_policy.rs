@@ -0,0 +1,192 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Snapshot policy that decides when to build a snapshot,
+//! by log count, raft-log bytes, wall-clock interval and in-memory data size.
+
+use std::fmt;
+use std::time::Duration;
+
+use databend_meta_raft_store::config::RaftConfig;
+
+/// Why a snapshot is triggered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SnapshotReason {
+    /// Too many logs are applied since the last snapshot.
+    LogCount,
+    /// Too many bytes are written to raft-log since the last snapshot.
+    LogBytes,
+    /// Too long since the last snapshot.
+    Interval,
+    /// Too many keys are held in the in-memory levels.
+    InMemoryKeys,
+}
+
+impl SnapshotReason {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotReason::LogCount => "log_count",
+            SnapshotReason::LogBytes => "log_bytes",
+            SnapshotReason::Interval => "interval",
+            SnapshotReason::InMemoryKeys => "in_memory_keys",
+        }
+    }
+}
+
+impl fmt::Display for SnapshotReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What has changed since the last snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SinceLastSnapshot {
+    /// Number of logs applied.
+    pub(crate) logs: u64,
+    /// Number of bytes written to raft-log.
+    pub(crate) log_bytes: u64,
+    /// Wall-clock time elapsed.
+    pub(crate) elapsed: Duration,
+    /// Number of keys in the in-memory levels, including the writable level.
+    pub(crate) in_memory_keys: u64,
+}
+
+impl fmt::Display for SinceLastSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "logs: {}, log_bytes: {}, elapsed: {:?}, in_memory_keys: {}",
+            self.logs, self.log_bytes, self.elapsed, self.in_memory_keys
+        )
+    }
+}
+
+/// Decides whether to build a snapshot. A threshold of `None` is disabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SnapshotPolicy {
+    pub(crate) max_logs: u64,
+    pub(crate) max_log_bytes: Option<u64>,
+    pub(crate) interval: Option<Duration>,
+    pub(crate) max_in_memory_keys: Option<u64>,
+}
+
+impl SnapshotPolicy {
+    pub(crate) fn new(config: &RaftConfig) -> Self {
+        Self {
+            max_logs: config.snapshot_logs_since_last,
+            max_log_bytes: config.snapshot_log_bytes_since_last,
+            interval: config.snapshot_interval_ms.map(Duration::from_millis),
+            max_in_memory_keys: config.snapshot_in_memory_keys,
+        }
+    }
+
+    /// Returns the reason to build a snapshot, or `None` if no snapshot is needed.
+    ///
+    /// No snapshot is built if no log is applied since the last snapshot,
+    /// because it would contain the same data as the last one.
+    pub(crate) fn decide(&self, since: &SinceLastSnapshot) -> Option<SnapshotReason> {
+        if since.logs == 0 {
+            return None;
+        }
+
+        if since.logs >= self.max_logs {
+            return Some(SnapshotReason::LogCount);
+        }
+
+        if self.max_log_bytes.is_some_and(|max| since.log_bytes >= max) {
+            return Some(SnapshotReason::LogBytes);
+        }
+
+        if self.interval.is_some_and(|d| since.elapsed >= d) {
+            return Some(SnapshotReason::Interval);
+        }
+
+        if self
+            .max_in_memory_keys
+            .is_some_and(|max| since.in_memory_keys >= max)
+        {
+            return Some(SnapshotReason::InMemoryKeys);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn since(logs: u64, log_bytes: u64, elapsed_ms: u64, in_memory_keys: u64) -> SinceLastSnapshot {
+        SinceLastSnapshot {
+            logs,
+            log_bytes,
+            elapsed: Duration::from_millis(elapsed_ms),
+            in_memory_keys,
+        }
+    }
+
+    #[test]
+    fn test_decide() {
+        let p = SnapshotPolicy {
+            max_logs: 100,
+            max_log_bytes: Some(1000),
+            interval: Some(Duration::from_millis(500)),
+            max_in_memory_keys: Some(50),
+        };
+
+        assert_eq!(None, p.decide(&since(10, 10, 10, 10)));
+        assert_eq!(
+            Some(SnapshotReason::LogCount),
+            p.decide(&since(100, 10, 10, 10))
+        );
+        assert_eq!(
+            Some(SnapshotReason::LogBytes),
+            p.decide(&since(10, 1000, 10, 10))
+        );
+        assert_eq!(
+            Some(SnapshotReason::Interval),
+            p.decide(&since(10, 10, 500, 10))
+        );
+        assert_eq!(
+            Some(SnapshotReason::InMemoryKeys),
+            p.decide(&since(10, 10, 10, 50))
+        );
+
+        // Log count is checked first.
+        assert_eq!(
+            Some(SnapshotReason::LogCount),
+            p.decide(&since(100, 1000, 500, 50))
+        );
+
+        // Nothing new since the last snapshot.
+        assert_eq!(None, p.decide(&since(0, 1000, 500, 50)));
+    }
+
+    #[test]
+    fn test_decide_disabled() {
+        let p = SnapshotPolicy {
+            max_logs: 100,
+            max_log_bytes: None,
+            interval: None,
+            max_in_memory_keys: None,
+        };
+
+        assert_eq!(None, p.decide(&since(10, u64::MAX, u64::MAX, u64::MAX)));
+        assert_eq!(
+            Some(SnapshotReason::LogCount),
+            p.decide(&since(100, 0, 0, 0))
+        );
+    }
+}
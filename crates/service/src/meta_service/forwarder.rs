@@ -22,17 +22,18 @@ use databend_meta_types::ForwardRPCError;
 use databend_meta_types::MetaAPIError;
 use databend_meta_types::MetaNetworkError;
 use databend_meta_types::protobuf::StreamItem;
-use databend_meta_types::protobuf::raft_service_client::RaftServiceClient;
 use databend_meta_types::raft_types::NodeId;
 use log::debug;
 use tonic::codegen::BoxStream;
-use tonic::transport::Channel;
 
 use crate::message::ForwardRequest;
 use crate::message::ForwardRequestBody;
 use crate::message::ForwardResponse;
 use crate::meta_node::meta_node::MetaRaft;
 use crate::meta_service::MetaNode;
+use crate::raft_auth::AuthRaftServiceClient;
+use crate::raft_auth::RaftAuth;
+use crate::raft_client::configure_raft_client;
 use crate::request_handling::Forwarder;
 use crate::store::RaftStore;
 use crate::util::reply_to_api_result;
@@ -55,7 +56,7 @@ impl<'a, SP: SpawnApi> MetaForwarder<'a, SP> {
     async fn new_raft_client(
         &self,
         target: &NodeId,
-    ) -> Result<(Endpoint, RaftServiceClient<Channel>), MetaNetworkError> {
+    ) -> Result<(Endpoint, AuthRaftServiceClient), MetaNetworkError> {
         debug!("new RaftServiceClient to: {}", target);
 
         let endpoint = self
@@ -69,17 +70,16 @@ impl<'a, SP: SpawnApi> MetaForwarder<'a, SP> {
                 ))
             })?;
 
-        let client = RaftServiceClient::connect(format!("http://{}", endpoint))
+        let client = RaftAuth::from_config(&self.sto.config)
+            .connect(&endpoint)
             .await
             .map_err(|e| {
                 let conn_err = ConnectionError::new(e, format!("address: {}", endpoint));
                 MetaNetworkError::ConnectionError(conn_err)
             })?;
 
-        let max_msg_size = self.sto.config.raft_grpc_max_message_size();
-        let client = client
-            .max_decoding_message_size(max_msg_size)
-            .max_encoding_message_size(max_msg_size);
+        let peer = self.sto.get_node(target).await;
+        let client = configure_raft_client(client, &self.sto.config, peer.as_ref());
 
         Ok((endpoint, client))
     }
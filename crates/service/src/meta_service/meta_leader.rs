@@ -23,11 +23,13 @@ use databend_meta_client::MetaGrpcReadReq;
 use databend_meta_kvapi::kvapi::KVApi;
 use databend_meta_kvapi::kvapi::KvApiExt;
 use databend_meta_kvapi::kvapi::ListOptions;
+use databend_meta_raft_store::StateMachineFeature;
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_sled_store::openraft::ChangeMembers;
 use databend_meta_sled_store::openraft::async_runtime::WatchReceiver;
 use databend_meta_types::AppliedState;
 use databend_meta_types::Cmd;
+use databend_meta_types::IncompatibleVersion;
 use databend_meta_types::LogEntry;
 use databend_meta_types::MetaDataError;
 use databend_meta_types::MetaDataReadError;
@@ -39,11 +41,13 @@ use databend_meta_types::raft_types::ClientWriteError;
 use databend_meta_types::raft_types::MembershipNode;
 use databend_meta_types::raft_types::NodeId;
 use databend_meta_types::raft_types::RaftError;
+use databend_meta_version::Version;
 use futures::Stream;
 use futures::StreamExt;
 use futures::TryStreamExt;
 use log::debug;
 use log::info;
+use log::warn;
 use maplit::btreemap;
 use maplit::btreeset;
 use tonic::Status;
@@ -56,6 +60,7 @@ use crate::message::JoinRequest;
 use crate::message::LeaveRequest;
 use crate::meta_node::meta_node::MetaRaft;
 use crate::meta_service::MetaNode;
+use crate::meta_service::write_limit;
 use crate::metrics::ProposalPending;
 use crate::metrics::server_metrics;
 use crate::request_handling::Handler;
@@ -92,7 +97,19 @@ impl<SP: SpawnApi> Handler<ForwardRequestBody> for MetaLeader<'_, SP> {
                 Ok(ForwardResponse::Leave(()))
             }
             ForwardRequestBody::Write(entry) => {
+                // Checked by the leader, so that the same limits apply
+                // no matter which node receives the request.
+                write_limit::check_log_entry(&self.sto.config.write_limit, &entry)
+                    .map_err(MetaDataError::WriteLimitExceeded)?;
+
                 let res = self.write(entry.clone()).await?;
+
+                // A command rejected by a prefix quota is applied as a no-op,
+                // report it to the caller as an error.
+                if let AppliedState::QuotaExceeded(e) = res {
+                    return Err(MetaDataError::QuotaExceeded(e).into());
+                }
+
                 Ok(ForwardResponse::AppliedState(res))
             }
 
@@ -184,8 +201,11 @@ impl<'a, SP: SpawnApi> MetaLeader<'a, SP> {
     /// - Adds the node to membership to let it become a voter.
     ///
     /// If the node is already in cluster membership, it still returns Ok.
+    ///
+    /// A node whose binary version can not work with this leader is rejected,
+    /// e.g., it can not receive snapshot from this leader.
     #[fastrace::trace]
-    pub async fn join(&self, req: JoinRequest) -> Result<(), RaftError<ClientWriteError>> {
+    pub async fn join(&self, req: JoinRequest) -> Result<(), MetaOperationError> {
         let role = req.role();
         let node_id = req.node_id;
         let endpoint = req.endpoint;
@@ -198,14 +218,39 @@ impl<'a, SP: SpawnApi> MetaLeader<'a, SP> {
             return Ok(());
         }
 
+        if let Some(binary_version) = &req.binary_version {
+            Self::check_peer_version(node_id, binary_version)?;
+        } else {
+            warn!(
+                "node {} joins without binary version, skip version check",
+                node_id
+            );
+        }
+
+        let binary_version = req.binary_version.clone();
+        self.check_enabled_features(node_id, binary_version.as_deref())?;
+
+        let added_before = self.sto.get_node(&node_id).await.is_some();
+
         let ent = LogEntry::new(Cmd::AddNode {
             node_id,
             node: Node::new(node_id, endpoint)
-                .with_grpc_advertise_address(req.grpc_api_advertise_address),
+                .with_grpc_advertise_address(req.grpc_api_advertise_address)
+                .with_labels(req.labels)
+                .with_binary_version(req.binary_version),
             overriding: false,
         });
         self.write(ent).await?;
 
+        // A feature may be enabled after the above check but before the node is added.
+        if let Err(e) = self.check_enabled_features(node_id, binary_version.as_deref()) {
+            if !added_before {
+                self.write(LogEntry::new(Cmd::RemoveNode { node_id }))
+                    .await?;
+            }
+            return Err(e.into());
+        }
+
         let msg = if role == "learner" {
             ChangeMembers::AddNodes(btreemap! {node_id=>MembershipNode{}})
         } else {
@@ -303,6 +348,62 @@ impl<'a, SP: SpawnApi> MetaLeader<'a, SP> {
         }
     }
 
+    /// Check if a node of `binary_version` can run in the same cluster as this node.
+    fn check_peer_version(node_id: NodeId, binary_version: &str) -> Result<(), MetaDataError> {
+        let incompatible = |reason: String| {
+            let e = IncompatibleVersion::new(node_id, binary_version, reason);
+            warn!("reject node: {}", e);
+            MetaDataError::IncompatibleVersion(e)
+        };
+
+        let peer = semver::Version::parse(binary_version)
+            .map_err(|e| incompatible(format!("invalid version: {}", e)))?;
+
+        databend_meta_version::spec()
+            .check_peer_compatible(Version::from(peer))
+            .map_err(incompatible)
+    }
+
+    /// Check if a joining node supports every enabled state machine feature,
+    /// otherwise it would apply the log without the feature and its state machine diverges.
+    ///
+    /// A node that has not reported its version is considered too old once such a feature is enabled.
+    fn check_enabled_features(
+        &self,
+        node_id: NodeId,
+        binary_version: Option<&str>,
+    ) -> Result<(), MetaDataError> {
+        let enabled = self.sto.get_sm_v003().sys_data().features().clone();
+
+        let version = binary_version
+            .and_then(|v| semver::Version::parse(v).ok())
+            .map(Version::from);
+
+        for feature in StateMachineFeature::all() {
+            let min = feature.min_version();
+            if min == Version::min() || !enabled.contains(&feature.to_string()) {
+                continue;
+            }
+
+            if version.is_some_and(|v| v >= min) {
+                continue;
+            }
+
+            let e = IncompatibleVersion::new(
+                node_id,
+                binary_version.unwrap_or("unknown"),
+                format!(
+                    "state machine feature {} is enabled and requires version >= {}",
+                    feature, min
+                ),
+            );
+            warn!("reject node: {}", e);
+            return Err(MetaDataError::IncompatibleVersion(e));
+        }
+
+        Ok(())
+    }
+
     /// Check if a node is allowed to leave the cluster.
     ///
     /// A cluster must have at least one node in it.
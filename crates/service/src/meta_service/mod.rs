@@ -19,6 +19,7 @@ pub mod meta_leader;
 pub mod raft_service_impl;
 pub mod runtime_config;
 pub mod watcher;
+pub mod write_limit;
 
 pub use forwarder::MetaForwarder;
 pub use raft_service_impl::RaftServiceImpl;
@@ -47,6 +47,7 @@ use databend_meta_types::raft_types::SnapshotMeta;
 use databend_meta_types::raft_types::TransferLeaderRequest;
 use databend_meta_types::raft_types::Vote;
 use databend_meta_types::raft_types::VoteRequest;
+use databend_meta_types::snapshot_checksum::SnapshotChecksum;
 use databend_meta_types::snapshot_db::DB;
 use databend_meta_types::sys_data::SysData;
 use fastrace::func_name;
@@ -69,14 +70,26 @@ use crate::message::ForwardRequest;
 use crate::message::ForwardRequestBody;
 use crate::meta_service::MetaNode;
 use crate::metrics::raft_metrics;
+use crate::raft_auth::RaftAuth;
 
 pub struct RaftServiceImpl<SP: SpawnApi> {
     pub meta_node: Arc<MetaNode<SP>>,
+    auth: RaftAuth,
 }
 
 impl<SP: SpawnApi> RaftServiceImpl<SP> {
     pub fn create(meta_node: Arc<MetaNode<SP>>) -> Self {
-        Self { meta_node }
+        let auth = RaftAuth::from_config(&meta_node.raft_store.config);
+        Self { meta_node, auth }
+    }
+
+    /// Reject the request if it does not carry the cluster secret.
+    fn check_auth<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        self.auth.verify(request.metadata()).inspect_err(|e| {
+            let addr = remote_addr(request);
+            warn!("RaftService: reject request from {}: {}", addr, e.message());
+            raft_metrics::network::incr_auth_rejected();
+        })
     }
 
     fn incr_meta_metrics_recv_bytes_from_peer(&self, request: &Request<RaftRequest>) {
@@ -105,11 +118,17 @@ impl<SP: SpawnApi> RaftServiceImpl<SP> {
 
         let raft_config = &self.meta_node.raft_store.config;
 
+        let key_ring = raft_config
+            .key_ring()
+            .map_err(|e| Status::internal(format!("Fail to load encryption key: {}", e)))?;
+
+        // A snapshot file encrypted by the sender can be opened only if this node has the key.
         let db = DB::open_snapshot(
             &storage_path,
             &temp_rel_path,
             snapshot_meta.snapshot_id.clone(),
             raft_config.to_rotbl_config(),
+            key_ring,
         )
         .map_err(|e| {
             Status::internal(format!(
@@ -118,6 +137,15 @@ impl<SP: SpawnApi> RaftServiceImpl<SP> {
             ))
         })?;
 
+        // The rotbl file is received as is: verify it before installing.
+        db.verify().await.map_err(|e| {
+            incr_snapshot_recvfrom_result(addr.clone(), false);
+            Status::data_loss(format!(
+                "Fail to verify snapshot: {}, snapshot_meta:{:?}",
+                e, &snapshot_meta
+            ))
+        })?;
+
         let snapshot = Snapshot {
             meta: snapshot_meta,
             snapshot: db,
@@ -163,6 +191,9 @@ impl<SP: SpawnApi> RaftServiceImpl<SP> {
 
         let mut commit = None;
 
+        // Checksum of the received key-values, to compare with the one in the commit.
+        let mut checksum = SnapshotChecksum::new();
+
         while let Some(entry) = strm
             .try_next()
             .await
@@ -176,6 +207,8 @@ impl<SP: SpawnApi> RaftServiceImpl<SP> {
                         .encode_to()
                         .map_err(|e| Status::internal(format!("Failed to convert SeqV: {}", e)))?;
 
+                    checksum.update(&kv.key, &seq_marked);
+
                     let data_entry = WriteEntry::Data((kv.key, seq_marked));
 
                     write_tx
@@ -197,6 +230,30 @@ impl<SP: SpawnApi> RaftServiceImpl<SP> {
             ));
         };
 
+        // Verify the received data before it is moved to the final path and installed.
+        // A sender without checksum support does not send one.
+        match commit.checksum {
+            Some(expected) if expected != checksum.checksum() => {
+                error!(
+                    "snapshot checksum mismatch from {}: expected: {:08x}, received: {:?}",
+                    addr, expected, checksum
+                );
+                return Err(Status::data_loss(format!(
+                    "snapshot checksum mismatch: expected: {:08x}, received: {:08x} of {} key-values",
+                    expected,
+                    checksum.checksum(),
+                    checksum.count()
+                )));
+            }
+            Some(_) => {}
+            None => {
+                warn!(
+                    "snapshot from {} has no checksum, skip verifying {:?}",
+                    addr, checksum
+                );
+            }
+        }
+
         let pb_vote = commit.vote.ok_or_else(|| {
             Status::invalid_argument("None vote received from commit in snapshot stream")
         })?;
@@ -315,6 +372,8 @@ impl<SP: SpawnApi> RaftServiceImpl<SP> {
 #[async_trait::async_trait]
 impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
     async fn forward(&self, request: Request<RaftRequest>) -> Result<Response<RaftReply>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| async {
             let forward_req: ForwardRequest<ForwardRequestBody> = GrpcHelper::parse_req(request)?;
 
@@ -335,6 +394,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
         &self,
         request: Request<RaftRequest>,
     ) -> Result<Response<Self::KvReadV1Stream>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| async {
             let forward_req: ForwardRequest<MetaGrpcReadReq> = GrpcHelper::parse_req(request)?;
 
@@ -356,6 +417,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
         &self,
         request: Request<RaftRequest>,
     ) -> Result<Response<RaftReply>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| async {
             let remote_addr = remote_addr(&request);
             self.incr_meta_metrics_recv_bytes_from_peer(&request);
@@ -388,6 +451,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
         &self,
         request: Request<Streaming<SnapshotChunkRequestV003>>,
     ) -> Result<Response<SnapshotResponseV003>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| {
             self.do_install_snapshot_v003(request)
         })
@@ -398,6 +463,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
         &self,
         request: Request<Streaming<InstallEntryV004>>,
     ) -> Result<Response<InstallSnapshotResponseV004>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| {
             self.do_install_snapshot_v004(request)
         })
@@ -405,6 +472,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
     }
 
     async fn vote(&self, request: Request<RaftRequest>) -> Result<Response<RaftReply>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| async {
             let remote_addr = remote_addr(&request);
             self.incr_meta_metrics_recv_bytes_from_peer(&request);
@@ -436,6 +505,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
         &self,
         request: Request<pb::VoteRequest>,
     ) -> Result<Response<pb::VoteResponse>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| async {
             let remote_addr = remote_addr(&request);
             let v_req_pb = request.into_inner();
@@ -468,6 +539,8 @@ impl<SP: SpawnApi> RaftService for RaftServiceImpl<SP> {
         &self,
         request: Request<pb::TransferLeaderRequest>,
     ) -> Result<Response<Empty>, Status> {
+        self.check_auth(&request)?;
+
         SP::trace_request(func_path!(), request, |request| async {
             let remote_addr = remote_addr(&request);
             let req = request.into_inner();
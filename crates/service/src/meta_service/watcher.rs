@@ -17,10 +17,17 @@
 use std::fmt;
 use std::io::Error;
 use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 
 use databend_meta_types::SeqV;
 use databend_meta_types::protobuf::WatchResponse;
 use log::debug;
+use log::warn;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::oneshot;
 use tonic::Status;
 use watcher::dispatch::DispatcherHandle as GenericDispatcherHandle;
 use watcher::type_config::KVChange;
@@ -97,3 +104,239 @@ impl DispatcherHandle {
         h
     }
 }
+
+/// The message of the terminal status of a watch stream whose consumer does not keep up.
+pub const WATCH_LAGGED_MESSAGE: &str =
+    "watch stream lagged: the consumer is too slow; re-establish the watch and resync";
+
+/// Returns the prefix of a watched key used as a metrics label: the part before the first `/`.
+pub(crate) fn watch_key_prefix(key: &str) -> &str {
+    key.split('/').next().unwrap_or_default()
+}
+
+/// Counts a watch stream against the max number of watchers and in the per-prefix metrics,
+/// until dropped.
+#[derive(Debug)]
+pub(crate) struct WatcherGuard {
+    prefix: String,
+    count: Arc<AtomicU64>,
+}
+
+impl WatcherGuard {
+    /// Count a new watcher of `key`.
+    ///
+    /// Returns `ResourceExhausted` if there are already `max` watchers.
+    pub(crate) fn try_new(
+        count: &Arc<AtomicU64>,
+        max: Option<u64>,
+        key: &str,
+    ) -> Result<Self, Status> {
+        let prev = count.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(max) = max {
+            if prev >= max {
+                count.fetch_sub(1, Ordering::Relaxed);
+                return Err(Status::resource_exhausted(format!(
+                    "number of watchers reaches limit {}",
+                    max
+                )));
+            }
+        }
+
+        let prefix = watch_key_prefix(key).to_string();
+        server_metrics::incr_watchers_by_prefix(&prefix, 1);
+
+        Ok(Self {
+            prefix,
+            count: count.clone(),
+        })
+    }
+}
+
+impl Drop for WatcherGuard {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::Relaxed);
+        server_metrics::incr_watchers_by_prefix(&self.prefix, -1);
+    }
+}
+
+/// Forward watch responses from the dispatcher to the bounded buffer of a consumer.
+///
+/// If the buffer is full when a response arrives, the consumer is considered lagged:
+/// forwarding stops, which removes the watcher from the dispatcher,
+/// and [`WATCH_LAGGED_MESSAGE`] is sent via `lagged_tx` to terminate the stream
+/// after the buffered responses.
+///
+/// It never waits for the consumer, thus a slow consumer does not stall the dispatcher.
+/// Except when `initializing` is `true`: the initial flush is read from the state machine
+/// much faster than the consumer receives it, thus it waits for the consumer
+/// until the initialization-complete flag is forwarded.
+pub(crate) async fn relay_watch_responses(
+    mut rx: mpsc::Receiver<Result<WatchResponse, Status>>,
+    tx: mpsc::Sender<Result<WatchResponse, Status>>,
+    lagged_tx: oneshot::Sender<Status>,
+    mut initializing: bool,
+    name: String,
+) {
+    while let Some(item) = rx.recv().await {
+        if initializing {
+            if let Ok(resp) = &item {
+                initializing = !resp.is_initialization_complete_flag();
+            }
+
+            if tx.send(item).await.is_err() {
+                debug!("{}: watch stream is closed", name);
+                return;
+            }
+            continue;
+        }
+
+        match tx.try_send(item) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!(
+                    "{}: buffer is full; terminate the lagged watch stream",
+                    name
+                );
+                server_metrics::incr_watch_lagged();
+                lagged_tx.send(Status::data_loss(WATCH_LAGGED_MESSAGE)).ok();
+                return;
+            }
+            Err(TrySendError::Closed(_)) => {
+                debug!("{}: watch stream is closed", name);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicU64;
+    use std::time::Duration;
+
+    use databend_meta_types::SeqV;
+    use databend_meta_types::protobuf::WatchResponse;
+    use tokio::sync::mpsc;
+    use tokio::sync::oneshot;
+
+    use super::WatcherGuard;
+    use super::relay_watch_responses;
+    use super::watch_key_prefix;
+
+    #[test]
+    fn test_watch_key_prefix() {
+        assert_eq!("__fd_table", watch_key_prefix("__fd_table/1/2"));
+        assert_eq!("foo", watch_key_prefix("foo"));
+        assert_eq!("", watch_key_prefix(""));
+    }
+
+    #[test]
+    fn test_watcher_guard_limit() {
+        let count = Arc::new(AtomicU64::new(0));
+
+        let g1 = WatcherGuard::try_new(&count, Some(2), "a/b").unwrap();
+        let _g2 = WatcherGuard::try_new(&count, Some(2), "a/c").unwrap();
+
+        let err = WatcherGuard::try_new(&count, Some(2), "a/d").unwrap_err();
+        assert_eq!(tonic::Code::ResourceExhausted, err.code());
+        assert_eq!(2, count.load(std::sync::atomic::Ordering::Relaxed));
+
+        drop(g1);
+        let _g3 = WatcherGuard::try_new(&count, Some(2), "a/d").unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_relay_watch_responses_lagged() {
+        let (in_tx, in_rx) = mpsc::channel(4);
+        let (out_tx, mut out_rx) = mpsc::channel(1);
+        let (lagged_tx, lagged_rx) = oneshot::channel();
+
+        let relay = tokio::spawn(relay_watch_responses(
+            in_rx,
+            out_tx,
+            lagged_tx,
+            false,
+            "test".to_string(),
+        ));
+
+        // The consumer does not read: the first fills the buffer, the second lags.
+        in_tx
+            .send(Ok(WatchResponse::new_initialization_complete()))
+            .await
+            .unwrap();
+        in_tx
+            .send(Ok(WatchResponse::new_initialization_complete()))
+            .await
+            .unwrap();
+
+        relay.await.unwrap();
+
+        // The dispatcher side is closed.
+        assert!(in_tx.is_closed());
+
+        // Buffered responses are still delivered, then the stream ends.
+        assert!(out_rx.recv().await.unwrap().is_ok());
+        assert!(out_rx.recv().await.is_none());
+
+        let status = lagged_rx.await.unwrap();
+        assert_eq!(tonic::Code::DataLoss, status.code());
+    }
+
+    #[tokio::test]
+    async fn test_relay_watch_responses_initial_flush_waits_for_consumer() {
+        let (in_tx, in_rx) = mpsc::channel(4);
+        let (out_tx, mut out_rx) = mpsc::channel(2);
+        let (lagged_tx, mut lagged_rx) = oneshot::channel();
+
+        let relay = tokio::spawn(relay_watch_responses(
+            in_rx,
+            out_tx,
+            lagged_tx,
+            true,
+            "test".to_string(),
+        ));
+
+        // Flush more responses than the buffer holds.
+        let flush = tokio::spawn(async move {
+            for i in 0..20 {
+                let resp = WatchResponse::new_initialization_event(
+                    format!("k{}", i),
+                    SeqV::new(i, vec![]),
+                );
+                in_tx.send(Ok(resp)).await.unwrap();
+            }
+            in_tx
+                .send(Ok(WatchResponse::new_initialization_complete()))
+                .await
+                .unwrap();
+            in_tx
+        });
+
+        // A slow consumer receives every flushed response.
+        for _ in 0..20 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            let resp = out_rx.recv().await.unwrap().unwrap();
+            assert!(!resp.is_initialization_complete_flag());
+        }
+        let resp = out_rx.recv().await.unwrap().unwrap();
+        assert!(resp.is_initialization_complete_flag());
+
+        let in_tx = flush.await.unwrap();
+        assert!(lagged_rx.try_recv().is_err());
+
+        // After the initialization, a consumer that does not read lags.
+        for _ in 0..3 {
+            in_tx
+                .send(Ok(WatchResponse::new_initialization_complete()))
+                .await
+                .unwrap();
+        }
+
+        relay.await.unwrap();
+
+        let status = lagged_rx.await.unwrap();
+        assert_eq!(tonic::Code::DataLoss, status.code());
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Size limits of write requests, checked by the leader before a request is proposed.
+
+use databend_meta_types::Cmd;
+use databend_meta_types::LogEntry;
+use databend_meta_types::Operation;
+use databend_meta_types::TxnRequest;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::WriteLimitExceeded;
+use databend_meta_types::protobuf as pb;
+use databend_meta_types::protobuf::BooleanExpression;
+use log::warn;
+use prost::Message;
+
+use crate::configs::WriteLimitConfig;
+
+/// Check a log entry to propose against the write limits.
+///
+/// Only the commands written by clients are checked.
+pub fn check_log_entry(
+    limit: &WriteLimitConfig,
+    entry: &LogEntry,
+) -> Result<(), WriteLimitExceeded> {
+    match &entry.cmd {
+        Cmd::UpsertKV(upsert) => check_upsert_kv(limit, upsert),
+        Cmd::Transaction(txn) => check_txn(limit, txn),
+        _ => Ok(()),
+    }
+}
+
+/// Check an `UpsertKV` request against the write limits.
+pub fn check_upsert_kv(
+    limit: &WriteLimitConfig,
+    upsert: &UpsertKV,
+) -> Result<(), WriteLimitExceeded> {
+    check_key(limit, &upsert.key)?;
+
+    if let Operation::Update(value) = &upsert.value {
+        check_value(limit, &upsert.key, value)?;
+    }
+
+    Ok(())
+}
+
+/// Check a transaction against the write limits.
+///
+/// Operations in all branches are counted,
+/// because which branch is executed is unknown until the transaction is applied.
+pub fn check_txn(limit: &WriteLimitConfig, txn: &TxnRequest) -> Result<(), WriteLimitExceeded> {
+    let size = txn.encoded_len() as u64;
+    if let Some(max) = limit.max_txn_bytes.filter(|max| size > *max) {
+        return Err(reject(format!(
+            "transaction size {} bytes exceeds the limit {} bytes",
+            size, max
+        )));
+    }
+
+    let branch_ops = txn.operations.iter().flat_map(|c| c.operations.iter());
+    let ops = txn
+        .if_then
+        .iter()
+        .chain(txn.else_then.iter())
+        .chain(branch_ops);
+
+    let mut n_ops = 0u64;
+    for op in ops {
+        n_ops += 1;
+        check_txn_op(limit, op)?;
+    }
+
+    if let Some(max) = limit.max_txn_ops.filter(|max| n_ops > *max) {
+        return Err(reject(format!(
+            "transaction has {} operations, exceeds the limit {}",
+            n_ops, max
+        )));
+    }
+
+    for cond in txn.condition.iter() {
+        check_key(limit, &cond.key)?;
+    }
+
+    for c in txn.operations.iter() {
+        if let Some(predicate) = &c.predicate {
+            check_predicate(limit, predicate)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_predicate(
+    limit: &WriteLimitConfig,
+    expr: &BooleanExpression,
+) -> Result<(), WriteLimitExceeded> {
+    for cond in expr.conditions.iter() {
+        check_key(limit, &cond.key)?;
+    }
+    for sub in expr.sub_expressions.iter() {
+        check_predicate(limit, sub)?;
+    }
+    Ok(())
+}
+
+fn check_txn_op(limit: &WriteLimitConfig, op: &pb::TxnOp) -> Result<(), WriteLimitExceeded> {
+    let Some(request) = &op.request else {
+        return Ok(());
+    };
+
+    match request {
+        pb::txn_op::Request::Get(r) => check_key(limit, &r.key),
+        pb::txn_op::Request::Put(r) => {
+            check_key(limit, &r.key)?;
+            check_value(limit, &r.key, &r.value)
+        }
+        pb::txn_op::Request::Delete(r) => check_key(limit, &r.key),
+        pb::txn_op::Request::DeleteByPrefix(r) => check_key(limit, &r.prefix),
+        pb::txn_op::Request::FetchIncreaseU64(r) => check_key(limit, &r.key),
+        pb::txn_op::Request::PutSequential(r) => {
+            check_key(limit, &r.prefix)?;
+            check_key(limit, &r.sequence_key)?;
+            check_value(limit, &r.prefix, &r.value)
+        }
+    }
+}
+
+fn check_key(limit: &WriteLimitConfig, key: &str) -> Result<(), WriteLimitExceeded> {
+    if let Some(max) = limit.max_key_len.filter(|max| key.len() as u64 > *max) {
+        return Err(reject(format!(
+            "key length {} exceeds the limit {}: key prefix: '{}'",
+            key.len(),
+            max,
+            key_prefix(key)
+        )));
+    }
+    Ok(())
+}
+
+fn check_value(
+    limit: &WriteLimitConfig,
+    key: &str,
+    value: &[u8],
+) -> Result<(), WriteLimitExceeded> {
+    if let Some(max) = limit.max_value_size.filter(|max| value.len() as u64 > *max) {
+        return Err(reject(format!(
+            "value size {} bytes exceeds the limit {} bytes: key prefix: '{}'",
+            value.len(),
+            max,
+            key_prefix(key)
+        )));
+    }
+    Ok(())
+}
+
+/// A short prefix of a key for the error message, since the key itself may be too large.
+fn key_prefix(key: &str) -> &str {
+    const MAX: usize = 64;
+
+    if key.len() <= MAX {
+        return key;
+    }
+
+    let mut end = MAX;
+    while !key.is_char_boundary(end) {
+        end -= 1;
+    }
+    &key[..end]
+}
+
+fn reject(msg: String) -> WriteLimitExceeded {
+    warn!("write request rejected: {}", msg);
+    WriteLimitExceeded::new(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use databend_meta_types::MatchSeq;
+    use databend_meta_types::TxnCondition;
+    use databend_meta_types::TxnOp;
+
+    use super::*;
+
+    fn limit() -> WriteLimitConfig {
+        WriteLimitConfig {
+            max_key_len: Some(8),
+            max_value_size: Some(4),
+            max_txn_ops: Some(2),
+            max_txn_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_check_log_entry() {
+        let l = limit();
+
+        let upsert = LogEntry::new(Cmd::UpsertKV(UpsertKV::update("123456789", b"1")));
+        let e = check_log_entry(&l, &upsert).unwrap_err();
+        assert!(e.msg.contains("key length 9"));
+
+        let txn = TxnRequest::new(vec![], vec![TxnOp::put("a", b"12345".to_vec())]);
+        let e = check_log_entry(&l, &LogEntry::new(Cmd::Transaction(txn))).unwrap_err();
+        assert!(e.msg.contains("value size 5 bytes"));
+
+        // Commands not written by clients are not checked.
+        let remove_node = LogEntry::new(Cmd::RemoveNode { node_id: 1 });
+        assert!(check_log_entry(&l, &remove_node).is_ok());
+    }
+
+    #[test]
+    fn test_check_upsert_kv() {
+        let l = limit();
+
+        let ok = UpsertKV::update("k", b"1234");
+        assert!(check_upsert_kv(&l, &ok).is_ok());
+
+        let long_key = UpsertKV::update("123456789", b"1");
+        let e = check_upsert_kv(&l, &long_key).unwrap_err();
+        assert!(e.msg.contains("key length 9 exceeds the limit 8"));
+
+        let large_value = UpsertKV::update("k", b"12345");
+        let e = check_upsert_kv(&l, &large_value).unwrap_err();
+        assert!(
+            e.msg
+                .contains("value size 5 bytes exceeds the limit 4 bytes")
+        );
+
+        // Delete carries no value, only the key is checked.
+        let delete = UpsertKV::new("k", MatchSeq::GE(0), Operation::Delete, None);
+        assert!(check_upsert_kv(&l, &delete).is_ok());
+
+        // No limit
+        let no_limit = WriteLimitConfig::default();
+        assert!(check_upsert_kv(&no_limit, &large_value).is_ok());
+    }
+
+    #[test]
+    fn test_check_txn_ops() {
+        let l = limit();
+
+        let txn = TxnRequest::new(vec![], vec![TxnOp::put("a", b"1".to_vec())])
+            .with_else(vec![TxnOp::get("b")]);
+        assert!(check_txn(&l, &txn).is_ok());
+
+        let txn = txn.push_branch(Some(BooleanExpression::default()), [TxnOp::delete("c")]);
+        let e = check_txn(&l, &txn).unwrap_err();
+        assert!(
+            e.msg
+                .contains("transaction has 3 operations, exceeds the limit 2")
+        );
+    }
+
+    #[test]
+    fn test_check_txn_keys_and_values() {
+        let l = limit();
+
+        let txn = TxnRequest::new(vec![], vec![TxnOp::put("a", b"12345".to_vec())]);
+        let e = check_txn(&l, &txn).unwrap_err();
+        assert!(e.msg.contains("value size 5 bytes"));
+
+        let txn = TxnRequest::new(vec![], vec![TxnOp::delete("123456789")]);
+        let e = check_txn(&l, &txn).unwrap_err();
+        assert!(e.msg.contains("key length 9"));
+
+        let txn = TxnRequest::new(vec![TxnCondition::eq_seq("123456789", 1)], vec![]);
+        let e = check_txn(&l, &txn).unwrap_err();
+        assert!(e.msg.contains("key length 9"));
+    }
+
+    #[test]
+    fn test_check_txn_bytes() {
+        let l = WriteLimitConfig {
+            max_txn_bytes: Some(16),
+            ..Default::default()
+        };
+
+        let txn = TxnRequest::new(vec![], vec![TxnOp::put("a", b"1".to_vec())]);
+        assert!(check_txn(&l, &txn).is_ok());
+
+        let txn = TxnRequest::new(vec![], vec![TxnOp::put("a", vec![b'x'; 32])]);
+        let e = check_txn(&l, &txn).unwrap_err();
+        assert!(e.msg.contains("exceeds the limit 16 bytes"));
+    }
+
+    #[test]
+    fn test_key_prefix() {
+        assert_eq!("abc", key_prefix("abc"));
+        assert_eq!(64, key_prefix(&"x".repeat(100)).len());
+        // Do not split a multi-byte char.
+        let k = format!("{}é", "x".repeat(63));
+        assert_eq!(63, key_prefix(&k).len());
+    }
+}
@@ -31,10 +31,13 @@ use databend_base::counter;
 use prometheus_client::encoding::text::encode as prometheus_encode;
 
 pub mod server_metrics {
+    use std::collections::BTreeSet;
     use std::sync::LazyLock;
+    use std::sync::Mutex;
 
     use databend_meta_raft_store::raft_log_v004::RaftLogStat;
     use databend_meta_types::raft_types::NodeId;
+    use prometheus_client::encoding::EncodeLabelSet;
     use prometheus_client::metrics::counter::Counter;
     use prometheus_client::metrics::family::Family;
     use prometheus_client::metrics::gauge::Gauge;
@@ -47,6 +50,24 @@ pub mod server_metrics {
         };
     }
 
+    /// The max number of distinct prefixes in `watchers_by_prefix`.
+    ///
+    /// Watchers of other prefixes are counted under [`OTHER_WATCH_PREFIX`].
+    pub const MAX_WATCH_PREFIX_LABELS: usize = 64;
+
+    /// The prefix label for the watchers whose prefix exceeds [`MAX_WATCH_PREFIX_LABELS`].
+    pub const OTHER_WATCH_PREFIX: &str = "_other";
+
+    #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+    pub struct PrefixLabels {
+        pub prefix: String,
+    }
+
+    #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+    pub struct ReasonLabels {
+        pub reason: String,
+    }
+
     #[derive(Default, Debug, Clone)]
     pub struct SnapshotStat {
         /// The total number of blocks in the snapshot.
@@ -72,8 +93,12 @@ pub mod server_metrics {
         is_leader: Gauge,
         node_is_health: Gauge,
         leader_changes: Counter,
+        leader_preference_transfers: Counter,
         applying_snapshot: Gauge,
 
+        /// Number of snapshots triggered by the snapshot policy, by the reason.
+        snapshot_triggers: Family<ReasonLabels, Counter>,
+
         /// Primary index is index by string key. Each primary index has an optional expire index key.
         ///
         /// `snapshot_key_count = snapshot_primary_index_count + snapshot_expire_index_count`
@@ -103,6 +128,20 @@ pub mod server_metrics {
         proposals_failed: Counter,
         read_failed: Counter,
         watchers: Gauge,
+
+        /// Number of watch streams by the prefix of the watched key.
+        ///
+        /// At most [`MAX_WATCH_PREFIX_LABELS`] prefixes are labeled, see `watch_prefixes`.
+        watchers_by_prefix: Family<PrefixLabels, Gauge>,
+
+        /// The prefixes that have a label in `watchers_by_prefix`.
+        ///
+        /// It only grows, so that a watcher is counted under the same label when it is removed.
+        watch_prefixes: Mutex<BTreeSet<String>>,
+
+        /// Number of watch streams terminated because the consumer is too slow.
+        watch_lagged: Counter,
+
         version: Family<Vec<(String, String)>, Gauge>,
     }
 
@@ -113,7 +152,9 @@ pub mod server_metrics {
                 is_leader: Gauge::default(),
                 node_is_health: Gauge::default(),
                 leader_changes: Counter::default(),
+                leader_preference_transfers: Counter::default(),
                 applying_snapshot: Gauge::default(),
+                snapshot_triggers: Family::default(),
 
                 snapshot_key_count: Gauge::default(),
                 snapshot_primary_index_count: Gauge::default(),
@@ -136,6 +177,9 @@ pub mod server_metrics {
                 proposals_failed: Counter::default(),
                 read_failed: Counter::default(),
                 watchers: Gauge::default(),
+                watchers_by_prefix: Family::default(),
+                watch_prefixes: Mutex::new(BTreeSet::new()),
+                watch_lagged: Counter::default(),
                 version: Family::default(),
             };
 
@@ -156,11 +200,21 @@ pub mod server_metrics {
                 "leader changes",
                 metrics.leader_changes.clone(),
             );
+            registry.register(
+                key!("leader_preference_transfers"),
+                "number of leadership transfers to the preferred zone",
+                metrics.leader_preference_transfers.clone(),
+            );
             registry.register(
                 key!("applying_snapshot"),
                 "if this node is applying snapshot",
                 metrics.applying_snapshot.clone(),
             );
+            registry.register(
+                key!("snapshot_triggers"),
+                "number of snapshots triggered by the snapshot policy, by reason",
+                metrics.snapshot_triggers.clone(),
+            );
             registry.register(
                 key!("snapshot_key_count"),
                 "number of keys in the last snapshot",
@@ -285,6 +339,16 @@ pub mod server_metrics {
                 metrics.read_failed.clone(),
             );
             registry.register(key!("watchers"), "watchers", metrics.watchers.clone());
+            registry.register(
+                key!("watchers_by_prefix"),
+                "watchers by the prefix of the watched key",
+                metrics.watchers_by_prefix.clone(),
+            );
+            registry.register(
+                key!("watch_lagged"),
+                "watch streams terminated because the consumer is too slow",
+                metrics.watch_lagged.clone(),
+            );
             registry.register(key!("version"), "version", metrics.version.clone());
             metrics
         }
@@ -308,11 +372,24 @@ pub mod server_metrics {
         SERVER_METRICS.leader_changes.inc();
     }
 
+    pub fn incr_leader_preference_transfer() {
+        SERVER_METRICS.leader_preference_transfers.inc();
+    }
+
     /// Whether or not state-machine is applying snapshot.
     pub fn incr_applying_snapshot(cnt: i64) {
         SERVER_METRICS.applying_snapshot.inc_by(cnt);
     }
 
+    pub fn incr_snapshot_trigger(reason: &str) {
+        SERVER_METRICS
+            .snapshot_triggers
+            .get_or_create(&ReasonLabels {
+                reason: reason.to_string(),
+            })
+            .inc();
+    }
+
     pub fn set_snapshot_key_count(n: u64) {
         SERVER_METRICS.snapshot_key_count.set(n as i64);
     }
@@ -395,6 +472,35 @@ pub mod server_metrics {
         SERVER_METRICS.watchers.inc_by(cnt);
     }
 
+    pub fn incr_watchers_by_prefix(prefix: &str, cnt: i64) {
+        let prefix = watch_prefix_label(&SERVER_METRICS.watch_prefixes, prefix);
+
+        SERVER_METRICS
+            .watchers_by_prefix
+            .get_or_create(&PrefixLabels { prefix })
+            .inc_by(cnt);
+    }
+
+    /// Returns the label of a watched prefix, adding it to `labeled` if there is room.
+    fn watch_prefix_label(labeled: &Mutex<BTreeSet<String>>, prefix: &str) -> String {
+        let mut labeled = labeled.lock().unwrap();
+
+        if labeled.contains(prefix) {
+            return prefix.to_string();
+        }
+
+        if labeled.len() < MAX_WATCH_PREFIX_LABELS {
+            labeled.insert(prefix.to_string());
+            return prefix.to_string();
+        }
+
+        OTHER_WATCH_PREFIX.to_string()
+    }
+
+    pub fn incr_watch_lagged() {
+        SERVER_METRICS.watch_lagged.inc();
+    }
+
     pub fn set_version(semver: String, sha: String) {
         let labels = &vec![
             ("component".to_string(), "metasrv".to_string()),
@@ -403,6 +509,30 @@ pub mod server_metrics {
         ];
         SERVER_METRICS.version.get_or_create(labels).set(1);
     }
+
+    #[cfg(test)]
+    mod tests {
+        use std::collections::BTreeSet;
+        use std::sync::Mutex;
+
+        use super::MAX_WATCH_PREFIX_LABELS;
+        use super::OTHER_WATCH_PREFIX;
+        use super::watch_prefix_label;
+
+        #[test]
+        fn test_watch_prefix_label_is_bounded() {
+            let labeled = Mutex::new(BTreeSet::new());
+
+            for i in 0..MAX_WATCH_PREFIX_LABELS {
+                let prefix = format!("p{}", i);
+                assert_eq!(prefix, watch_prefix_label(&labeled, &prefix));
+            }
+
+            assert_eq!(OTHER_WATCH_PREFIX, watch_prefix_label(&labeled, "new"));
+            assert_eq!("p0", watch_prefix_label(&labeled, "p0"));
+            assert_eq!(MAX_WATCH_PREFIX_LABELS, labeled.lock().unwrap().len());
+        }
+    }
 }
 
 pub mod raft_metrics {
@@ -456,6 +586,20 @@ pub mod raft_metrics {
             snapshot_recv_seconds: Family<FromLabels, Histogram>,
             snapshot_recv_success: Family<FromLabels, Counter>,
             snapshot_recv_failures: Family<FromLabels, Counter>,
+
+            /// Raft requests rejected because of absent or mismatched auth secret.
+            ///
+            /// Not labeled by the remote address: unauthenticated callers must not be able to
+            /// grow the metric family.
+            auth_rejected: Counter,
+
+            /// Bytes received on the connections to the raft service, after compression.
+            ///
+            /// Not labeled by peer: the address of an incoming connection has an ephemeral port.
+            wire_recv_bytes: Counter,
+
+            /// Bytes sent on the connections to the raft service, after compression.
+            wire_sent_bytes: Counter,
         }
 
         impl RaftMetrics {
@@ -481,6 +625,9 @@ pub mod raft_metrics {
                     }), // 1s ~ 1024s
                     snapshot_recv_success: Family::default(),
                     snapshot_recv_failures: Family::default(),
+                    auth_rejected: Counter::default(),
+                    wire_recv_bytes: Counter::default(),
+                    wire_sent_bytes: Counter::default(),
                 };
 
                 let mut registry = crate::metrics::registry::load_global_registry();
@@ -546,6 +693,21 @@ pub mod raft_metrics {
                     "snapshot recv failures",
                     metrics.snapshot_recv_failures.clone(),
                 );
+                registry.register(
+                    key!("auth_rejected"),
+                    "raft requests rejected by auth",
+                    metrics.auth_rejected.clone(),
+                );
+                registry.register(
+                    key!("wire_recv_bytes"),
+                    "recv bytes on the connections, after compression",
+                    metrics.wire_recv_bytes.clone(),
+                );
+                registry.register(
+                    key!("wire_sent_bytes"),
+                    "sent bytes on the connections, after compression",
+                    metrics.wire_sent_bytes.clone(),
+                );
                 metrics
             }
         }
@@ -589,6 +751,14 @@ pub mod raft_metrics {
                 .inc_by(bytes);
         }
 
+        pub fn incr_wire_recv_bytes(bytes: u64) {
+            RAFT_METRICS.wire_recv_bytes.inc_by(bytes);
+        }
+
+        pub fn incr_wire_sent_bytes(bytes: u64) {
+            RAFT_METRICS.wire_sent_bytes.inc_by(bytes);
+        }
+
         pub fn incr_sendto_result(id: &NodeId, success: bool) {
             if success {
                 // success is not collected.
@@ -663,6 +833,10 @@ pub mod raft_metrics {
             .get_or_create(&FromLabels { from: addr })
             .inc();
         }
+
+        pub fn incr_auth_rejected() {
+            RAFT_METRICS.auth_rejected.inc();
+        }
     }
 
     pub mod storage {
@@ -672,6 +846,8 @@ pub mod raft_metrics {
         use prometheus_client::metrics::counter::Counter;
         use prometheus_client::metrics::family::Family;
         use prometheus_client::metrics::gauge::Gauge;
+        use prometheus_client::metrics::histogram::Histogram;
+        use prometheus_client::metrics::histogram::exponential_buckets;
 
         use crate::metrics::registry::load_global_registry;
 
@@ -697,6 +873,15 @@ pub mod raft_metrics {
 
             /// The number of entries written to the snapshot file.
             snapshot_written_entries: Counter,
+
+            /// The number of in-memory compaction runs that merged at least one level.
+            in_memory_compactions: Counter,
+
+            /// The number of in-memory levels merged into another level.
+            in_memory_compaction_levels_merged: Counter,
+
+            /// Time spent in an in-memory compaction run.
+            in_memory_compaction_seconds: Histogram,
         }
 
         impl StorageMetrics {
@@ -707,6 +892,13 @@ pub mod raft_metrics {
 
                     snapshot_building: Gauge::default(),
                     snapshot_written_entries: Counter::default(),
+
+                    in_memory_compactions: Counter::default(),
+                    in_memory_compaction_levels_merged: Counter::default(),
+                    // 0.0001s ~ 13s
+                    in_memory_compaction_seconds: Histogram::new(exponential_buckets(
+                        0.0001f64, 2f64, 18,
+                    )),
                 };
 
                 let mut registry = load_global_registry();
@@ -732,6 +924,22 @@ pub mod raft_metrics {
                     metrics.snapshot_written_entries.clone(),
                 );
 
+                registry.register(
+                    key!("in_memory_compactions"),
+                    "The number of in-memory compaction runs that merged at least one level.",
+                    metrics.in_memory_compactions.clone(),
+                );
+                registry.register(
+                    key!("in_memory_compaction_levels_merged"),
+                    "The number of in-memory levels merged into another level.",
+                    metrics.in_memory_compaction_levels_merged.clone(),
+                );
+                registry.register(
+                    key!("in_memory_compaction_seconds"),
+                    "Time spent in an in-memory compaction run.",
+                    metrics.in_memory_compaction_seconds.clone(),
+                );
+
                 metrics
             }
         }
@@ -784,6 +992,20 @@ pub mod raft_metrics {
         pub fn incr_snapshot_written_entries() {
             STORAGE_METRICS.snapshot_written_entries.inc();
         }
+
+        /// Record an in-memory compaction run that merged `levels_merged` levels in `seconds`.
+        pub fn observe_in_memory_compaction(levels_merged: u64, seconds: f64) {
+            STORAGE_METRICS
+                .in_memory_compaction_seconds
+                .observe(seconds);
+
+            if levels_merged > 0 {
+                STORAGE_METRICS.in_memory_compactions.inc();
+                STORAGE_METRICS
+                    .in_memory_compaction_levels_merged
+                    .inc_by(levels_merged);
+            }
+        }
     }
 }
 
@@ -793,7 +1015,9 @@ pub mod network_metrics {
 
     use databend_meta_types::protobuf::WatchResponse;
     use log::error;
+    use prometheus_client::encoding::EncodeLabelSet;
     use prometheus_client::metrics::counter::Counter;
+    use prometheus_client::metrics::family::Family;
     use prometheus_client::metrics::gauge::Gauge;
     use prometheus_client::metrics::histogram::Histogram;
 
@@ -805,6 +1029,11 @@ pub mod network_metrics {
         };
     }
 
+    #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+    pub struct RpcKindLabels {
+        pub kind: String,
+    }
+
     #[derive(Debug)]
     struct NetworkMetrics {
         rpc_delay_ms: Histogram,
@@ -830,6 +1059,15 @@ pub mod network_metrics {
 
         /// Number of items sent in a stream list response.
         stream_list_item_sent: Counter,
+
+        /// Number of requests rejected by rate limits or in-flight limits, by kind of RPC.
+        req_limited: Family<RpcKindLabels, Counter>,
+
+        /// Bytes sent on the connections, after compression.
+        wire_sent_bytes: Counter,
+
+        /// Bytes received on the connections, after compression.
+        wire_recv_bytes: Counter,
     }
 
     impl NetworkMetrics {
@@ -858,6 +1096,11 @@ pub mod network_metrics {
                 stream_get_item_sent: Counter::default(),
                 stream_mget_item_sent: Counter::default(),
                 stream_list_item_sent: Counter::default(),
+
+                req_limited: Family::default(),
+
+                wire_sent_bytes: Counter::default(),
+                wire_recv_bytes: Counter::default(),
             };
 
             let mut registry = load_global_registry();
@@ -917,6 +1160,23 @@ pub mod network_metrics {
                 metrics.stream_list_item_sent.clone(),
             );
 
+            registry.register(
+                key!("req_limited"),
+                "Number of requests rejected by rate limits or in-flight limits",
+                metrics.req_limited.clone(),
+            );
+
+            registry.register(
+                key!("wire_sent_bytes"),
+                "sent bytes on the connections, after compression",
+                metrics.wire_sent_bytes.clone(),
+            );
+            registry.register(
+                key!("wire_recv_bytes"),
+                "recv bytes on the connections, after compression",
+                metrics.wire_recv_bytes.clone(),
+            );
+
             metrics
         }
     }
@@ -952,10 +1212,27 @@ pub mod network_metrics {
         NETWORK_METRICS.recv_bytes.inc_by(bytes);
     }
 
+    pub fn incr_wire_sent_bytes(bytes: u64) {
+        NETWORK_METRICS.wire_sent_bytes.inc_by(bytes);
+    }
+
+    pub fn incr_wire_recv_bytes(bytes: u64) {
+        NETWORK_METRICS.wire_recv_bytes.inc_by(bytes);
+    }
+
     pub fn incr_request_inflights(cnt: i64) {
         NETWORK_METRICS.req_inflights.inc_by(cnt);
     }
 
+    pub fn incr_request_limited(kind: &str) {
+        NETWORK_METRICS
+            .req_limited
+            .get_or_create(&RpcKindLabels {
+                kind: kind.to_string(),
+            })
+            .inc();
+    }
+
     pub fn incr_request_result(success: bool) {
         if success {
             NETWORK_METRICS.req_success.inc();
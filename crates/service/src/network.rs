@@ -56,6 +56,7 @@ use databend_meta_types::raft_types::Unreachable;
 use databend_meta_types::raft_types::Vote;
 use databend_meta_types::raft_types::VoteRequest;
 use databend_meta_types::raft_types::VoteResponse;
+use databend_meta_types::snapshot_checksum::SnapshotChecksum;
 use fastrace::func_name;
 use futures::FutureExt;
 use futures::StreamExt;
@@ -180,11 +181,14 @@ impl<SP: SpawnApi> Network<SP> {
             ))
             .await?;
 
+        let peer = self.sto.get_node(&self.target).await;
+
         let client = RaftClientApi::new(
             self.target,
             self.endpoint.clone(),
             channel,
             &self.sto.config,
+            peer.as_ref(),
         );
 
         info!(
@@ -473,30 +477,16 @@ impl<SP: SpawnApi> Network<SP> {
 
         let mut c = std::pin::pin!(cancel);
 
-        // Stream KV data from the snapshot DB
-        let strm = db.inner_range();
-
-        // Discard tombstones and convert SeqMarked to SeqData and then to protobuf SeqV
-        let strm = strm.try_filter_map(|(k, v)| async move {
-            let seq_data: Option<SeqData<_>> = v.into();
-            let Some(seq_data) = seq_data else {
-                // Tombstone, skip
-                return Ok(None);
-            };
-
-            let seq_data = SeqData::<MetaValue>::decode_from(seq_data)?;
-            let seq_v = SeqV::from(seq_data);
-            let pb_seq_v = pb::SeqV::from(seq_v);
-            let item = pb::StreamItem::new(k, Some(pb_seq_v));
-            Ok(Some(item))
-        });
-
-        // Chunk the stream into batches of 64 items for efficiency
-        let mut strm = strm.try_chunks(64).boxed();
+        // Stream KV data from the snapshot DB,
+        // chunked into batches of 64 items for efficiency
+        let mut strm = db.inner_range().try_chunks(64).boxed();
 
         let mut kv_count = 0u64;
 
-        while let Some(chunk) = strm.try_next().await.map_err(|err| {
+        // Checksum of the sent key-values, for the receiver to verify.
+        let mut checksum = SnapshotChecksum::new();
+
+        while let Some(raw_chunk) = strm.try_next().await.map_err(|err| {
             StorageError::read_snapshot(Some(snapshot_meta.signature()), (&err.1).into())
         })? {
             // Check for cancellation
@@ -504,6 +494,29 @@ impl<SP: SpawnApi> Network<SP> {
                 return Err(err.into());
             }
 
+            let mut chunk = Vec::with_capacity(raw_chunk.len());
+
+            for (k, v) in raw_chunk {
+                checksum.update(&k, &v);
+
+                // Discard tombstones and convert SeqMarked to SeqData and then to protobuf SeqV
+                let seq_data: Option<SeqData<_>> = v.into();
+                let Some(seq_data) = seq_data else {
+                    continue;
+                };
+
+                let seq_data = SeqData::<MetaValue>::decode_from(seq_data).map_err(|e| {
+                    StorageError::read_snapshot(Some(snapshot_meta.signature()), (&e).into())
+                })?;
+                let seq_v = SeqV::from(seq_data);
+                let pb_seq_v = pb::SeqV::from(seq_v);
+                chunk.push(pb::StreamItem::new(k, Some(pb_seq_v)));
+            }
+
+            if chunk.is_empty() {
+                continue;
+            }
+
             // Total length of keys and values in this chunk
             let total_kv_len = chunk
                 .iter()
@@ -532,7 +545,10 @@ impl<SP: SpawnApi> Network<SP> {
             raft_metrics::network::incr_sendto_bytes(&target, total_kv_len as u64);
         }
 
-        info!("V004 snapshot streaming: completed {} KV entries", kv_count);
+        info!(
+            "V004 snapshot streaming: completed {} KV entries, checksum: {:?}",
+            kv_count, checksum
+        );
 
         // Send commit entry
         let sys_data_json = serde_json::to_string(db.sys_data()).map_err(|e| {
@@ -546,6 +562,7 @@ impl<SP: SpawnApi> Network<SP> {
             snapshot_id: snapshot_meta.snapshot_id.to_string(),
             sys_data: sys_data_json,
             vote: Some(pb_vote),
+            checksum: Some(checksum.checksum()),
         };
 
         let final_entry = InstallEntryV004 {
@@ -0,0 +1,167 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authentication of the calls between meta-service nodes to `RaftService`.
+//!
+//! When `RaftConfig::raft_auth_secret` is set, every request sent to a `RaftService`
+//! carries the cluster secret in its metadata,
+//! and every received request without a matching secret is rejected with `Unauthenticated`.
+
+use std::fmt;
+use std::sync::Arc;
+
+use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_types::protobuf::raft_service_client::RaftServiceClient;
+use tonic::Request;
+use tonic::Status;
+use tonic::codegen::InterceptedService;
+use tonic::metadata::MetadataMap;
+use tonic::metadata::MetadataValue;
+use tonic::service::Interceptor;
+use tonic::transport::Channel;
+
+/// The metadata key that carries the cluster secret.
+pub const RAFT_AUTH_KEY: &str = "raft-auth-bin";
+
+/// A `RaftService` client that fills in the cluster secret for every request.
+pub type AuthRaftServiceClient = RaftServiceClient<InterceptedService<Channel, RaftAuth>>;
+
+/// Fills in and verifies the cluster secret for `RaftService` calls.
+///
+/// Without a secret configured, no metadata is added and every request is accepted.
+#[derive(Clone, Default)]
+pub struct RaftAuth {
+    secret: Option<Arc<[u8]>>,
+}
+
+impl fmt::Debug for RaftAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the secret.
+        f.debug_struct("RaftAuth")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+impl RaftAuth {
+    pub fn new(secret: Option<&str>) -> Self {
+        Self {
+            secret: secret.map(|s| Arc::from(s.as_bytes())),
+        }
+    }
+
+    pub fn from_config(config: &RaftConfig) -> Self {
+        Self::new(config.raft_auth_secret.as_ref().map(|s| s.expose()))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Create a `RaftService` client on the channel that sends the secret with every request.
+    pub fn new_client(&self, channel: Channel) -> AuthRaftServiceClient {
+        RaftServiceClient::with_interceptor(channel, self.clone())
+    }
+
+    /// Connect to a `RaftService` at `addr` in form of `host:port`.
+    pub async fn connect(
+        &self,
+        addr: impl fmt::Display,
+    ) -> Result<AuthRaftServiceClient, tonic::transport::Error> {
+        let channel = tonic::transport::Endpoint::new(format!("http://{}", addr))?
+            .connect()
+            .await?;
+        Ok(self.new_client(channel))
+    }
+
+    /// Check the secret in the request metadata.
+    pub fn verify(&self, metadata: &MetadataMap) -> Result<(), Status> {
+        let Some(secret) = &self.secret else {
+            return Ok(());
+        };
+
+        let token = metadata
+            .get_bin(RAFT_AUTH_KEY)
+            .ok_or_else(|| Status::unauthenticated("raft auth secret is absent"))?
+            .to_bytes()
+            .map_err(|e| Status::unauthenticated(format!("invalid raft auth secret: {}", e)))?;
+
+        if constant_time_eq(&token, secret) {
+            Ok(())
+        } else {
+            Err(Status::unauthenticated("raft auth secret mismatch"))
+        }
+    }
+}
+
+impl Interceptor for RaftAuth {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(secret) = &self.secret {
+            let meta_value = MetadataValue::from_bytes(secret.as_ref());
+            req.metadata_mut().insert_bin(RAFT_AUTH_KEY, meta_value);
+        }
+        Ok(req)
+    }
+}
+
+/// Compare two byte strings in time that does not depend on where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raft_auth_verify() {
+        let mut auth = RaftAuth::new(Some("foo"));
+
+        let req = auth.call(Request::new(())).unwrap();
+        assert!(auth.verify(req.metadata()).is_ok());
+
+        // Absent secret
+        let err = auth.verify(&MetadataMap::new()).unwrap_err();
+        assert_eq!(tonic::Code::Unauthenticated, err.code());
+
+        // Mismatched secret
+        let req = RaftAuth::new(Some("bar")).call(Request::new(())).unwrap();
+        let err = auth.verify(req.metadata()).unwrap_err();
+        assert_eq!(tonic::Code::Unauthenticated, err.code());
+
+        // Disabled auth accepts any request and sends no secret.
+        let mut disabled = RaftAuth::new(None);
+        let req = disabled.call(Request::new(())).unwrap();
+        assert!(req.metadata().get_bin(RAFT_AUTH_KEY).is_none());
+        assert!(disabled.verify(&MetadataMap::new()).is_ok());
+    }
+
+    #[test]
+    fn test_raft_auth_debug_hides_secret() {
+        let auth = RaftAuth::new(Some("foo"));
+        assert_eq!("RaftAuth { enabled: true }", format!("{:?}", auth));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
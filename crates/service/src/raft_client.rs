@@ -15,12 +15,18 @@
 use databend_base::counter;
 use databend_meta_raft_store::config::RaftConfig;
 use databend_meta_types::Endpoint;
-use databend_meta_types::protobuf::raft_service_client::RaftServiceClient;
+use databend_meta_types::GrpcCompression;
+use databend_meta_types::Node;
 use databend_meta_types::raft_types::NodeId;
+use databend_meta_version::Version;
 use log::debug;
+use log::info;
+use tonic::codec::CompressionEncoding;
 use tonic::transport::channel::Channel;
 
 use crate::metrics::raft_metrics;
+use crate::raft_auth::AuthRaftServiceClient;
+use crate::raft_auth::RaftAuth;
 
 /// A metrics reporter of active raft peers.
 #[derive(Debug)]
@@ -36,17 +42,35 @@ impl counter::Counter for PeerCounter {
     }
 }
 
+/// The lowest version of a raft node that accepts compressed raft requests.
+///
+/// An older node rejects every compressed request,
+/// thus requests to a peer are compressed only if the version it reported is at least this.
+pub(crate) const RAFT_COMPRESSION_MIN_VERSION: Version = Version::new(260205, 4, 0);
+
 /// RaftClient is a grpc client bound with a metrics reporter..
-pub type RaftClient = counter::Counted<PeerCounter, RaftServiceClient<Channel>>;
+pub type RaftClient = counter::Counted<PeerCounter, AuthRaftServiceClient>;
 
 /// Defines the API of the client to a raft node.
 pub trait RaftClientApi {
-    fn new(target: NodeId, endpoint: Endpoint, channel: Channel, config: &RaftConfig) -> Self;
+    fn new(
+        target: NodeId,
+        endpoint: Endpoint,
+        channel: Channel,
+        config: &RaftConfig,
+        peer: Option<&Node>,
+    ) -> Self;
     fn endpoint(&self) -> &Endpoint;
 }
 
 impl RaftClientApi for RaftClient {
-    fn new(target: NodeId, endpoint: Endpoint, channel: Channel, config: &RaftConfig) -> Self {
+    fn new(
+        target: NodeId,
+        endpoint: Endpoint,
+        channel: Channel,
+        config: &RaftConfig,
+        peer: Option<&Node>,
+    ) -> Self {
         let endpoint_str = endpoint.to_string();
 
         debug!(
@@ -54,10 +78,8 @@ impl RaftClientApi for RaftClient {
             target, endpoint_str
         );
 
-        let max_msg_size = config.raft_grpc_max_message_size();
-        let cli = RaftServiceClient::new(channel)
-            .max_decoding_message_size(max_msg_size)
-            .max_encoding_message_size(max_msg_size);
+        let cli = RaftAuth::from_config(config).new_client(channel);
+        let cli = configure_raft_client(cli, config, peer);
         counter::Counted::new(cli, PeerCounter {
             target,
             endpoint,
@@ -69,3 +91,93 @@ impl RaftClientApi for RaftClient {
         &self.counter().endpoint
     }
 }
+
+/// Apply the message size limits and the compression in `config` to a raft client to `peer`.
+///
+/// Compressed responses are always accepted;
+/// requests are compressed as [`raft_compression_to`] decides.
+pub(crate) fn configure_raft_client(
+    client: AuthRaftServiceClient,
+    config: &RaftConfig,
+    peer: Option<&Node>,
+) -> AuthRaftServiceClient {
+    let max_msg_size = config.raft_grpc_max_message_size();
+    let mut client = client
+        .max_decoding_message_size(max_msg_size)
+        .max_encoding_message_size(max_msg_size);
+
+    for encoding in GrpcCompression::ACCEPTED {
+        client = client.accept_compressed(encoding);
+    }
+
+    if let Some(encoding) = raft_compression_to(config, peer) {
+        client = client.send_compressed(encoding);
+    }
+
+    client
+}
+
+/// Returns the encoding to compress the requests to `peer` with.
+///
+/// Requests are sent uncompressed if `RaftConfig::raft_compression` is `none`,
+/// or if `peer` is unknown or has not reported a version of at least [`RAFT_COMPRESSION_MIN_VERSION`],
+/// e.g., during a rolling upgrade from a version that does not accept compressed requests.
+pub(crate) fn raft_compression_to(
+    config: &RaftConfig,
+    peer: Option<&Node>,
+) -> Option<CompressionEncoding> {
+    let encoding = config.raft_compression.encoding()?;
+
+    let peer_version = peer
+        .and_then(|n| n.binary_version.as_ref())
+        .and_then(|v| semver::Version::parse(v).ok())
+        .map(Version::from);
+
+    if peer_version.is_some_and(|v| v >= RAFT_COMPRESSION_MIN_VERSION) {
+        return Some(encoding);
+    }
+
+    info!(
+        "send uncompressed raft requests to {}: version {:?} < {}",
+        peer.map(|n| n.name.as_str()).unwrap_or("unknown node"),
+        peer_version,
+        RAFT_COMPRESSION_MIN_VERSION
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raft_compression_to() {
+        let config = RaftConfig {
+            raft_compression: GrpcCompression::Zstd,
+            ..Default::default()
+        };
+
+        let node = |v: Option<&str>| Node::new("n1", Endpoint::new("e1", 1)).with_binary_version(v);
+
+        let old = node(Some("1.2.770"));
+        // The last release before compression is accepted.
+        let released = node(Some("260205.3.0"));
+        let new = node(Some(&RAFT_COMPRESSION_MIN_VERSION.to_string()));
+        let unknown = node(None);
+
+        assert_eq!(
+            Some(CompressionEncoding::Zstd),
+            raft_compression_to(&config, Some(&new))
+        );
+        assert_eq!(None, raft_compression_to(&config, Some(&old)));
+        assert_eq!(None, raft_compression_to(&config, Some(&released)));
+        assert_eq!(None, raft_compression_to(&config, Some(&unknown)));
+        assert_eq!(None, raft_compression_to(&config, None));
+
+        // Disabled
+        assert_eq!(
+            None,
+            raft_compression_to(&RaftConfig::default(), Some(&new))
+        );
+    }
+}
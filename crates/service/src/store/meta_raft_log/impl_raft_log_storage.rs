@@ -18,6 +18,8 @@ use std::ops::Bound;
 use std::ops::RangeBounds;
 
 use databend_meta_raft_store::raft_log_v004;
+use databend_meta_raft_store::raft_log_v004::RaftLogDurability;
+use databend_meta_raft_store::raft_log_v004::Sealed;
 use databend_meta_raft_store::raft_log_v004::codec_wrapper::Cw;
 use databend_meta_raft_store::raft_log_v004::io_desc::IODesc;
 use databend_meta_sled_store::openraft::EntryPayload;
@@ -35,7 +37,6 @@ use databend_meta_types::raft_types::TypeConfig;
 use databend_meta_types::raft_types::Vote;
 use deepsize::DeepSizeOf;
 use display_more::DisplayOptionExt;
-use itertools::Itertools;
 use log::debug;
 use log::info;
 use log::warn;
@@ -61,11 +62,14 @@ impl RaftLogReader<TypeConfig> for MetaRaftLog {
 
         let entries = log
             .read(start, end)
-            .map_ok(|(log_id, payload)| Entry {
-                log_id: log_id.0,
-                payload: payload.0,
+            .map(|res| {
+                let (log_id, payload) = res?;
+                Ok(Entry {
+                    log_id: log_id.0,
+                    payload: payload.unseal(self.key_ring.as_deref())?,
+                })
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect::<Result<Vec<_>, io::Error>>()?;
 
         io.set_done_time();
         info!("{}", io.ok_done());
@@ -209,12 +213,15 @@ impl RaftLogStorage<TypeConfig> for MetaRaftLog {
         I: IntoIterator<Item = Entry> + OptionalSend,
         I::IntoIter: OptionalSend,
     {
-        let mut entries = entries
+        let entries = entries
             .into_iter()
-            .map(|x| (Cw(x.log_id), Cw(x.payload)))
-            .peekable();
+            .map(|x| {
+                let payload = Sealed::seal(self.key_ring.as_deref(), x.payload)?;
+                Ok((Cw(x.log_id), payload))
+            })
+            .collect::<Result<Vec<_>, io::Error>>()?;
 
-        let first = entries.peek().map(|x| x.0);
+        let first = entries.first().map(|x| x.0);
 
         let io = IODesc::append(format!(
             "RaftStore(id={})::append([{}, ...])",
@@ -228,10 +235,25 @@ impl RaftLogStorage<TypeConfig> for MetaRaftLog {
 
         debug!("{}", io.ok_submit());
 
-        log.flush(Some(raft_log_v004::Callback::new_io_flushed(
-            callback,
-            io.clone(),
-        )))?;
+        match self.config.raft_log_durability {
+            RaftLogDurability::Fsync => {
+                log.flush(Some(raft_log_v004::Callback::new_io_flushed(
+                    callback,
+                    io.clone(),
+                )))?;
+            }
+            RaftLogDurability::GroupCommit => {
+                let tx = self.group_commit_tx.as_ref().ok_or_else(|| {
+                    io::Error::other(format!("{}: group commit is not started", io))
+                })?;
+                tx.send(callback)
+                    .map_err(|_e| io::Error::other(format!("{}: group commit loop quit", io)))?;
+            }
+            RaftLogDurability::Replication => {
+                log.flush(None)?;
+                callback.io_completed(Ok(()));
+            }
+        }
 
         info!("{}", io.ok_submit_flush());
 
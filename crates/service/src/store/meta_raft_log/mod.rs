@@ -12,13 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
 
 use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_raft_store::raft_log_v004;
+use databend_meta_raft_store::raft_log_v004::IODesc;
+use databend_meta_raft_store::raft_log_v004::IOFlushedBatch;
+use databend_meta_raft_store::raft_log_v004::RaftLogDurability;
 use databend_meta_raft_store::raft_log_v004::RaftLogV004;
+use databend_meta_runtime_api::SpawnApi;
+use databend_meta_types::encryption::KeyRing;
+use databend_meta_types::raft_types::IOFlushed;
 use databend_meta_types::raft_types::NodeId;
+use log::info;
+use log::warn;
+use raft_log::api::raft_log_writer::RaftLogWriter;
 use tokio::sync::RwLock;
+use tokio::sync::mpsc;
 
 mod impl_raft_log_storage;
 
@@ -26,9 +40,15 @@ mod impl_raft_log_storage;
 #[derive(Debug, Clone)]
 pub struct MetaRaftLog {
     pub(crate) id: NodeId,
-    #[allow(dead_code)]
     pub(crate) config: Arc<RaftConfig>,
     inner: Arc<RwLock<RaftLogV004>>,
+
+    /// Encrypts the appended entries and decrypts the read entries if encryption at rest is enabled.
+    pub(crate) key_ring: Option<Arc<KeyRing>>,
+
+    /// Sends the callbacks of the appended entries to the group commit loop,
+    /// if [`RaftLogDurability::GroupCommit`] is enabled.
+    group_commit_tx: Option<mpsc::UnboundedSender<IOFlushed>>,
 }
 
 impl Deref for MetaRaftLog {
@@ -40,11 +60,79 @@ impl Deref for MetaRaftLog {
 }
 
 impl MetaRaftLog {
-    pub fn new(id: NodeId, config: Arc<RaftConfig>, inner: RaftLogV004) -> Self {
+    pub fn new(
+        id: NodeId,
+        config: Arc<RaftConfig>,
+        inner: RaftLogV004,
+        key_ring: Option<Arc<KeyRing>>,
+    ) -> Self {
         Self {
             id,
             config,
             inner: Arc::new(RwLock::new(inner)),
+            key_ring,
+            group_commit_tx: None,
+        }
+    }
+
+    /// Spawn the loop that flushes appended entries in groups,
+    /// if [`RaftLogDurability::GroupCommit`] is configured.
+    ///
+    /// The loop quits when all the clones of this instance are dropped.
+    pub(crate) fn spawn_group_commit<SP: SpawnApi>(&mut self) {
+        if self.config.raft_log_durability != RaftLogDurability::GroupCommit {
+            return;
+        }
+
+        let delay = Duration::from_millis(self.config.raft_log_group_commit_delay_ms);
+        info!("spawn raft-log group commit, delay: {:?}", delay);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let fut = Self::group_commit_loop(self.id, Arc::downgrade(&self.inner), rx, delay);
+        SP::spawn(fut, Some("raft-log-group-commit".into()));
+
+        self.group_commit_tx = Some(tx);
+    }
+
+    /// Wait for `delay` after the first pending append, then flush all the pending appends at once.
+    async fn group_commit_loop(
+        id: NodeId,
+        inner: Weak<RwLock<RaftLogV004>>,
+        mut rx: mpsc::UnboundedReceiver<IOFlushed>,
+        delay: Duration,
+    ) {
+        while let Some(first) = rx.recv().await {
+            tokio::time::sleep(delay).await;
+
+            let mut batch = vec![first];
+            while let Ok(x) = rx.try_recv() {
+                batch.push(x);
+            }
+
+            let io = IODesc::append(format!(
+                "RaftStore(id={})::group_commit({} appends)",
+                id,
+                batch.len()
+            ));
+
+            let batch = IOFlushedBatch::new(batch);
+
+            let Some(inner) = inner.upgrade() else {
+                let err = io::Error::other(format!("{}: raft-log is closed", io));
+                batch.complete(&Err(err));
+                break;
+            };
+
+            let callback = raft_log_v004::Callback::new_io_flushed_batch(batch.clone(), io);
+
+            let mut log = inner.write().await;
+            if let Err(e) = log.flush(Some(callback)) {
+                warn!("RaftStore(id={}): group commit flush failed: {}", id, e);
+                // The callback is dropped: complete the appends that are not yet completed.
+                batch.complete(&Err(e));
+            }
         }
+
+        info!("RaftStore(id={}): raft-log group commit loop quit", id);
     }
 }
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::collections::BTreeMap;
+use std::fs;
 use std::io;
 use std::marker::PhantomData;
 use std::ops::Deref;
@@ -20,9 +21,12 @@ use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::Weak;
 use std::time::Duration;
+use std::time::Instant;
 
 use databend_base::counter::Counter;
 use databend_meta_raft_store::config::RaftConfig;
+use databend_meta_raft_store::immutable_compactor::CompactPolicy;
+use databend_meta_raft_store::immutable_compactor::ImmutableCompactor;
 use databend_meta_raft_store::immutable_compactor::InMemoryCompactor;
 use databend_meta_raft_store::sm_v003::SMV003;
 use databend_meta_raft_store::sm_v003::SnapshotStoreV004;
@@ -38,6 +42,7 @@ use futures::TryStreamExt;
 use log::error;
 use log::info;
 use seq_marked::InternalSeq;
+use tokio::sync::Notify;
 use tokio::sync::oneshot;
 
 use crate::metrics::SnapshotBuilding;
@@ -82,13 +87,18 @@ impl<SP: SpawnApi> MetaRaftStateMachine<SP> {
         }
     }
 
+    /// Spawn a task that freezes the writable level every `interval`
+    /// and compacts the immutable levels every time levels are frozen, if the policy requires.
     pub(crate) fn spawn_in_memory_compactor(&mut self, interval: Duration) {
         let (tx, rx) = oneshot::channel();
 
         self.in_memory_compactor_cancel = Some(Arc::new(tx));
 
-        let weak = Arc::downgrade(&self.get_inner());
-        let fu = Self::compact_loop(weak, interval, rx);
+        let sm = self.get_inner();
+        let frozen = sm.leveled_map().frozen_notify();
+        let weak = Arc::downgrade(&sm);
+        let policy = self.config.to_compact_policy();
+        let fu = Self::compact_loop(weak, interval, policy, frozen, rx);
 
         #[allow(unused_must_use)]
         SP::spawn(fu, Some("in_memory_compactor".into()));
@@ -97,46 +107,70 @@ impl<SP: SpawnApi> MetaRaftStateMachine<SP> {
     async fn compact_loop(
         weak_sm: Weak<SMV003>,
         interval: Duration,
+        policy: CompactPolicy,
+        frozen: Arc<Notify>,
         cancel: oneshot::Receiver<()>,
     ) {
         let mut c = std::pin::pin!(cancel);
 
         loop {
             let timeout_fu = tokio::time::sleep(interval);
-            futures::select! {
+            let frozen_fu = frozen.notified();
+
+            let to_freeze = futures::select! {
                 _ = c.as_mut().fuse() => {
                     info!("in_memory_compact canceled by user");
                     return;
                 }
 
                 _ = timeout_fu.fuse() => {
-                    info!("in_memory_compact is woken up, try to compact");
+                    info!("in_memory_compact is woken up, try to freeze");
+                    true
                 }
 
-            }
+                _ = frozen_fu.fuse() => {
+                    info!("in_memory_compact: levels are frozen, try to compact");
+                    false
+                }
+            };
 
             let Some(sm) = weak_sm.upgrade() else {
                 info!("in_memory_compact canceled as state machine dropped");
                 return;
             };
 
-            Self::in_memory_compact_once(sm).await
+            if to_freeze {
+                // Freezing notifies `frozen`, then the compaction is done in the next round.
+                InMemoryCompactor::new(sm, "in_memory_compactor")
+                    .await
+                    .freeze();
+            } else {
+                Self::in_memory_compact_once(sm, &policy).await
+            }
         }
     }
 
-    async fn in_memory_compact_once(sm: Arc<SMV003>) {
-        let compactor = InMemoryCompactor::new(sm, "in_memory_compactor").await;
+    async fn in_memory_compact_once(sm: Arc<SMV003>, policy: &CompactPolicy) {
+        let immutable_compactor = ImmutableCompactor::acquire(&sm, "in_memory_compactor").await;
 
-        // 1. freeze the writable
-        let immutable_compactor = compactor.freeze();
+        let start = Instant::now();
 
-        // 2. compact two adjacent levels if needed.
+        // Compact adjacent levels until the policy is satisfied.
         // TODO: add snapshot seq when a mvcc is created.
         //       currently, use the max value to eliminate all older records.
         let min_snapshot_seq = InternalSeq::new(u64::MAX);
-        immutable_compactor.compact(min_snapshot_seq);
+        let stat = immutable_compactor.compact(min_snapshot_seq, policy);
 
-        info!("in_memory_compact completed");
+        let elapsed = start.elapsed();
+        raft_metrics::storage::observe_in_memory_compaction(
+            stat.levels_merged(),
+            elapsed.as_secs_f64(),
+        );
+
+        info!(
+            "in_memory_compact completed: levels: {} -> {}, elapsed: {:?}",
+            stat.levels_before, stat.levels_after, elapsed
+        );
     }
 
     pub fn get_inner(&self) -> Arc<SMV003> {
@@ -233,6 +267,14 @@ impl<SP: SpawnApi> MetaRaftStateMachine<SP> {
             snapshot_file_size :% = db.file_size(),
             snapshot_stat :% = db.stat(); "do_build_snapshot complete");
 
+        // Verify the snapshot once it is written, thus it is not verified again when loaded.
+        // A corrupted one is removed before it is used.
+        if let Err(e) = db.verify().await {
+            error!("remove snapshot that fails to verify: {}: {}", db.path(), e);
+            fs::remove_file(db.path())?;
+            return Err(e);
+        }
+
         {
             sm_v003
                 .leveled_map()
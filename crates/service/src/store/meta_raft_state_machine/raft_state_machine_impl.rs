@@ -92,6 +92,13 @@ impl<SP: SpawnApi> RaftStateMachine<TypeConfig> for MetaRaftStateMachine<SP> {
             "decoding snapshot for installation"
         );
 
+        // Refuse a corrupted snapshot before it is moved to the final path,
+        // where it would be loaded when restarted.
+        snapshot.verify().await.inspect_err(|e| {
+            raft_metrics::storage::incr_raft_storage_fail("install_snapshot", true);
+            error!("refuse to install snapshot {:?}: {}", meta, e);
+        })?;
+
         let ss_store: SnapshotStoreV004<SP> = SnapshotStoreV004::new(self.config.as_ref().clone());
         let (storage_path, rel_path) = ss_store
             .snapshot_config()
@@ -102,6 +109,7 @@ impl<SP: SpawnApi> RaftStateMachine<TypeConfig> for MetaRaftStateMachine<SP> {
             rel_path,
             meta.snapshot_id.clone(),
             self.config.to_rotbl_config(),
+            self.config.key_ring()?,
         )?;
 
         info!("snapshot meta: {:?}", meta);
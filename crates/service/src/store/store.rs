@@ -87,6 +87,8 @@ impl<SP: SpawnApi> RaftStore<SP> {
             MetaStartupError::StoreOpenError(ae)
         }
 
+        let key_ring = config.key_ring().map_err(to_startup_err)?;
+
         let raft_log_config = Arc::new(config.to_raft_log_config());
 
         let dir = &raft_log_config.dir;
@@ -127,6 +129,13 @@ impl<SP: SpawnApi> RaftStore<SP> {
         let last = loader.load_last_snapshot().await.map_err(to_startup_err)?;
 
         let sm = if let Some((id, snapshot)) = last {
+            // Refuse to start from a snapshot corrupted on disk.
+            if config.snapshot_verify_on_load {
+                snapshot.verify().await.map_err(to_startup_err)?;
+            } else {
+                info!("skip verifying snapshot({:?}) on load", id);
+            }
+
             let sm = Self::rebuild_state_machine(&id, snapshot)
                 .await
                 .map_err(to_startup_err)?;
@@ -150,10 +159,12 @@ impl<SP: SpawnApi> RaftStore<SP> {
         let mut store = Self {
             id,
             config: config.clone(),
-            log: MetaRaftLog::new(id, config.clone(), log),
+            log: MetaRaftLog::new(id, config.clone(), log, key_ring),
             state_machine: MetaRaftStateMachine::new(id, config, Arc::new(sm)),
         };
 
+        store.log.spawn_group_commit::<SP>();
+
         {
             let interval_ms = store.config.compact_immutables_ms.unwrap_or(1000);
 
@@ -290,7 +301,7 @@ impl<SP: SpawnApi> RaftStore<SP> {
             for res in dump.iter() {
                 let (log_id, payload) = res?;
                 let log_id = log_id.unpack();
-                let payload = payload.unpack();
+                let payload = payload.unseal(self.log.key_ring.as_deref())?;
 
                 let log_entry = Entry { log_id, payload };
 
@@ -0,0 +1,98 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test gRPC compression of the client API and the raft service.
+
+use std::time::Duration;
+
+use databend_meta::message::ForwardRequest;
+use databend_meta::message::ForwardRequestBody;
+use databend_meta_client::DEFAULT_GRPC_MESSAGE_SIZE;
+use databend_meta_client::MetaGrpcClient;
+use databend_meta_runtime_api::TokioRuntime;
+use databend_meta_types::GrpcCompression;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::protobuf::KvListRequest;
+use futures::TryStreamExt;
+use test_harness::test;
+use tonic::codec::CompressionEncoding;
+
+use crate::testing::meta_service_test_harness;
+use crate::tests::service::MetaSrvTestContext;
+use crate::tests::start_metasrv_with_context;
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_grpc_compression() -> anyhow::Result<()> {
+    // - Start a metasrv that compresses responses of both services.
+    // - A client compressing requests with another algorithm works.
+    // - A client without compression works.
+
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(0);
+    tc.config.grpc.compression = GrpcCompression::Zstd;
+    tc.config.raft_config.raft_compression = GrpcCompression::Gzip;
+
+    start_metasrv_with_context::<TokioRuntime>(&mut tc).await?;
+
+    let addr = tc.config.grpc.api_address().unwrap();
+
+    let value = vec![b'a'; 64 * 1024];
+
+    for compression in [GrpcCompression::Gzip, GrpcCompression::None] {
+        let client = MetaGrpcClient::<TokioRuntime>::try_create_with_features(
+            vec![addr.clone()],
+            "root",
+            "xxx",
+            None,
+            Some(Duration::from_secs(10)),
+            None,
+            DEFAULT_GRPC_MESSAGE_SIZE,
+            compression,
+        )?;
+
+        let key = format!("c/{}", compression);
+        client.upsert_kv(UpsertKV::update(&key, &value)).await?;
+
+        let mut ec = client.make_established_client().await?;
+        let items = ec
+            .kv_list(KvListRequest {
+                prefix: key.clone(),
+                limit: None,
+            })
+            .await?
+            .into_inner()
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(1, items.len(), "{}", compression);
+        assert_eq!(key, items[0].key);
+        assert_eq!(value, items[0].value.as_ref().unwrap().data);
+    }
+
+    // Raft service accepts compressed requests and replies compressed.
+    let mut client = tc
+        .raft_client()
+        .await?
+        .send_compressed(CompressionEncoding::Zstd)
+        .accept_compressed(CompressionEncoding::Gzip);
+
+    client
+        .forward(ForwardRequest {
+            forward_to_leader: 0,
+            body: ForwardRequestBody::Ping,
+        })
+        .await?;
+
+    Ok(())
+}
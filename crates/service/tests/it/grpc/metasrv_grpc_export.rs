@@ -84,7 +84,7 @@ async fn test_export() -> anyhow::Result<()> {
         r#"["raft_log",{"Purged":null}]"#,
         r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":0,"node_id":0},"index":0},"payload":{"Membership":{"configs":[[0]],"nodes":{"0":{}}}}}}]"#,
         r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":1},"payload":"Blank"}}]"#,
-        r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":2},"payload":{"Normal":{"time_ms":1111111111111,"cmd":{"AddNode":{"node_id":0,"node":{"name":"0","endpoint":{"addr":"localhost","port":29000},"grpc_api_advertise_address":"127.0.0.1:29000"},"overriding":false}}}}}}]"#,
+        r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":2},"payload":{"Normal":{"time_ms":1111111111111,"cmd":{"AddNode":{"node_id":0,"node":{"name":"0","endpoint":{"addr":"localhost","port":29000},"grpc_api_advertise_address":"127.0.0.1:29000","binary_version":"1.0.0"},"overriding":false}}}}}}]"#,
         r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":3},"payload":{"Membership":{"configs":[[0]],"nodes":{"0":{}}}}}}]"#,
         r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":4},"payload":{"Normal":{"time_ms":1111111111111,"cmd":{"Transaction":{"condition":[{"key":"foo","expected":2,"target":{"Seq":0}}],"if_then":[{"request":{"Put":{"key":"foo","value":[102,111,111],"prev_value":true,"expire_at":null}}}],"else_then":[{"request":{"Get":{"key":"foo"}}}]}}}}}}]"#,
         r#"["raft_log",{"LogEntry":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":5},"payload":{"Normal":{"time_ms":1111111111111,"cmd":{"Transaction":{"condition":[{"key":"bar","expected":2,"target":{"Seq":0}}],"if_then":[{"request":{"Put":{"key":"bar","value":[98,97,114],"prev_value":true,"expire_at":null}}}],"else_then":[{"request":{"Get":{"key":"bar"}}}]}}}}}}]"#,
@@ -92,7 +92,7 @@ async fn test_export() -> anyhow::Result<()> {
         r#"["state_machine/0",{"Sequences":{"key":"generic-kv","value":3}}]"#,
         r#"["state_machine/0",{"StateMachineMeta":{"key":"LastApplied","value":{"LogId":{"leader_id":{"term":1,"node_id":0},"index":6}}}}]"#,
         r#"["state_machine/0",{"StateMachineMeta":{"key":"LastMembership","value":{"Membership":{"log_id":{"leader_id":{"term":1,"node_id":0},"index":3},"membership":{"configs":[[0]],"nodes":{"0":{}}}}}}}]"#,
-        r#"["state_machine/0",{"Nodes":{"key":0,"value":{"name":"0","endpoint":{"addr":"localhost","port":29000},"grpc_api_advertise_address":"127.0.0.1:29000"}}}]"#,
+        r#"["state_machine/0",{"Nodes":{"key":0,"value":{"name":"0","endpoint":{"addr":"localhost","port":29000},"grpc_api_advertise_address":"127.0.0.1:29000","binary_version":"1.0.0"}}}]"#,
         r#"["state_machine/0",{"GenericKV":{"key":"bar","value":{"seq":2,"meta":{"proposed_at_ms":1111111111111},"data":[98,97,114]}}}]"#,
         r#"["state_machine/0",{"GenericKV":{"key":"foo","value":{"seq":1,"meta":{"proposed_at_ms":1111111111111},"data":[102,111,111]}}}]"#,
         r#"["state_machine/0",{"GenericKV":{"key":"wow","value":{"seq":3,"meta":{"proposed_at_ms":1111111111111},"data":[119,111,119]}}}]"#,
@@ -123,6 +123,13 @@ async fn test_export() -> anyhow::Result<()> {
                 .replace_all(&x, "localhost:29000")
                 .to_string()
         })
+        .map(|x| {
+            // Normalize the version of the binary
+            Regex::new(r#""binary_version":"[^"]*""#)
+                .unwrap()
+                .replace_all(&x, r#""binary_version":"1.0.0""#)
+                .to_string()
+        })
         .map(|x| {
             // Normalize timestamps
             Regex::new(r"\d{13}")
@@ -22,6 +22,7 @@ use databend_meta_client::MetaChannelManager;
 use databend_meta_client::handshake;
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_runtime_api::TokioRuntime;
+use databend_meta_version::Feature;
 use databend_meta_version::Version;
 use databend_meta_version::version;
 use log::debug;
@@ -106,5 +107,16 @@ async fn test_metasrv_handshake() -> anyhow::Result<()> {
         assert!(res.is_ok());
     }
 
+    info!("--- server advertises its features");
+    {
+        let res = handshake(&mut client, version(), &Version::min(), "root", "xxx").await;
+
+        let (_token, server_version, features) = res?;
+        assert_eq!(server_version, version().to_digit());
+        assert!(features.contains(&Feature::KvList));
+        assert!(features.contains(&Feature::KvGetMany));
+        assert!(!features.contains(&Feature::KvApiGetKv));
+    }
+
     Ok(())
 }
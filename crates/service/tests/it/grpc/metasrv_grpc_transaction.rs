@@ -15,9 +15,20 @@
 //! Test special cases of grpc API: transaction().
 
 use std::collections::BTreeSet;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
+use databend_meta_client::OptimisticTxnError;
+use databend_meta_client::OptimisticTxnOptions;
+use databend_meta_client::RequestOptions;
+use databend_meta_client::RetryPolicy;
 use databend_meta_runtime_api::TokioRuntime;
+use databend_meta_types::MetaError;
+use databend_meta_types::TxnOp;
 use databend_meta_types::TxnRequest;
+use databend_meta_types::UpsertKV;
+use log::info;
 use test_harness::test;
 
 use crate::testing::meta_service_test_harness;
@@ -67,3 +78,104 @@ async fn test_transaction_follower_responds_leader_endpoint() -> anyhow::Result<
 
     Ok(())
 }
+
+/// An optimistic transaction is retried if a key it read is changed before commit.
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_optimistic_txn_retry_on_conflict() -> anyhow::Result<()> {
+    let (tc, _) = crate::tests::start_metasrv::<TokioRuntime>().await?;
+    let client = tc.grpc_client().await?;
+
+    client.upsert_kv(UpsertKV::update("counter", b"1")).await?;
+
+    let parse = |data: &[u8]| -> u64 { std::str::from_utf8(data).unwrap().parse().unwrap() };
+
+    // The first attempt conflicts with another writer.
+    {
+        let attempts = AtomicU32::new(0);
+
+        let committed = client
+            .optimistic_txn(|txn| {
+                let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+                let other = client.clone();
+                async move {
+                    let n = parse(&txn.get("counter").await?.unwrap().data);
+                    if attempt == 0 {
+                        other.upsert_kv(UpsertKV::update("counter", b"10")).await?;
+                    }
+                    txn.put("counter", (n + 1).to_string().into_bytes());
+                    Ok::<_, MetaError>(n + 1)
+                }
+            })
+            .await?;
+
+        assert_eq!(11, committed.value);
+        assert_eq!(1, committed.retries);
+
+        let got = client.get_kv("counter").await?.unwrap();
+        assert_eq!(11, parse(&got.data));
+    }
+
+    // Every attempt conflicts.
+    {
+        let opts = OptimisticTxnOptions::default().with_max_retries(2);
+
+        let res = client
+            .optimistic_txn_with(opts, |txn| {
+                let other = client.clone();
+                async move {
+                    txn.get("counter").await?;
+                    other.upsert_kv(UpsertKV::update("counter", b"0")).await?;
+                    Ok::<_, MetaError>(())
+                }
+            })
+            .await;
+
+        match res {
+            Err(OptimisticTxnError::Conflict { retries, keys }) => {
+                assert_eq!(2, retries);
+                assert_eq!(vec!["counter".to_string()], keys);
+            }
+            other => panic!("expect conflict, got: {:?}", other),
+        }
+    }
+
+    Ok(())
+}
+
+/// A transaction is sent with the retry policy and timeout overridden per call.
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_transaction_with_options() -> anyhow::Result<()> {
+    let (tc, _addr) = crate::tests::start_metasrv::<TokioRuntime>().await?;
+    let client = tc.grpc_client().await?;
+
+    let txn = || TxnRequest::unconditional(vec![TxnOp::put("k1", b"v1".to_vec())]);
+
+    info!("--- fail fast: no retry, in time");
+    {
+        let opts = RequestOptions::default()
+            .with_retry_policy(RetryPolicy::no_retry())
+            .with_timeout(Duration::from_secs(5));
+
+        let reply = client.transaction_with_options(txn(), opts).await?;
+        assert!(reply.success);
+    }
+
+    info!("--- the deadline is reached before the first attempt");
+    {
+        let opts = RequestOptions::default().with_timeout(Duration::ZERO);
+
+        let err = client
+            .transaction_with_options(txn(), opts)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("deadline exceeded"),
+            "got: {}",
+            err
+        );
+    }
+
+    Ok(())
+}
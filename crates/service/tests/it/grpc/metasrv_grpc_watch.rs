@@ -15,6 +15,7 @@
 #![allow(clippy::collapsible_if, clippy::useless_vec)]
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Duration;
@@ -22,10 +23,14 @@ use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use databend_base::string_util::prefix_to_range;
+use databend_meta::configs::WatchConfig;
 use databend_meta::meta_service::MetaNode;
 use databend_meta_client::ClientHandle;
 use databend_meta_client::DEFAULT_GRPC_MESSAGE_SIZE;
 use databend_meta_client::MetaGrpcClient;
+use databend_meta_client::PrefixCache;
+use databend_meta_client::WaitForSeqError;
+use databend_meta_client::WatchEvent;
 use databend_meta_runtime_api::SpawnApi;
 use databend_meta_runtime_api::TokioRuntime;
 use databend_meta_types::ConditionResult;
@@ -44,11 +49,16 @@ use databend_meta_types::protobuf::WatchRequest;
 use databend_meta_types::protobuf::watch_request::FilterType;
 use databend_meta_types::txn_condition;
 use databend_meta_types::txn_op;
+use futures::StreamExt;
+use futures::stream::BoxStream;
 use log::info;
 use test_harness::test;
 use tokio::time::sleep;
+use tokio::time::timeout;
 
 use crate::testing::meta_service_test_harness;
+use crate::tests::service::MetaSrvTestContext;
+use crate::tests::start_metasrv_with_context;
 
 async fn test_watch_main(
     addr: String,
@@ -659,6 +669,188 @@ fn add_event(key: &str, res_seq: u64, res_val: &str, meta: Option<KvMeta>) -> Ev
     }
 }
 
+/// A `PrefixCache` loads the existing key-values under a dir and applies later changes.
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_prefix_cache() -> anyhow::Result<()> {
+    let (_tc, addr) = crate::tests::start_metasrv::<TokioRuntime>().await?;
+
+    let client = make_client(&addr)?;
+
+    client.upsert_kv(UpsertKV::update("c/a", b"1")).await?;
+    client.upsert_kv(UpsertKV::update("c/b", b"2")).await?;
+    client.upsert_kv(UpsertKV::update("d/a", b"3")).await?;
+
+    let cache = PrefixCache::new(client.clone(), "c");
+    assert_eq!("c/", cache.dir());
+
+    timeout(Duration::from_secs(5), cache.wait_initialized()).await??;
+
+    let keys = |kvs: Vec<(String, _)>| kvs.into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+
+    assert_eq!(vec!["c/a", "c/b"], keys(cache.list("c/")));
+    assert_eq!(vec!["c/b"], keys(cache.list("c/b")));
+    assert_eq!(b"1".to_vec(), cache.get("c/a").unwrap().data);
+    assert!(cache.get("d/a").is_none());
+
+    info!("--- read the cache after a write");
+    {
+        let reply = client.upsert_kv(UpsertKV::update("c/c", b"4")).await?;
+        let seq = reply.result.unwrap().seq;
+
+        cache.wait_for_seq(seq, Duration::from_secs(5)).await?;
+        assert_eq!(Some(seq), cache.last_seq());
+        assert_eq!(b"4".to_vec(), cache.get("c/c").unwrap().data);
+    }
+
+    info!("--- a delete is applied before a later write");
+    {
+        client.upsert_kv(UpsertKV::delete("c/a")).await?;
+        let reply = client.upsert_kv(UpsertKV::update("c/d", b"5")).await?;
+        let seq = reply.result.unwrap().seq;
+
+        cache.wait_for_seq(seq, Duration::from_secs(5)).await?;
+        assert_eq!(vec!["c/b", "c/c", "c/d"], keys(cache.list("c/")));
+    }
+
+    info!("--- a write outside of the dir is never seen");
+    {
+        let reply = client.upsert_kv(UpsertKV::update("d/b", b"6")).await?;
+        let seq = reply.result.unwrap().seq;
+
+        let res = cache.wait_for_seq(seq, Duration::from_millis(200)).await;
+        assert!(matches!(res, Err(WaitForSeqError::Timeout { .. })));
+    }
+
+    Ok(())
+}
+
+/// A `PrefixCache` initializes a dir with more keys than the watch buffer of the server holds.
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_prefix_cache_more_keys_than_watch_buffer() -> anyhow::Result<()> {
+    let (_tc, addr) = crate::tests::start_metasrv::<TokioRuntime>().await?;
+
+    let client = make_client(&addr)?;
+
+    let n = WatchConfig::default().buffer_size * 4;
+    for i in 0..n {
+        let key = format!("c/{:04}", i);
+        client.upsert_kv(UpsertKV::update(key, b"v")).await?;
+    }
+
+    let cache = PrefixCache::new(client.clone(), "c");
+
+    timeout(Duration::from_secs(10), cache.wait_initialized()).await??;
+    assert_eq!(n, cache.list("c/").len());
+
+    let reply = client.upsert_kv(UpsertKV::update("c/x", b"w")).await?;
+    let seq = reply.result.unwrap().seq;
+
+    cache.wait_for_seq(seq, Duration::from_secs(5)).await?;
+    assert_eq!(n + 1, cache.list("c/").len());
+
+    Ok(())
+}
+
+/// A resilient watch re-watches after the server restarts, and emits a reset event before
+/// the key-values are flushed again.
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_watch_resilient_server_restart() -> anyhow::Result<()> {
+    async fn next_event(strm: &mut BoxStream<'static, WatchEvent>) -> anyhow::Result<WatchEvent> {
+        let ev = timeout(Duration::from_secs(20), strm.next()).await?;
+        ev.ok_or_else(|| anyhow::anyhow!("resilient watch stream ended"))
+    }
+
+    fn key_of(ev: WatchEvent) -> Option<String> {
+        match ev {
+            WatchEvent::Response(resp) => resp.unpack().map(|(k, _, _)| k),
+            WatchEvent::Reset { .. } => None,
+            WatchEvent::Failed(status) => panic!("unexpected watch failure: {}", status),
+        }
+    }
+
+    let (mut tc, addr) = crate::tests::start_metasrv::<TokioRuntime>().await?;
+    let client = make_client(&addr)?;
+
+    client.upsert_kv(UpsertKV::update("w/a", b"1")).await?;
+
+    let mut strm = client.watch_resilient(WatchRequest::new_dir("w").with_initial_flush(true));
+
+    assert_eq!(
+        Some("w/a".to_string()),
+        key_of(next_event(&mut strm).await?)
+    );
+    assert!(matches!(
+        next_event(&mut strm).await?,
+        WatchEvent::Response(resp) if resp.is_initialization_complete_flag()
+    ));
+
+    info!("--- restart the server");
+    {
+        let mut srv = tc.grpc_srv.take().unwrap();
+        srv.do_stop(None).await;
+
+        sleep(Duration::from_secs(1)).await;
+
+        start_metasrv_with_context(&mut tc).await?;
+
+        let meta_handle = tc.grpc_srv.as_ref().unwrap().get_meta_handle();
+        meta_handle
+            .handle_raft_metrics_wait(Some(Duration::from_secs(10)))
+            .await?
+            .metrics(|m| m.current_leader.is_some(), "a leader is observed")
+            .await?;
+    }
+
+    client.upsert_kv(UpsertKV::update("w/b", b"2")).await?;
+
+    let ev = next_event(&mut strm).await?;
+    assert!(matches!(ev, WatchEvent::Reset { .. }), "got: {}", ev);
+
+    // Both keys are received, by initial flush or by a change event.
+    let mut keys = BTreeSet::new();
+    while !keys.contains("w/b") {
+        if let Some(k) = key_of(next_event(&mut strm).await?) {
+            keys.insert(k);
+        }
+    }
+    assert!(keys.contains("w/a"));
+
+    Ok(())
+}
+
+/// A resilient watch does not re-watch after a non-retryable error, but emits it and ends.
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_watch_resilient_non_retryable_error() -> anyhow::Result<()> {
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(0);
+    tc.config.grpc.watch.max_watchers = Some(1);
+
+    start_metasrv_with_context(&mut tc).await?;
+
+    let addr = tc.config.grpc.api_address().unwrap();
+    let client = make_client(&addr)?;
+
+    let _occupied = client.watch(WatchRequest::new_dir("w")).await?;
+
+    let mut strm = client.watch_resilient(WatchRequest::new_dir("w"));
+
+    let ev = timeout(Duration::from_secs(10), strm.next()).await?;
+    match ev {
+        Some(WatchEvent::Failed(status)) => {
+            assert_eq!(tonic::Code::ResourceExhausted, status.code(), "{}", status);
+        }
+        other => panic!("expect Failed, got: {:?}", other),
+    }
+
+    let ev = timeout(Duration::from_secs(10), strm.next()).await?;
+    assert!(ev.is_none());
+
+    Ok(())
+}
+
 fn make_client(addr: impl ToString) -> anyhow::Result<Arc<ClientHandle<TokioRuntime>>> {
     let client = MetaGrpcClient::<TokioRuntime>::try_create(
         vec![addr.to_string()],
@@ -0,0 +1,88 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test size limits of write requests.
+
+use databend_meta::configs::WriteLimitConfig;
+use databend_meta_runtime_api::TokioRuntime;
+use databend_meta_types::TxnOp;
+use databend_meta_types::TxnRequest;
+use databend_meta_types::UpsertKV;
+use test_harness::test;
+use tonic::Code;
+
+use crate::testing::meta_service_test_harness;
+use crate::tests::service::MetaSrvTestContext;
+use crate::tests::service::make_grpc_client;
+use crate::tests::start_metasrv_with_context;
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_write_limit() -> anyhow::Result<()> {
+    // - Start a metasrv with write limits.
+    // - Requests within the limits succeed.
+    // - Oversized requests are rejected with InvalidArgument and nothing is written.
+
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(0);
+    tc.config.raft_config.write_limit = WriteLimitConfig {
+        max_key_len: Some(16),
+        max_value_size: Some(1024),
+        max_txn_ops: Some(4),
+        max_txn_bytes: Some(4096),
+    };
+
+    start_metasrv_with_context::<TokioRuntime>(&mut tc).await?;
+
+    let addr = tc.config.grpc.api_address().unwrap();
+    let client = make_grpc_client::<TokioRuntime>(vec![addr])?;
+
+    client.upsert_kv(UpsertKV::update("a", b"1")).await?;
+
+    let cases = vec![
+        (
+            TxnRequest::unconditional(vec![TxnOp::put("k".repeat(17), b"1".to_vec())]),
+            "key length 17 exceeds the limit 16",
+        ),
+        (
+            TxnRequest::unconditional(vec![TxnOp::put("k", vec![b'x'; 1025])]),
+            "value size 1025 bytes exceeds the limit 1024 bytes",
+        ),
+        (
+            TxnRequest::unconditional((0..5).map(|i| TxnOp::get(format!("k{}", i))).collect()),
+            "transaction has 5 operations, exceeds the limit 4",
+        ),
+        (
+            TxnRequest::unconditional(vec![
+                TxnOp::put("k1", vec![b'x'; 1024]),
+                TxnOp::put("k2", vec![b'x'; 1024]),
+                TxnOp::put("k3", vec![b'x'; 1024]),
+                TxnOp::put("k4", vec![b'x'; 1024]),
+            ]),
+            "exceeds the limit 4096 bytes",
+        ),
+    ];
+
+    for (txn, want) in cases {
+        let mut ec = client.make_established_client().await?;
+        let st = ec.transaction(txn).await.unwrap_err();
+
+        assert_eq!(Code::InvalidArgument, st.code(), "{}", want);
+        assert!(st.message().contains(want), "{}: {}", want, st.message());
+    }
+
+    let got = client.get_kv("k").await?;
+    assert!(got.is_none(), "rejected writes are not applied");
+
+    Ok(())
+}
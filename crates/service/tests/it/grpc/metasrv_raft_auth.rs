@@ -0,0 +1,54 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use databend_meta::message::ForwardRequest;
+use databend_meta::message::ForwardRequestBody;
+use databend_meta::raft_auth::RaftAuth;
+use databend_meta_raft_store::config::Secret;
+use databend_meta_runtime_api::TokioRuntime;
+use test_harness::test;
+
+use crate::testing::meta_service_test_harness;
+use crate::tests::service::MetaSrvTestContext;
+use crate::tests::start_metasrv_with_context;
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_raft_service_auth() -> anyhow::Result<()> {
+    // - Start a metasrv with raft auth secret.
+    // - Requests with the same secret are accepted.
+    // - Requests without secret or with a different secret are rejected.
+
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(0);
+    tc.config.raft_config.raft_auth_secret = Some(Secret::new("foo"));
+
+    start_metasrv_with_context::<TokioRuntime>(&mut tc).await?;
+
+    tc.assert_raft_server_connection().await?;
+
+    let addr = tc.config.raft_config.raft_api_addr::<TokioRuntime>().await?;
+
+    let ping = || ForwardRequest {
+        forward_to_leader: 0,
+        body: ForwardRequestBody::Ping,
+    };
+
+    for auth in [RaftAuth::new(None), RaftAuth::new(Some("bar"))] {
+        let mut client = auth.connect(&addr).await?;
+        let err = client.forward(ping()).await.unwrap_err();
+        assert_eq!(tonic::Code::Unauthenticated, err.code(), "{:?}", auth);
+    }
+
+    Ok(())
+}
@@ -14,6 +14,7 @@
 
 pub mod metasrv_connection_error;
 pub mod metasrv_grpc_api;
+pub mod metasrv_grpc_compression;
 mod metasrv_grpc_export;
 pub mod metasrv_grpc_get_client_info;
 pub mod metasrv_grpc_handshake;
@@ -25,4 +26,6 @@ pub mod metasrv_grpc_member_list;
 pub mod metasrv_grpc_tls;
 pub mod metasrv_grpc_transaction;
 pub mod metasrv_grpc_watch;
+pub mod metasrv_grpc_write_limit;
+pub mod metasrv_raft_auth;
 pub mod t53_metasrv_grpc_snapshot_keys_layout;
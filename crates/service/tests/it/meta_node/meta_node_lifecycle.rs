@@ -22,6 +22,7 @@ use databend_meta::message::JoinRequest;
 use databend_meta::message::LeaveRequest;
 use databend_meta::meta_service::MetaNode;
 use databend_meta_kvapi::kvapi::KvApiExt;
+use databend_meta_raft_store::StateMachineFeature;
 use databend_meta_runtime_api::TokioRuntime;
 use databend_meta_sled_store::openraft::LogIdOptionExt;
 use databend_meta_sled_store::openraft::RaftLogReader;
@@ -328,6 +329,235 @@ async fn test_meta_node_join_as_learner() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_meta_node_join_incompatible_version() -> anyhow::Result<()> {
+    // - Bring up a cluster
+    // - A node of a too old version is rejected by the leader.
+
+    let (_nlog, tcs) = start_meta_node_cluster(btreeset![0], btreeset![]).await?;
+    let all = test_context_nodes(&tcs);
+    let leader = all[0].clone();
+
+    let node_id = 1;
+    let tc1 = MetaSrvTestContext::<TokioRuntime>::new(node_id);
+    let endpoint = tc1
+        .config
+        .raft_config
+        .raft_api_addr::<TokioRuntime>()
+        .await?;
+
+    info!("--- join node-1 of version 1.2.479");
+    {
+        let admin_req = ForwardRequest {
+            forward_to_leader: 0,
+            body: ForwardRequestBody::Join(
+                JoinRequest::new(node_id, endpoint.clone(), tc1.config.grpc.advertise_address())
+                    .with_binary_version("1.2.479"),
+            ),
+        };
+
+        let err = leader
+            .handle_forwardable_request(admin_req)
+            .await
+            .unwrap_err();
+
+        let msg = err.to_string();
+        assert!(
+            msg.starts_with("node 1 of version 1.2.479 is incompatible: "),
+            "{}",
+            msg
+        );
+        assert!(msg.contains("install_snapshot_v1"), "{}", msg);
+    }
+
+    info!("--- node-1 is not added");
+    {
+        assert!(leader.raft_store.get_node(&node_id).await.is_none());
+    }
+
+    info!("--- join node-1 of an invalid version");
+    {
+        let admin_req = ForwardRequest {
+            forward_to_leader: 0,
+            body: ForwardRequestBody::Join(
+                JoinRequest::new(node_id, endpoint, tc1.config.grpc.advertise_address())
+                    .with_binary_version("foo"),
+            ),
+        };
+
+        let err = leader
+            .handle_forwardable_request(admin_req)
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("node 1 of version foo is incompatible: invalid version: "),
+            "{}",
+            err
+        );
+    }
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_meta_node_join_requires_enabled_features() -> anyhow::Result<()> {
+    // - Bring up a cluster and enable prefix_quota.
+    // - A node of a version before prefix_quota, or of unknown version, is rejected.
+
+    let (_nlog, tcs) = start_meta_node_cluster(btreeset![0], btreeset![]).await?;
+    let all = test_context_nodes(&tcs);
+    let leader = all[0].clone();
+
+    leader
+        .set_feature(StateMachineFeature::PrefixQuota, true)
+        .await?;
+
+    let node_id = 1;
+    let tc1 = MetaSrvTestContext::<TokioRuntime>::new(node_id);
+    let endpoint = tc1
+        .config
+        .raft_config
+        .raft_api_addr::<TokioRuntime>()
+        .await?;
+
+    let join = |binary_version: Option<&'static str>| {
+        let mut req = JoinRequest::new(
+            node_id,
+            endpoint.clone(),
+            tc1.config.grpc.advertise_address(),
+        );
+        if let Some(v) = binary_version {
+            req = req.with_binary_version(v);
+        }
+        ForwardRequest {
+            forward_to_leader: 0,
+            body: ForwardRequestBody::Join(req),
+        }
+    };
+
+    info!("--- join node-1 of version 260205.3.0");
+    {
+        let err = leader
+            .handle_forwardable_request(join(Some("260205.3.0")))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            "node 1 of version 260205.3.0 is incompatible: state machine feature prefix_quota is enabled and requires version >= 260205.4.0",
+            err.to_string()
+        );
+    }
+
+    info!("--- join node-1 of unknown version");
+    {
+        let err = leader
+            .handle_forwardable_request(join(None))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            "node 1 of version unknown is incompatible: state machine feature prefix_quota is enabled and requires version >= 260205.4.0",
+            err.to_string()
+        );
+    }
+
+    info!("--- node-1 is not added");
+    {
+        assert!(leader.raft_store.get_node(&node_id).await.is_none());
+    }
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_meta_node_set_feature_requires_node_version() -> anyhow::Result<()> {
+    // - Bring up a cluster with a learner that has not reported its version.
+    // - A feature can not be enabled until every node supports it.
+    // - Finalizing upgrade enables all features once every node is upgraded.
+
+    let (_nlog, tcs) = start_meta_node_cluster(btreeset![0], btreeset![1]).await?;
+    let all = test_context_nodes(&tcs);
+    let leader = all[0].clone();
+
+    let set_node_version = |version: &'static str| {
+        let leader = leader.clone();
+        async move {
+            let node = leader.get_node(&1).await.unwrap();
+            let cmd = Cmd::AddNode {
+                node_id: 1,
+                node: node.with_binary_version(Some(version)),
+                overriding: true,
+            };
+            leader.write(LogEntry::new(cmd)).await
+        }
+    };
+
+    info!("--- node-1 of unknown version rejects prefix_quota");
+    {
+        let err = leader
+            .set_feature(StateMachineFeature::PrefixQuota, true)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            "node 1 of version unknown is incompatible: state machine feature prefix_quota requires version >= 260205.4.0",
+            err.to_string()
+        );
+    }
+
+    info!("--- a feature without version requirement can be enabled");
+    {
+        leader.set_feature(StateMachineFeature::Dummy, true).await?;
+    }
+
+    info!("--- node-1 of an old version rejects prefix_quota");
+    {
+        set_node_version("1.2.479").await?;
+
+        let err = leader
+            .set_feature(StateMachineFeature::PrefixQuota, true)
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("node 1 of version 1.2.479 is incompatible: "),
+            "{}",
+            err
+        );
+
+        let enabled = leader.finalize_upgrade().await?;
+        assert_eq!(vec![StateMachineFeature::DummyFeature2], enabled);
+    }
+
+    info!("--- finalize upgrade after node-1 is upgraded");
+    {
+        set_node_version(databend_meta_version::version_str()).await?;
+
+        let enabled = leader.finalize_upgrade().await?;
+        assert_eq!(vec![StateMachineFeature::PrefixQuota], enabled);
+
+        let sys_data = leader.raft_store.get_sm_v003().sys_data();
+        for feature in StateMachineFeature::all() {
+            assert!(
+                sys_data.feature_enabled(&feature.to_string()),
+                "{}",
+                feature
+            );
+        }
+
+        let enabled = leader.finalize_upgrade().await?;
+        assert!(enabled.is_empty());
+    }
+
+    Ok(())
+}
+
 #[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
 #[fastrace::trace]
 async fn test_meta_node_join_rejoin() -> anyhow::Result<()> {
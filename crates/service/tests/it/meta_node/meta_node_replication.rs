@@ -299,6 +299,7 @@ async fn test_raft_service_install_snapshot_v004() -> anyhow::Result<()> {
                 snapshot_id: snapshot_id.to_string(),
                 sys_data: sys_data_str,
                 vote: Some(pb::Vote::from(Vote::new_committed(10, 2))),
+                checksum: None,
             }),
         },
     ];
@@ -358,5 +359,38 @@ async fn test_raft_service_install_snapshot_v004() -> anyhow::Result<()> {
 
     assert_eq!(err.code(), tonic::Code::InvalidArgument);
 
+    // Checksum mismatch
+
+    let strm_data = [
+        //
+        pb::InstallEntryV004 {
+            version: 1,
+            key_values: vec![
+                //
+                pb::StreamItem::new(
+                    "kv--/a".to_string(),
+                    Some(SeqV::new(1, b"bar".to_vec()).into()),
+                ),
+            ],
+            commit: Some(pb::Commit {
+                snapshot_id: MetaSnapshotId::new(Some(log_id(10, 2, 5)), 2).to_string(),
+                sys_data: serde_json::to_string(&sys_data)?,
+                vote: Some(pb::Vote::from(Vote::new_committed(10, 2))),
+                checksum: Some(0),
+            }),
+        },
+    ];
+
+    let err = client0
+        .install_snapshot_v004(stream::iter(strm_data))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.code(), tonic::Code::DataLoss);
+
+    // The snapshot is not installed.
+    let m = meta_node.raft.metrics().borrow_watched().clone();
+    assert_eq!(Some(last_log_id), m.snapshot);
+
     Ok(())
 }
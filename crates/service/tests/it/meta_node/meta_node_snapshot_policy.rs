@@ -0,0 +1,101 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use databend_meta::meta_service::MetaNode;
+use databend_meta_runtime_api::TokioRuntime;
+use databend_meta_sled_store::openraft::LogIdOptionExt;
+use databend_meta_sled_store::openraft::ServerState;
+use databend_meta_sled_store::openraft::async_runtime::WatchReceiver;
+use databend_meta_types::Cmd;
+use databend_meta_types::LogEntry;
+use databend_meta_types::UpsertKV;
+use log::info;
+use test_harness::test;
+
+use crate::testing::meta_service_test_harness;
+use crate::tests::meta_node::timeout;
+use crate::tests::service::MetaSrvTestContext;
+
+/// Boot a single node that never builds a snapshot by log count.
+async fn boot(
+    tc: &mut MetaSrvTestContext<TokioRuntime>,
+) -> anyhow::Result<Arc<MetaNode<TokioRuntime>>> {
+    tc.config.raft_config.snapshot_logs_since_last = 1_000_000;
+
+    let mn = MetaNode::<TokioRuntime>::boot(&tc.config).await?;
+
+    mn.raft
+        .wait(timeout())
+        .state(ServerState::Leader, "leader started")
+        .await?;
+
+    Ok(mn)
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_snapshot_policy_interval() -> anyhow::Result<()> {
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(0);
+    tc.config.raft_config.snapshot_interval_ms = Some(500);
+
+    let mn = boot(&mut tc).await?;
+
+    mn.write(LogEntry::new(Cmd::UpsertKV(UpsertKV::update("a", b"v"))))
+        .await?;
+
+    let applied = mn.raft.metrics().borrow_watched().last_applied;
+
+    info!("--- snapshot is built after the interval, without reaching the log count");
+
+    mn.raft
+        .wait(timeout())
+        .metrics(
+            |x| x.snapshot.next_index() >= applied.next_index(),
+            "snapshot is built by interval",
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_snapshot_policy_log_bytes() -> anyhow::Result<()> {
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(0);
+    tc.config.raft_config.snapshot_log_bytes_since_last = Some(64 * 1024);
+
+    let mn = boot(&mut tc).await?;
+
+    mn.write(LogEntry::new(Cmd::UpsertKV(UpsertKV::update(
+        "a",
+        &vec![b'x'; 128 * 1024],
+    ))))
+    .await?;
+
+    let applied = mn.raft.metrics().borrow_watched().last_applied;
+
+    info!("--- snapshot is built after a large entry is written");
+
+    mn.raft
+        .wait(timeout())
+        .metrics(
+            |x| x.snapshot.next_index() >= applied.next_index(),
+            "snapshot is built by log bytes",
+        )
+        .await?;
+
+    Ok(())
+}
@@ -16,4 +16,5 @@ pub(crate) mod meta_node_kv_api_expire;
 pub(crate) mod meta_node_lifecycle;
 pub(crate) mod meta_node_replication;
 pub(crate) mod meta_node_request_forwarding;
+pub(crate) mod meta_node_snapshot_policy;
 pub(crate) mod t90_time_revert_cross_snapshot_boundary;
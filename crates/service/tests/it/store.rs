@@ -12,12 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs;
 use std::io;
+use std::path::PathBuf;
 
+use databend_meta::analysis::snapshot_inspector::SnapshotInspector;
 use databend_meta::meta_node::meta_node::LogStore;
 use databend_meta::meta_node::meta_node::SMStore;
 use databend_meta::store::RaftStore;
 use databend_meta_raft_store::leveled_store::db_exporter::DBExporter;
+use databend_meta_raft_store::raft_log_v004::RaftLogDurability;
+use databend_meta_raft_store::rewind::rewind_to_snapshot;
 use databend_meta_raft_store::state_machine::testing::snapshot_logs;
 use databend_meta_runtime_api::TokioRuntime;
 use databend_meta_sled_store::openraft::RaftLogReader;
@@ -29,7 +34,13 @@ use databend_meta_sled_store::openraft::storage::RaftLogStorageExt;
 use databend_meta_sled_store::openraft::storage::RaftStateMachine;
 use databend_meta_sled_store::openraft::testing::log::StoreBuilder;
 use databend_meta_sled_store::openraft::testing::log_id;
+use databend_meta_types::Cmd;
+use databend_meta_types::LogEntry;
+use databend_meta_types::UpsertKV;
+use databend_meta_types::protobuf::KeysCount;
+use databend_meta_types::protobuf::KeysLayoutRequest;
 use databend_meta_types::raft_types::Entry;
+use databend_meta_types::raft_types::EntryPayload;
 use databend_meta_types::raft_types::Membership;
 use databend_meta_types::raft_types::StorageError;
 use databend_meta_types::raft_types::StoredMembership;
@@ -37,6 +48,7 @@ use databend_meta_types::raft_types::TypeConfig;
 use databend_meta_types::raft_types::Vote;
 use databend_meta_types::raft_types::new_log_id;
 use databend_meta_types::snapshot_db::DB;
+use databend_meta_types::sys_data::SysData;
 use futures::TryStreamExt;
 use futures::stream;
 use log::debug;
@@ -49,7 +61,9 @@ use test_harness::test;
 use crate::testing::meta_service_test_harness;
 use crate::tests::service::MetaSrvTestContext;
 
-struct MetaStoreBuilder {}
+struct MetaStoreBuilder {
+    durability: RaftLogDurability,
+}
 
 impl StoreBuilder<TypeConfig, LogStore, SMStore<TokioRuntime>, MetaSrvTestContext<TokioRuntime>>
     for MetaStoreBuilder
@@ -64,7 +78,9 @@ impl StoreBuilder<TypeConfig, LogStore, SMStore<TokioRuntime>, MetaSrvTestContex
         ),
         StorageError,
     > {
-        let tc = MetaSrvTestContext::<TokioRuntime>::new(555);
+        let mut tc = MetaSrvTestContext::<TokioRuntime>::new(555);
+        tc.config.raft_config.raft_log_durability = self.durability;
+
         let sto = RaftStore::<TokioRuntime>::open(&tc.config.raft_config)
             .await
             .expect("fail to create store");
@@ -75,7 +91,32 @@ impl StoreBuilder<TypeConfig, LogStore, SMStore<TokioRuntime>, MetaSrvTestContex
 #[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
 #[fastrace::trace]
 async fn test_impl_raft_storage() -> anyhow::Result<()> {
-    databend_meta_sled_store::openraft::testing::log::Suite::test_all(MetaStoreBuilder {}).await?;
+    databend_meta_sled_store::openraft::testing::log::Suite::test_all(MetaStoreBuilder {
+        durability: RaftLogDurability::Fsync,
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_impl_raft_storage_group_commit() -> anyhow::Result<()> {
+    databend_meta_sled_store::openraft::testing::log::Suite::test_all(MetaStoreBuilder {
+        durability: RaftLogDurability::GroupCommit,
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_impl_raft_storage_replication_durability() -> anyhow::Result<()> {
+    databend_meta_sled_store::openraft::testing::log::Suite::test_all(MetaStoreBuilder {
+        durability: RaftLogDurability::Replication,
+    })
+    .await?;
 
     Ok(())
 }
@@ -118,13 +159,13 @@ ChunkId(00_000_000_000_000_000_000)
   R-00000: [000_000_000, 000_000_018) Size(18): State(RaftLogState { vote: None, last: None, committed: None, purged: None, user_data: None })
   R-00001: [000_000_018, 000_000_046) Size(28): State(RaftLogState { vote: None, last: None, committed: None, purged: None, user_data: Some(LogStoreMeta { node_id: Some(3) }) })
   R-00002: [000_000_046, 000_000_096) Size(50): SaveVote(Cw(Vote { leader_id: LeaderId { term: 10, node_id: 5 }, committed: false }))
-  R-00003: [000_000_096, 000_000_148) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 1 }), Cw(blank))
-  R-00004: [000_000_148, 000_000_200) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 2 }), Cw(blank))
+  R-00003: [000_000_096, 000_000_148) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 1 }), Plain(blank))
+  R-00004: [000_000_148, 000_000_200) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 2 }), Plain(blank))
 ChunkId(00_000_000_000_000_000_200)
   R-00000: [000_000_000, 000_000_100) Size(100): State(RaftLogState { vote: Some(Cw(Vote { leader_id: LeaderId { term: 10, node_id: 5 }, committed: false })), last: Some(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 2 })), committed: None, purged: None, user_data: Some(LogStoreMeta { node_id: Some(3) }) })
-  R-00001: [000_000_100, 000_000_152) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 3 }), Cw(blank))
-  R-00002: [000_000_152, 000_000_204) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 4 }), Cw(blank))
-  R-00003: [000_000_204, 000_000_256) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 5 }), Cw(blank))
+  R-00001: [000_000_100, 000_000_152) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 3 }), Plain(blank))
+  R-00002: [000_000_152, 000_000_204) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 4 }), Plain(blank))
+  R-00003: [000_000_204, 000_000_256) Size(52): Append(Cw(LogId { leader_id: LeaderId { term: 1, node_id: 2 }, index: 5 }), Plain(blank))
 "#;
             assert_eq!(want_dumped, got);
         }
@@ -376,6 +417,237 @@ async fn test_meta_store_install_snapshot() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_meta_store_list_and_rewind_snapshot() -> anyhow::Result<()> {
+    // - Build a snapshot at log 4 and another at log 9
+    // - List the retained snapshots
+    // - Refuse to rewind to the second snapshot, which has more than one voter
+    // - Rewind the stopped store to the first snapshot, which has a single voter
+    // - Reopen and check the state is at log 4
+
+    let id = 3;
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(id);
+    tc.config.raft_config.snapshot_keep_count = 5;
+
+    let (mut logs, _want) = snapshot_logs();
+    logs[0].payload =
+        EntryPayload::Membership(Membership::new_with_defaults(vec![btreeset![id]], []));
+
+    let first_id;
+    let second_id;
+    {
+        let sto = RaftStore::<TokioRuntime>::open(&tc.config.raft_config).await?;
+
+        sto.log().clone().save_vote(&Vote::new(10, 5)).await?;
+        sto.log().clone().blocking_append(logs.clone()).await?;
+        sto.log()
+            .clone()
+            .save_committed(Some(log_id(1, 0, 9)))
+            .await?;
+
+        let sm = sto.get_sm_v003();
+
+        let entry_stream = stream::iter(logs[..4].iter().cloned().map(|e| Ok((e, None))));
+        sm.apply_entries(entry_stream).await?;
+        sto.state_machine().clone().build_snapshot().await?;
+
+        let entry_stream = stream::iter(logs[4..].iter().cloned().map(|e| Ok((e, None))));
+        sm.apply_entries(entry_stream).await?;
+        sto.state_machine().clone().build_snapshot().await?;
+
+        info!("--- list snapshots");
+
+        let loader = sto.state_machine().snapshot_store().new_loader();
+        let infos = loader.list_snapshots().await?;
+
+        assert_eq!(2, infos.len());
+        assert_eq!(Some(log_id(1, 0, 4)), infos[0].last_log_id());
+        assert_eq!(Some(log_id(1, 0, 9)), infos[1].last_log_id());
+        assert!(infos[0].key_num < infos[1].key_num);
+
+        first_id = infos[0].snapshot_id.to_string();
+        second_id = infos[1].snapshot_id.to_string();
+    }
+
+    info!("--- rewind to the first snapshot");
+    {
+        let res = rewind_to_snapshot(&tc.config.raft_config, "1-0-100-1").await;
+        assert_eq!(io::ErrorKind::NotFound, res.unwrap_err().kind());
+
+        let res = rewind_to_snapshot(&tc.config.raft_config, &second_id).await;
+        assert_eq!(io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+        let info = rewind_to_snapshot(&tc.config.raft_config, &first_id).await?;
+        assert_eq!(Some(log_id(1, 0, 4)), info.last_log_id());
+    }
+
+    info!("--- reopen and check the rewound state");
+    {
+        let sto = RaftStore::<TokioRuntime>::open(&tc.config.raft_config).await?;
+
+        assert_eq!(Some(Vote::new(10, 5)), sto.log().clone().read_vote().await?);
+        assert_eq!(
+            Some(log_id(1, 0, 4)),
+            sto.log().clone().read_committed().await?
+        );
+
+        let log_state = sto.log().clone().get_log_state().await?;
+        assert_eq!(Some(log_id(1, 0, 4)), log_state.last_purged_log_id);
+        assert_eq!(Some(log_id(1, 0, 4)), log_state.last_log_id);
+
+        let last_applied = *sto.get_sm_v003().sys_data().last_applied_ref();
+        assert_eq!(Some(log_id(1, 0, 4)), last_applied);
+
+        let loader = sto.state_machine().snapshot_store().new_loader();
+        let infos = loader.list_snapshots().await?;
+        assert_eq!(1, infos.len());
+        assert_eq!(first_id, infos[0].snapshot_id.to_string());
+    }
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_meta_store_encrypted() -> anyhow::Result<()> {
+    // - Open a store with an encryption key file, append logs and build a snapshot
+    // - Neither the raft-log nor the snapshot contains the plaintext
+    // - Reopen and read the logs and the state machine back
+
+    let id = 3;
+    let mut tc = MetaSrvTestContext::<TokioRuntime>::new(id);
+
+    let key_file = tc._temp_dir.path().join("encryption_keys");
+    fs::write(
+        &key_file,
+        "1:0000000000000000000000000000000000000000000000000000000000000001",
+    )?;
+    tc.config.raft_config.encryption_key_file = Some(key_file.to_string_lossy().to_string());
+
+    let (mut logs, _want) = snapshot_logs();
+    logs.push(Entry {
+        log_id: new_log_id(1, 0, 10),
+        payload: EntryPayload::Normal(LogEntry::new(Cmd::UpsertKV(UpsertKV::update(
+            "secret-key",
+            b"secret-value",
+        )))),
+    });
+
+    {
+        let sto = RaftStore::<TokioRuntime>::open(&tc.config.raft_config).await?;
+
+        sto.log().clone().blocking_append(logs.clone()).await?;
+
+        let sm = sto.get_sm_v003();
+        let entry_stream = stream::iter(logs.iter().cloned().map(|e| Ok((e, None))));
+        sm.apply_entries(entry_stream).await?;
+        sto.state_machine().clone().build_snapshot().await?;
+    }
+
+    info!("--- no plaintext on disk");
+    {
+        let mut files = vec![PathBuf::from(&tc.config.raft_config.raft_dir)];
+        let mut n = 0;
+        while let Some(path) = files.pop() {
+            if path.is_dir() {
+                for dent in fs::read_dir(&path)? {
+                    files.push(dent?.path());
+                }
+                continue;
+            }
+
+            let content = fs::read(&path)?;
+            for plain in [&b"secret-key"[..], b"secret-value"] {
+                assert!(
+                    !content.windows(plain.len()).any(|w| w == plain),
+                    "plaintext found in {}",
+                    path.display()
+                );
+            }
+            n += 1;
+        }
+        assert!(n > 0);
+    }
+
+    info!("--- reopen and read back");
+    {
+        let sto = RaftStore::<TokioRuntime>::open(&tc.config.raft_config).await?;
+
+        let got = sto.log().clone().try_get_log_entries(0..11).await?;
+        assert_eq!(logs, got);
+
+        let got = sto.get_sm_v003().get_maybe_expired_kv("secret-key").await?;
+        assert_eq!(Some(b"secret-value".to_vec()), got.map(|x| x.data));
+    }
+
+    info!("--- can not be opened without the key");
+    {
+        let mut config = tc.config.raft_config.clone();
+        config.encryption_key_file = None;
+
+        let res = RaftStore::<TokioRuntime>::open(&config).await;
+        assert!(res.is_err());
+    }
+
+    Ok(())
+}
+
+#[test(harness = meta_service_test_harness::<TokioRuntime, _, _>)]
+#[fastrace::trace]
+async fn test_meta_store_inspect_snapshot() -> anyhow::Result<()> {
+    // - Build a snapshot
+    // - Inspect it from the raft dir and from the snapshot file, without a running store
+
+    let id = 3;
+    let tc = MetaSrvTestContext::<TokioRuntime>::new(id);
+
+    let (logs, _want) = snapshot_logs();
+
+    let snapshot_path;
+    {
+        let sto = RaftStore::<TokioRuntime>::open(&tc.config.raft_config).await?;
+
+        let entry_stream = stream::iter(logs.into_iter().map(|e| Ok((e, None))));
+        sto.get_sm_v003().apply_entries(entry_stream).await?;
+
+        let snap = sto.state_machine().clone().build_snapshot().await?;
+        snapshot_path = snap.snapshot.path();
+    }
+
+    let config = &tc.config.raft_config;
+
+    for inspector in [
+        SnapshotInspector::open_raft_dir::<TokioRuntime>(config).await?,
+        SnapshotInspector::open_file(&snapshot_path, config)?,
+    ] {
+        assert_eq!(Some(log_id(1, 0, 9)), inspector.snapshot_meta().last_log_id);
+
+        let got = inspector.get("a").await?;
+        assert_eq!(Some(b"A".to_vec()), got.map(|x| x.data));
+        assert_eq!(None, inspector.get("b").await?);
+
+        let listed = inspector.list("").await?.try_collect::<Vec<_>>().await?;
+        assert_eq!(
+            vec!["a".to_string()],
+            listed.into_iter().map(|(k, _)| k).collect::<Vec<_>>()
+        );
+
+        let counts = inspector
+            .count_prefix(KeysLayoutRequest { depth: None })
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(vec![KeysCount::new("", 1)], counts);
+
+        let json = inspector.sys_data_json()?;
+        let sys_data: SysData = serde_json::from_str(&json)?;
+        assert_eq!(inspector.sys_data(), &sys_data);
+    }
+
+    Ok(())
+}
+
 async fn db_to_lines(db: &DB) -> Result<Vec<String>, io::Error> {
     let strm = DBExporter::new(db).export().await?;
     let res = strm.try_collect::<Vec<_>>().await?;
@@ -27,12 +27,13 @@ use databend_meta::message::ForwardRequest;
 use databend_meta::message::ForwardRequestBody;
 use databend_meta::meta_node::meta_worker::MetaWorker;
 use databend_meta::meta_service::MetaNode;
+use databend_meta::raft_auth::AuthRaftServiceClient;
+use databend_meta::raft_auth::RaftAuth;
 use databend_meta_client::ClientHandle;
 use databend_meta_client::DEFAULT_GRPC_MESSAGE_SIZE;
 use databend_meta_client::MetaGrpcClient;
 use databend_meta_client::errors::CreationError;
 use databend_meta_runtime_api::RuntimeApi;
-use databend_meta_types::protobuf::raft_service_client::RaftServiceClient;
 use databend_meta_types::raft_types::NodeId;
 use log::info;
 use log::warn;
@@ -241,14 +242,13 @@ impl<R: RuntimeApi> MetaSrvTestContext<R> {
         Ok(client)
     }
 
-    pub async fn raft_client(
-        &self,
-    ) -> anyhow::Result<RaftServiceClient<tonic::transport::Channel>> {
+    pub async fn raft_client(&self) -> anyhow::Result<AuthRaftServiceClient> {
         let addr = self.config.raft_config.raft_api_addr::<R>().await?;
+        let auth = RaftAuth::from_config(&self.config.raft_config);
 
         // retry 3 times until server starts listening.
         for _ in 0..3 {
-            let client = RaftServiceClient::connect(format!("http://{}", addr)).await;
+            let client = auth.connect(&addr).await;
             match client {
                 Ok(x) => return Ok(x),
                 Err(err) => {
@@ -44,7 +44,11 @@ fn build_proto() {
 
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     tonic_build::configure()
-        .btree_map(["RaftLogStatus.wal_closed_chunk_sizes"])
+        .btree_map([
+            "RaftLogStatus.wal_closed_chunk_sizes",
+            "ClusterStatus.labels",
+            "ClusterStatus.prefix_quotas",
+        ])
         .file_descriptor_set_path(out_dir.join("meta_descriptor.bin"))
         .type_attribute(
             "SeqV",
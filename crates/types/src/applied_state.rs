@@ -16,6 +16,7 @@ use std::fmt;
 use std::fmt::Formatter;
 
 use crate::Change;
+use crate::QuotaExceeded;
 use crate::TxnReply;
 use crate::node::Node;
 use crate::protobuf::RaftReply;
@@ -42,6 +43,9 @@ pub enum AppliedState {
 
     TxnReply(TxnReply),
 
+    /// The command is not applied because it would exceed the quota of a key prefix.
+    QuotaExceeded(QuotaExceeded),
+
     #[try_into(ignore)]
     None,
 }
@@ -59,6 +63,9 @@ impl fmt::Display for AppliedState {
             AppliedState::TxnReply(txnreply) => {
                 write!(f, "Txn: {}", txnreply)
             }
+            AppliedState::QuotaExceeded(e) => {
+                write!(f, "QuotaExceeded: {}", e)
+            }
             AppliedState::None => {
                 write!(f, "None")
             }
@@ -74,6 +81,7 @@ impl AppliedState {
             AppliedState::KV(ch) => ch.is_changed(),
             AppliedState::None => false,
             AppliedState::TxnReply(txn) => txn.success,
+            AppliedState::QuotaExceeded(_) => false,
         }
     }
 
@@ -99,6 +107,7 @@ impl AppliedState {
             AppliedState::KV(Change { prev, .. }) => prev.is_none(),
             AppliedState::None => true,
             AppliedState::TxnReply(_txn) => true,
+            AppliedState::QuotaExceeded(_) => true,
         }
     }
 
@@ -108,6 +117,7 @@ impl AppliedState {
             AppliedState::KV(Change { result, .. }) => result.is_none(),
             AppliedState::None => true,
             AppliedState::TxnReply(txn) => !txn.success,
+            AppliedState::QuotaExceeded(_) => true,
         }
     }
 }
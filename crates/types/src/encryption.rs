@@ -0,0 +1,460 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encryption at rest of the raft log, the snapshot data and the data header.
+//!
+//! Encrypted data is stored in a frame, with AES-256-GCM:
+//!
+//! ```text
+//! | 0xc1 | key_id: u32 | nonce: 12 bytes | len: u32 | ciphertext: len bytes |
+//! ```
+//!
+//! `0xc1` is never used by msgpack, and it is not the first byte of a JSON document.
+//! Thus a raft log record or a data header written before encryption is enabled is still readable.
+//!
+//! In a snapshot, every key-value, including the key, is encrypted into one frame,
+//! and so is the [`SysData`] stored in the snapshot meta, see [`DB`].
+//!
+//! The key file contains one key per line in the form `<key_id>:<64 hex digits>`.
+//! Empty lines and lines starting with `#` are ignored.
+//! The last key is the active key that encrypts new data,
+//! the other keys are only used to decrypt data written before.
+//!
+//! To rotate keys, append a new key to the file and restart the node.
+//! New log records and the next snapshot are encrypted with the new key,
+//! and a snapshot re-encrypts every key-value it contains.
+//! An old key can be removed after the next snapshot is built and the logs before it are purged.
+//!
+//! The keys are loaded by the config of a node, see [`KeyRingCache`],
+//! thus nodes, tests and tools in one process do not share keys.
+//!
+//! When a snapshot is streamed to another node, the key-values are sent decrypted,
+//! and the receiver encrypts them with its own active key.
+//! A snapshot file sent as is stays encrypted with the sender's key:
+//! the receiver can not open it unless its key file contains that key.
+//!
+//! [`SysData`]: crate::sys_data::SysData
+//! [`DB`]: crate::snapshot_db::DB
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use aes_gcm::Aes256Gcm;
+use aes_gcm::Nonce;
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::AeadCore;
+use aes_gcm::aead::KeyInit;
+use aes_gcm::aead::OsRng;
+use log::info;
+
+/// The first byte of an encrypted frame.
+pub const FRAME_MARKER: u8 = 0xc1;
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+/// Size of the frame header: marker, key id, nonce and ciphertext length.
+const HEADER_SIZE: usize = 1 + 4 + NONCE_SIZE + 4;
+
+/// Returns if `data` is an encrypted frame.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.first() == Some(&FRAME_MARKER)
+}
+
+/// Returns the id of the key that encrypted the frame `data`, `None` if it is not encrypted.
+pub fn frame_key_id(data: &[u8]) -> Option<u32> {
+    if !is_encrypted(data) || data.len() < 5 {
+        return None;
+    }
+    Some(u32::from_be_bytes(data[1..5].try_into().unwrap()))
+}
+
+/// Encrypt `data` with the key ring if there is one, otherwise return it as is.
+pub fn seal(key_ring: Option<&KeyRing>, data: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+    match key_ring {
+        None => Ok(data),
+        Some(kr) => kr.encrypt(&data),
+    }
+}
+
+/// Decrypt `data` if it is an encrypted frame, otherwise return it as is.
+pub fn unseal(key_ring: Option<&KeyRing>, data: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+
+    let Some(kr) = key_ring else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "data is encrypted with key {:?}, but no encryption key file is configured",
+                frame_key_id(&data)
+            ),
+        ));
+    };
+
+    kr.decrypt(&data)
+}
+
+/// Read the rest of an encrypted frame from `r`, after the marker byte has been read.
+///
+/// Returns the complete frame including the marker.
+pub fn read_frame_after_marker<R: Read>(mut r: R) -> Result<Vec<u8>, io::Error> {
+    let mut frame = vec![0u8; HEADER_SIZE];
+    frame[0] = FRAME_MARKER;
+    r.read_exact(&mut frame[1..])?;
+
+    let len = u32::from_be_bytes(frame[HEADER_SIZE - 4..].try_into().unwrap()) as usize;
+
+    frame.resize(HEADER_SIZE + len, 0);
+    r.read_exact(&mut frame[HEADER_SIZE..])?;
+
+    Ok(frame)
+}
+
+/// The keys to encrypt and decrypt data, loaded from a key file.
+#[derive(Clone)]
+pub struct KeyRing {
+    active: u32,
+    ciphers: BTreeMap<u32, Aes256Gcm>,
+}
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyRing")
+            .field("active", &self.active)
+            .field("key_ids", &self.ciphers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl KeyRing {
+    /// Load keys from a key file, see the [module doc](self) for the format.
+    pub fn load(path: &str) -> Result<Self, io::Error> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("{}; when:(read key file: {})", e, path))
+        })?;
+
+        Self::parse(&content).map_err(|e| {
+            io::Error::new(e.kind(), format!("{}; when:(parse key file: {})", e, path))
+        })
+    }
+
+    /// Parse keys from the content of a key file.
+    pub fn parse(content: &str) -> Result<Self, io::Error> {
+        let mut active = None;
+        let mut ciphers = BTreeMap::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |msg: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid key at line {}: {}", i + 1, msg),
+                )
+            };
+
+            let (id, key) = line
+                .split_once(':')
+                .ok_or_else(|| invalid("expect <key_id>:<hex key>"))?;
+
+            let id = id
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| invalid(&format!("key_id: {}", e)))?;
+
+            let key = hex::decode(key.trim()).map_err(|e| invalid(&format!("key: {}", e)))?;
+            if key.len() != KEY_SIZE {
+                return Err(invalid(&format!(
+                    "key must be {} bytes, got {} bytes",
+                    KEY_SIZE,
+                    key.len()
+                )));
+            }
+
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| invalid(&e.to_string()))?;
+
+            if ciphers.insert(id, cipher).is_some() {
+                return Err(invalid(&format!("duplicated key_id: {}", id)));
+            }
+            active = Some(id);
+        }
+
+        let Some(active) = active else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no key found in key file",
+            ));
+        };
+
+        Ok(Self { active, ciphers })
+    }
+
+    /// The id of the key that encrypts new data.
+    pub fn active_key_id(&self) -> u32 {
+        self.active
+    }
+
+    /// Encrypt `plain` with the active key into a frame.
+    pub fn encrypt(&self, plain: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let cipher = &self.ciphers[&self.active];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher.encrypt(&nonce, plain).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("fail to encrypt with key {}: {}", self.active, e),
+            )
+        })?;
+
+        let mut frame = Vec::with_capacity(HEADER_SIZE + ciphertext.len());
+        frame.push(FRAME_MARKER);
+        frame.extend_from_slice(&self.active.to_be_bytes());
+        frame.extend_from_slice(nonce.as_slice());
+        frame.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&ciphertext);
+
+        Ok(frame)
+    }
+
+    /// Decrypt a frame built by [`Self::encrypt`], with the key it was encrypted with.
+    pub fn decrypt(&self, frame: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        if !is_encrypted(frame) || frame.len() < HEADER_SIZE {
+            return Err(invalid(format!(
+                "not an encrypted frame: {} bytes",
+                frame.len()
+            )));
+        }
+
+        let key_id = u32::from_be_bytes(frame[1..5].try_into().unwrap());
+        let nonce = Nonce::from_slice(&frame[5..5 + NONCE_SIZE]);
+        let len = u32::from_be_bytes(frame[HEADER_SIZE - 4..HEADER_SIZE].try_into().unwrap());
+        let ciphertext = &frame[HEADER_SIZE..];
+
+        if ciphertext.len() != len as usize {
+            return Err(invalid(format!(
+                "encrypted frame length mismatch: expect: {}, actual: {}",
+                len,
+                ciphertext.len()
+            )));
+        }
+
+        let cipher = self.ciphers.get(&key_id).ok_or_else(|| {
+            invalid(format!(
+                "encryption key {} not found in key file, known keys: {:?}",
+                key_id,
+                self.ciphers.keys().collect::<Vec<_>>()
+            ))
+        })?;
+
+        cipher
+            .decrypt(nonce, ciphertext)
+            .map_err(|e| invalid(format!("fail to decrypt with key {}: {}", key_id, e)))
+    }
+}
+
+/// Loads the key file of a config at most once, and shares the loaded [`KeyRing`] among the clones.
+///
+/// It lives in the config instead of a process wide static,
+/// so that every node, test or offline tool uses the keys it is configured with.
+///
+/// The file is not reloaded when it changes: a rotated key is used after the node restarts.
+#[derive(Clone, Default)]
+pub struct KeyRingCache {
+    loaded: Arc<Mutex<Option<(String, Arc<KeyRing>)>>>,
+}
+
+impl fmt::Debug for KeyRingCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let loaded = self.loaded.lock().unwrap();
+        f.debug_struct("KeyRingCache")
+            .field("loaded", &loaded.as_ref().map(|(path, _)| path))
+            .finish()
+    }
+}
+
+/// Two caches are equal if they have loaded the same key file, or neither has loaded one.
+impl PartialEq for KeyRingCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.loaded, &other.loaded) || self.loaded_path() == other.loaded_path()
+    }
+}
+
+impl Eq for KeyRingCache {}
+
+impl KeyRingCache {
+    /// Returns the key ring loaded from `path`, load it if it is not loaded yet.
+    pub fn load(&self, path: &str) -> Result<Arc<KeyRing>, io::Error> {
+        let mut loaded = self.loaded.lock().unwrap();
+
+        if let Some((p, key_ring)) = loaded.as_ref() {
+            if p == path {
+                return Ok(key_ring.clone());
+            }
+        }
+
+        let key_ring = Arc::new(KeyRing::load(path)?);
+        info!("encryption at rest enabled: {:?}", key_ring);
+
+        *loaded = Some((path.to_string(), key_ring.clone()));
+        Ok(key_ring)
+    }
+
+    /// Returns the path of the loaded key file, `None` if nothing is loaded.
+    pub fn loaded_path(&self) -> Option<String> {
+        let loaded = self.loaded.lock().unwrap();
+        loaded.as_ref().map(|(path, _)| path.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY1: &str = "1:0000000000000000000000000000000000000000000000000000000000000001";
+    const KEY2: &str = "2:0000000000000000000000000000000000000000000000000000000000000002";
+
+    #[test]
+    fn test_key_ring_parse() -> anyhow::Result<()> {
+        let kr = KeyRing::parse(&format!("# comment\n{}\n\n{}\n", KEY1, KEY2))?;
+        assert_eq!(2, kr.active_key_id());
+        assert_eq!(
+            "KeyRing { active: 2, key_ids: [1, 2] }",
+            format!("{:?}", kr)
+        );
+
+        assert!(KeyRing::parse("").is_err());
+        assert!(KeyRing::parse("1:00").is_err());
+        assert!(KeyRing::parse("x:00").is_err());
+        assert!(KeyRing::parse(&format!("{}\n{}", KEY1, KEY1)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() -> anyhow::Result<()> {
+        let kr = KeyRing::parse(KEY1)?;
+
+        let frame = kr.encrypt(b"hello")?;
+        assert_eq!(Some(1), frame_key_id(&frame));
+        assert!(!frame.windows(5).any(|w| w == b"hello"));
+        assert_eq!(b"hello".to_vec(), kr.decrypt(&frame)?);
+
+        // Random nonce
+        assert_ne!(frame, kr.encrypt(b"hello")?);
+
+        // Tampered
+        let mut bad = frame.clone();
+        *bad.last_mut().unwrap() ^= 1;
+        assert!(kr.decrypt(&bad).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_rotation() -> anyhow::Result<()> {
+        let kr1 = KeyRing::parse(KEY1)?;
+        let kr12 = KeyRing::parse(&format!("{}\n{}", KEY1, KEY2))?;
+        let kr2 = KeyRing::parse(KEY2)?;
+
+        let old = kr1.encrypt(b"foo")?;
+
+        // The rotated key ring decrypts old data and encrypts with the new key.
+        let plain = kr12.decrypt(&old)?;
+        let new = kr12.encrypt(&plain)?;
+        assert_eq!(Some(2), frame_key_id(&new));
+
+        // After the old key is removed, only re-encrypted data is readable.
+        assert!(kr2.decrypt(&old).is_err());
+        assert_eq!(b"foo".to_vec(), kr2.decrypt(&new)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seal_unseal() -> anyhow::Result<()> {
+        let kr = KeyRing::parse(KEY1)?;
+
+        // Plaintext is readable with or without a key ring.
+        assert_eq!(b"a".to_vec(), unseal(Some(&kr), b"a".to_vec())?);
+        assert_eq!(b"a".to_vec(), unseal(None, b"a".to_vec())?);
+
+        assert_eq!(b"a".to_vec(), seal(None, b"a".to_vec())?);
+
+        let sealed = seal(Some(&kr), b"a".to_vec())?;
+        assert!(is_encrypted(&sealed));
+        assert_eq!(b"a".to_vec(), unseal(Some(&kr), sealed.clone())?);
+        assert!(unseal(None, sealed).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_ring_cache() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("keys");
+        let path = path.to_str().unwrap();
+        fs::write(path, KEY1)?;
+
+        let cache = KeyRingCache::default();
+        assert_eq!(1, cache.load(path)?.active_key_id());
+
+        // Loaded only once, and shared by the clones.
+        fs::write(path, format!("{}\n{}", KEY1, KEY2))?;
+        assert_eq!(1, cache.clone().load(path)?.active_key_id());
+
+        // Another cache does not share the loaded keys.
+        let other = KeyRingCache::default();
+        assert_ne!(cache, other);
+        assert_eq!(2, other.load(path)?.active_key_id());
+
+        // Compared by the loaded key file.
+        assert_eq!(cache, other);
+        assert_eq!(Some(path.to_string()), cache.loaded_path());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_frame_after_marker() -> anyhow::Result<()> {
+        let kr = KeyRing::parse(KEY1)?;
+
+        let frame = kr.encrypt(b"hello")?;
+        let mut buf = frame.clone();
+        buf.extend_from_slice(b"trailing");
+
+        let mut r = &buf[1..];
+        let got = read_frame_after_marker(&mut r)?;
+        assert_eq!(frame, got);
+        assert_eq!(b"trailing", r);
+
+        // Incomplete frame
+        let mut r = &frame[1..frame.len() - 1];
+        let err = read_frame_after_marker(&mut r).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+
+        Ok(())
+    }
+}
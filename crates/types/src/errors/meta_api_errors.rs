@@ -22,10 +22,13 @@ use crate::InvalidArgument;
 use crate::InvalidReply;
 use crate::MetaNetworkError;
 use crate::errors;
+use crate::prefix_quota::PrefixQuota;
+use crate::prefix_quota::PrefixUsage;
 use crate::raft_types::ChangeMembershipError;
 use crate::raft_types::ClientWriteError;
 use crate::raft_types::Fatal;
 use crate::raft_types::ForwardToLeader;
+use crate::raft_types::NodeId;
 use crate::raft_types::RaftError;
 
 /// Errors raised when meta-service handling a request.
@@ -72,6 +75,9 @@ impl MetaAPIError {
                 },
                 MetaDataError::WriteError(_) => false,
                 MetaDataError::ReadError(_) => false,
+                MetaDataError::QuotaExceeded(_) => false,
+                MetaDataError::WriteLimitExceeded(_) => false,
+                MetaDataError::IncompatibleVersion(_) => false,
             },
             MetaAPIError::ForwardToLeader(_) => {
                 // Leader is changing, wait a while and retry
@@ -85,6 +91,9 @@ impl MetaAPIError {
                 MetaDataError::WriteError(_) => false,
                 MetaDataError::ChangeMembershipError(_) => true,
                 MetaDataError::ReadError(_) => false,
+                MetaDataError::QuotaExceeded(_) => false,
+                MetaDataError::WriteLimitExceeded(_) => false,
+                MetaDataError::IncompatibleVersion(_) => false,
             },
         }
     }
@@ -98,6 +107,24 @@ impl MetaAPIError {
             MetaAPIError::RemoteError(_) => "RemoteError",
         }
     }
+
+    /// Return the [`QuotaExceeded`] error if a write is rejected by a prefix quota.
+    pub fn quota_exceeded(&self) -> Option<&QuotaExceeded> {
+        match self {
+            MetaAPIError::DataError(MetaDataError::QuotaExceeded(e)) => Some(e),
+            MetaAPIError::RemoteError(MetaDataError::QuotaExceeded(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Return the [`WriteLimitExceeded`] error if a write is rejected by the leader's write limits.
+    pub fn write_limit_exceeded(&self) -> Option<&WriteLimitExceeded> {
+        match self {
+            MetaAPIError::DataError(MetaDataError::WriteLimitExceeded(e)) => Some(e),
+            MetaAPIError::RemoteError(MetaDataError::WriteLimitExceeded(e)) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 /// Errors raised when handling a request by raft node.
@@ -134,6 +161,87 @@ pub enum MetaDataError {
     /// Error occurred when reading.
     #[error(transparent)]
     ReadError(#[from] MetaDataReadError),
+
+    /// A write is rejected because it would exceed the quota of a key prefix.
+    #[error(transparent)]
+    QuotaExceeded(#[from] QuotaExceeded),
+
+    /// A write is rejected by the leader before proposing, because it exceeds a size limit.
+    #[error(transparent)]
+    WriteLimitExceeded(#[from] WriteLimitExceeded),
+
+    /// A request is rejected because the version of a node can not work with the cluster.
+    #[error(transparent)]
+    IncompatibleVersion(#[from] IncompatibleVersion),
+}
+
+/// A write is rejected because it would make the usage of a key prefix exceed its quota.
+#[derive(thiserror::Error, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[error("quota of prefix '{prefix}' exceeded by writing '{key}': usage: {usage}; quota: {quota}")]
+pub struct QuotaExceeded {
+    /// The prefix the quota applies to.
+    pub prefix: String,
+
+    /// The key being written.
+    pub key: String,
+
+    pub quota: PrefixQuota,
+
+    /// The usage before the rejected write.
+    pub usage: PrefixUsage,
+}
+
+impl QuotaExceeded {
+    pub fn new(
+        prefix: impl ToString,
+        key: impl ToString,
+        quota: PrefixQuota,
+        usage: PrefixUsage,
+    ) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            key: key.to_string(),
+            quota,
+            usage,
+        }
+    }
+}
+
+/// A write request exceeds a size limit, such as the key length or the number of operations.
+#[derive(thiserror::Error, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[error("write request exceeds limit: {msg}")]
+pub struct WriteLimitExceeded {
+    pub msg: String,
+}
+
+impl WriteLimitExceeded {
+    pub fn new(msg: impl ToString) -> Self {
+        Self {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+/// The version of a node can not work with the cluster.
+#[derive(thiserror::Error, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[error("node {node_id} of version {version} is incompatible: {reason}")]
+pub struct IncompatibleVersion {
+    pub node_id: NodeId,
+
+    /// The binary version of the node.
+    pub version: String,
+
+    pub reason: String,
+}
+
+impl IncompatibleVersion {
+    pub fn new(node_id: NodeId, version: impl ToString, reason: impl ToString) -> Self {
+        Self {
+            node_id,
+            version: version.to_string(),
+            reason: reason.to_string(),
+        }
+    }
 }
 
 /// Error occurred when a meta-node reads data.
@@ -14,6 +14,7 @@
 
 use std::io;
 
+use tonic::Code;
 use tonic::Status;
 
 use crate::ConnectionError;
@@ -38,6 +39,14 @@ impl MetaClientError {
             MetaClientError::HandshakeError(_) => "MetaHandshakeError",
         }
     }
+
+    /// The gRPC status code if the error is built from a status returned by the server.
+    pub fn status_code(&self) -> Option<Code> {
+        match self {
+            MetaClientError::NetworkError(err) => err.status_code(),
+            MetaClientError::HandshakeError(_) => None,
+        }
+    }
 }
 
 impl From<Status> for MetaClientError {
@@ -42,6 +42,10 @@ pub enum MetaNetworkError {
     #[error(transparent)]
     BadAddressFormat(AnyError),
 
+    /// Failed to listen on a local address, e.g., the address is in use.
+    #[error(transparent)]
+    BindError(AnyError),
+
     #[error(transparent)]
     InvalidArgument(#[from] InvalidArgument),
 
@@ -57,11 +61,21 @@ impl MetaNetworkError {
             Self::DnsParseError(e) => Self::DnsParseError(format!("{}: {}", e, context)),
             Self::TLSConfigError(e) => Self::TLSConfigError(e.add_context(|| context)),
             Self::BadAddressFormat(e) => Self::BadAddressFormat(e.add_context(|| context)),
+            Self::BindError(e) => Self::BindError(e.add_context(|| context)),
             Self::InvalidArgument(e) => e.add_context(context).into(),
             Self::InvalidReply(e) => e.add_context(context).into(),
         }
     }
 
+    /// The gRPC status code if the error is built from a status returned by the server.
+    pub fn status_code(&self) -> Option<Code> {
+        match self {
+            Self::ConnectionError(e) => e.status_code(),
+            Self::InvalidArgument(_) => Some(Code::InvalidArgument),
+            _ => None,
+        }
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             MetaNetworkError::ConnectionError(_) => "ConnectionError",
@@ -69,6 +83,7 @@ impl MetaNetworkError {
             MetaNetworkError::DnsParseError(_) => "DnsParseError",
             MetaNetworkError::TLSConfigError(_) => "TLSConfigError",
             MetaNetworkError::BadAddressFormat(_) => "BadAddressFormat",
+            MetaNetworkError::BindError(_) => "BindError",
             MetaNetworkError::InvalidArgument(_) => "InvalidArgument",
             MetaNetworkError::InvalidReply(_) => "InvalidReply",
         }
@@ -83,6 +98,10 @@ pub struct ConnectionError {
     msg: String,
     #[source]
     source: AnyError,
+
+    /// The gRPC status code, if it is built from a status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<i32>,
 }
 
 impl ConnectionError {
@@ -90,8 +109,19 @@ impl ConnectionError {
         Self {
             msg: msg.into(),
             source: AnyError::new(&source),
+            code: None,
         }
     }
+
+    pub fn with_status_code(mut self, code: Code) -> Self {
+        self.code = Some(code as i32);
+        self
+    }
+
+    pub fn status_code(&self) -> Option<Code> {
+        self.code.map(Code::from)
+    }
+
     pub fn add_context(mut self, context: impl Display) -> Self {
         self.msg = format!("{}: {}", self.msg, context);
         self
@@ -185,7 +215,11 @@ impl From<tonic::Status> for MetaNetworkError {
             // Code::Unavailable => {}
             // Code::DataLoss => {}
             // Code::Unauthenticated => {}
-            _ => MetaNetworkError::ConnectionError(ConnectionError::new(status, "")),
+            _ => {
+                let code = status.code();
+                let e = ConnectionError::new(status, "").with_status_code(code);
+                MetaNetworkError::ConnectionError(e)
+            }
         }
     }
 }
@@ -210,6 +244,7 @@ impl From<MetaNetworkError> for io::Error {
             MetaNetworkError::DnsParseError(_) => io::ErrorKind::NotConnected,
             MetaNetworkError::TLSConfigError(_) => io::ErrorKind::NotConnected,
             MetaNetworkError::BadAddressFormat(_) => io::ErrorKind::InvalidInput,
+            MetaNetworkError::BindError(_) => io::ErrorKind::AddrNotAvailable,
             MetaNetworkError::InvalidArgument(_) => io::ErrorKind::InvalidInput,
             MetaNetworkError::InvalidReply(_) => io::ErrorKind::InvalidData,
         };
@@ -0,0 +1,103 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The compression algorithm of gRPC messages.
+
+use std::fmt;
+use std::str::FromStr;
+
+use tonic::codec::CompressionEncoding;
+
+/// The compression algorithm a gRPC peer uses to send messages.
+///
+/// A receiver accepts every supported algorithm.
+/// A server compresses a response only if the client advertises the algorithm
+/// in the `grpc-accept-encoding` header of the request.
+/// A request is compressed without negotiation:
+/// the sender must know that the receiver accepts it, otherwise the call is rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrpcCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl GrpcCompression {
+    /// All the supported encodings, to be accepted by a receiver.
+    pub const ACCEPTED: [CompressionEncoding; 2] =
+        [CompressionEncoding::Gzip, CompressionEncoding::Zstd];
+
+    /// The encoding to send messages with, or `None` to send uncompressed messages.
+    pub fn encoding(&self) -> Option<CompressionEncoding> {
+        match self {
+            GrpcCompression::None => None,
+            GrpcCompression::Gzip => Some(CompressionEncoding::Gzip),
+            GrpcCompression::Zstd => Some(CompressionEncoding::Zstd),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrpcCompression::None => "none",
+            GrpcCompression::Gzip => "gzip",
+            GrpcCompression::Zstd => "zstd",
+        }
+    }
+}
+
+impl fmt::Display for GrpcCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for GrpcCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(GrpcCompression::None),
+            "gzip" => Ok(GrpcCompression::Gzip),
+            "zstd" => Ok(GrpcCompression::Zstd),
+            _ => Err(format!(
+                "invalid grpc compression: {:?}; expect one of: none, gzip, zstd",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grpc_compression_from_str() {
+        assert_eq!(Ok(GrpcCompression::None), "".parse());
+        assert_eq!(Ok(GrpcCompression::None), "none".parse());
+        assert_eq!(Ok(GrpcCompression::Gzip), "gzip".parse());
+        assert_eq!(Ok(GrpcCompression::Zstd), "ZSTD".parse());
+        assert!("lz4".parse::<GrpcCompression>().is_err());
+
+        for c in [
+            GrpcCompression::None,
+            GrpcCompression::Gzip,
+            GrpcCompression::Zstd,
+        ] {
+            assert_eq!(Ok(c), c.to_string().parse());
+        }
+    }
+}
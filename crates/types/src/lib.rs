@@ -22,6 +22,7 @@ mod applied_state;
 mod change;
 mod cluster;
 mod endpoint;
+mod grpc_compression;
 mod grpc_helper;
 mod log_entry;
 mod message;
@@ -34,10 +35,13 @@ mod proto_display;
 mod proto_ext;
 
 pub mod cmd;
+pub mod encryption;
 pub mod errors;
 pub mod node;
 pub mod normalize_meta;
+pub mod prefix_quota;
 pub mod raft_types;
+pub mod snapshot_checksum;
 pub mod snapshot_db;
 pub mod sys_data;
 // reexport
@@ -57,10 +61,13 @@ pub use change::Change;
 pub use cluster::NodeInfo;
 pub use cluster::NodeType;
 pub use endpoint::Endpoint;
+pub use errors::meta_api_errors::IncompatibleVersion;
 pub use errors::meta_api_errors::MetaAPIError;
 pub use errors::meta_api_errors::MetaDataError;
 pub use errors::meta_api_errors::MetaDataReadError;
 pub use errors::meta_api_errors::MetaOperationError;
+pub use errors::meta_api_errors::QuotaExceeded;
+pub use errors::meta_api_errors::WriteLimitExceeded;
 pub use errors::meta_client_errors::MetaClientError;
 pub use errors::meta_errors::MetaError;
 pub use errors::meta_handshake_errors::MetaHandshakeError;
@@ -112,4 +119,5 @@ pub use crate::cmd::Cmd;
 pub use crate::cmd::CmdContext;
 pub use crate::cmd::MetaSpec;
 pub use crate::cmd::UpsertKV;
+pub use crate::grpc_compression::GrpcCompression;
 pub use crate::grpc_helper::GrpcHelper;
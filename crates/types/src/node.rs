@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
 use std::fmt;
 
 use serde::Deserialize;
@@ -35,9 +36,29 @@ pub struct Node {
 
     /// The address `ip:port` for a meta-client to connect to.
     pub grpc_api_advertise_address: Option<String>,
+
+    /// Placement labels of this node, such as `zone` and `rack`, or arbitrary key/values.
+    ///
+    /// Absent in data written by older versions.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
+    /// The version of the `databend-meta` binary this node runs, reported when it joins or starts.
+    ///
+    /// Absent in data written by older versions, or if the node has not reported yet.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_version: Option<String>,
 }
 
 impl Node {
+    /// The label key for the availability zone a node is in.
+    pub const LABEL_ZONE: &'static str = "zone";
+
+    /// The label key for the rack a node is in.
+    pub const LABEL_RACK: &'static str = "rack";
+
     pub fn new(name: impl ToString, endpoint: Endpoint) -> Self {
         Self {
             name: name.to_string(),
@@ -50,6 +71,31 @@ impl Node {
         self.grpc_api_advertise_address = g.map(|x| x.to_string());
         self
     }
+
+    pub fn with_binary_version(mut self, v: Option<impl ToString>) -> Self {
+        self.binary_version = v.map(|x| x.to_string());
+        self
+    }
+
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn with_label(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Returns the availability zone of this node, if labeled.
+    pub fn zone(&self) -> Option<&str> {
+        self.labels.get(Self::LABEL_ZONE).map(|s| s.as_str())
+    }
+
+    /// Returns the rack of this node, if labeled.
+    pub fn rack(&self) -> Option<&str> {
+        self.labels.get(Self::LABEL_RACK).map(|s| s.as_str())
+    }
 }
 
 impl fmt::Display for Node {
@@ -63,6 +109,74 @@ impl fmt::Display for Node {
             f,
             "id={} raft={} grpc={}",
             self.name, self.endpoint, grpc_addr_display
-        )
+        )?;
+
+        if !self.labels.is_empty() {
+            let labels = self
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(",");
+            write!(f, " labels={{{}}}", labels)?;
+        }
+
+        if let Some(v) = &self.binary_version {
+            write!(f, " version={}", v)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Node;
+    use crate::Endpoint;
+
+    #[test]
+    fn test_node_labels_serde() -> anyhow::Result<()> {
+        // Empty labels are not serialized, to keep compatible with older versions.
+        let n = Node::new("n1", Endpoint::new("e1", 12));
+        let want = r#"{"name":"n1","endpoint":{"addr":"e1","port":12},"grpc_api_advertise_address":null}"#;
+        assert_eq!(want, serde_json::to_string(&n)?);
+        assert_eq!(n, serde_json::from_str(want)?);
+
+        let n = Node::new("n1", Endpoint::new("e1", 12))
+            .with_label(Node::LABEL_ZONE, "z1")
+            .with_label(Node::LABEL_RACK, "r1");
+        let want = r#"{"name":"n1","endpoint":{"addr":"e1","port":12},"grpc_api_advertise_address":null,"labels":{"rack":"r1","zone":"z1"}}"#;
+        assert_eq!(want, serde_json::to_string(&n)?);
+        assert_eq!(n, serde_json::from_str(want)?);
+
+        assert_eq!(Some("z1"), n.zone());
+        assert_eq!(Some("r1"), n.rack());
+        assert_eq!(
+            "id=n1 raft=e1:12 grpc= labels={rack=r1,zone=z1}",
+            n.to_string()
+        );
+
+        let n = n.with_labels(BTreeMap::new());
+        assert_eq!(None, n.zone());
+        assert_eq!("id=n1 raft=e1:12 grpc=", n.to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_node_binary_version_serde() -> anyhow::Result<()> {
+        let n = Node::new("n1", Endpoint::new("e1", 12)).with_binary_version(Some("1.2.3"));
+        let want = r#"{"name":"n1","endpoint":{"addr":"e1","port":12},"grpc_api_advertise_address":null,"binary_version":"1.2.3"}"#;
+        assert_eq!(want, serde_json::to_string(&n)?);
+        assert_eq!(n, serde_json::from_str(want)?);
+        assert_eq!("id=n1 raft=e1:12 grpc= version=1.2.3", n.to_string());
+
+        // Data written by older versions has no version.
+        let old = r#"{"name":"n1","endpoint":{"addr":"e1","port":12},"grpc_api_advertise_address":null}"#;
+        let n: Node = serde_json::from_str(old)?;
+        assert_eq!(None, n.binary_version);
+
+        Ok(())
     }
 }
@@ -0,0 +1,183 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage quotas of key prefixes.
+//!
+//! A quota of prefix `P` is defined by writing a JSON encoded [`PrefixQuota`]
+//! to the reserved key `__fd_quota/P`, and is removed by deleting this key.
+//! The state machine maintains the usage of every defined quota in [`SysData`],
+//! and rejects a write that would make the usage exceed the quota.
+//!
+//! [`SysData`]: crate::sys_data::SysData
+
+use std::fmt;
+
+/// The reserved key space in which quota definitions are stored.
+///
+/// Keys in this key space are not counted in any quota.
+pub const QUOTA_KEY_PREFIX: &str = "__fd_quota/";
+
+/// Build the key that stores the quota of `prefix`.
+pub fn quota_key(prefix: &str) -> String {
+    format!("{}{}", QUOTA_KEY_PREFIX, prefix)
+}
+
+/// Parse the prefix a quota applies to from a quota key.
+///
+/// Returns `None` if the key is not a quota key.
+pub fn parse_quota_key(key: &str) -> Option<&str> {
+    key.strip_prefix(QUOTA_KEY_PREFIX)
+}
+
+/// Limits of the keys under a prefix. `None` means no limit.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixQuota {
+    /// Max number of keys under the prefix.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_keys: Option<u64>,
+
+    /// Max total size in bytes of the keys and values under the prefix.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+}
+
+impl PrefixQuota {
+    pub fn new(max_keys: Option<u64>, max_bytes: Option<u64>) -> Self {
+        Self {
+            max_keys,
+            max_bytes,
+        }
+    }
+
+    /// Whether adding `growth` to `usage` exceeds this quota.
+    ///
+    /// A dimension that does not grow never exceeds,
+    /// so that a write that does not increase the usage is still allowed
+    /// after the quota is lowered below the current usage.
+    pub fn exceeded_by(&self, usage: &PrefixUsage, growth: &PrefixUsage) -> bool {
+        let keys = self
+            .max_keys
+            .is_some_and(|max| growth.keys > 0 && usage.keys + growth.keys > max);
+        let bytes = self
+            .max_bytes
+            .is_some_and(|max| growth.bytes > 0 && usage.bytes + growth.bytes > max);
+        keys || bytes
+    }
+}
+
+impl fmt::Display for PrefixQuota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "max_keys: ")?;
+        match self.max_keys {
+            Some(n) => write!(f, "{}", n)?,
+            None => write!(f, "unlimited")?,
+        }
+        write!(f, ", max_bytes: ")?;
+        match self.max_bytes {
+            Some(n) => write!(f, "{}", n),
+            None => write!(f, "unlimited"),
+        }
+    }
+}
+
+/// The number of keys and the total size of keys and values under a prefix.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefixUsage {
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+impl PrefixUsage {
+    pub fn new(keys: u64, bytes: u64) -> Self {
+        Self { keys, bytes }
+    }
+
+    /// The size of an entry counted in a quota.
+    pub fn entry_size(key: &str, value: &[u8]) -> u64 {
+        (key.len() + value.len()) as u64
+    }
+
+    pub fn add(&mut self, keys: u64, bytes: u64) {
+        self.keys += keys;
+        self.bytes += bytes;
+    }
+
+    pub fn sub(&mut self, keys: u64, bytes: u64) {
+        self.keys = self.keys.saturating_sub(keys);
+        self.bytes = self.bytes.saturating_sub(bytes);
+    }
+}
+
+impl fmt::Display for PrefixUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "keys: {}, bytes: {}", self.keys, self.bytes)
+    }
+}
+
+/// A quota and the current usage of its prefix.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuotaState {
+    pub quota: PrefixQuota,
+    pub usage: PrefixUsage,
+}
+
+impl QuotaState {
+    pub fn new(quota: PrefixQuota, usage: PrefixUsage) -> Self {
+        Self { quota, usage }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_key() {
+        assert_eq!("__fd_quota/tenant1/", quota_key("tenant1/"));
+        assert_eq!(Some("tenant1/"), parse_quota_key("__fd_quota/tenant1/"));
+        assert_eq!(None, parse_quota_key("tenant1/"));
+    }
+
+    #[test]
+    fn test_prefix_quota_serde() {
+        let q = PrefixQuota::new(Some(3), None);
+        let s = serde_json::to_string(&q).unwrap();
+        assert_eq!(r#"{"max_keys":3}"#, s);
+
+        let got: PrefixQuota = serde_json::from_str(r#"{"max_bytes":10}"#).unwrap();
+        assert_eq!(PrefixQuota::new(None, Some(10)), got);
+
+        assert_eq!("max_keys: 3, max_bytes: unlimited", q.to_string());
+    }
+
+    #[test]
+    fn test_prefix_quota_exceeded_by() {
+        let q = PrefixQuota::new(Some(2), Some(10));
+        let usage = PrefixUsage::new(1, 5);
+
+        assert!(!q.exceeded_by(&usage, &PrefixUsage::new(1, 5)));
+        assert!(q.exceeded_by(&usage, &PrefixUsage::new(2, 5)));
+        assert!(q.exceeded_by(&usage, &PrefixUsage::new(1, 6)));
+
+        // Usage is already above the quota, but the write does not grow it.
+        let over = PrefixUsage::new(5, 50);
+        assert!(!q.exceeded_by(&over, &PrefixUsage::new(0, 0)));
+        assert!(q.exceeded_by(&over, &PrefixUsage::new(0, 1)));
+
+        let unlimited = PrefixQuota::default();
+        assert!(!unlimited.exceeded_by(&over, &PrefixUsage::new(u32::MAX as u64, 1)));
+    }
+}
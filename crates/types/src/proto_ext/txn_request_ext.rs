@@ -129,6 +129,19 @@ impl pb::TxnRequest {
             else_then: vec![],
         }
     }
+
+    /// Returns `true` if every operation in every branch is a `get`,
+    /// i.e., executing it more than once does not change anything.
+    pub fn is_read_only(&self) -> bool {
+        let branch_ops = self.operations.iter().flat_map(|b| b.operations.iter());
+        let mut ops = self
+            .if_then
+            .iter()
+            .chain(self.else_then.iter())
+            .chain(branch_ops);
+
+        ops.all(|op| matches!(op.request, Some(pb::txn_op::Request::Get(_))))
+    }
 }
 
 impl Display for pb::TxnRequest {
@@ -153,6 +166,21 @@ mod tests {
     use crate::Interval;
     use crate::MetaSpec;
 
+    #[test]
+    fn test_is_read_only() {
+        let req = pb::TxnRequest::new(vec![pb::TxnCondition::eq_seq("k1", 1)], vec![
+            pb::TxnOp::get("k1"),
+        ])
+        .with_else(vec![pb::TxnOp::get("k2")]);
+        assert!(req.is_read_only());
+
+        let req = req.push_branch(None, [pb::TxnOp::delete("k1")]);
+        assert!(!req.is_read_only());
+
+        let req = pb::TxnRequest::new(vec![], vec![pb::TxnOp::put("k1", b"v1".to_vec())]);
+        assert!(!req.is_read_only());
+    }
+
     #[test]
     fn test_display_txn_request() {
         let op = pb::ConditionalOperation {
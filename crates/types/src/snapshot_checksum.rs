@@ -0,0 +1,123 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checksum of the key-values in a snapshot.
+
+use std::fmt;
+
+use rotbl::v001::SeqMarked;
+
+/// A rolling checksum of the key-values in a snapshot, in key order.
+///
+/// Only normal values are included, tombstones are skipped:
+/// they are not transmitted to another node and are not present in an installed snapshot.
+///
+/// A key-value contributes the key, the seq and the value bytes as stored in a snapshot,
+/// before being encrypted, so that the checksum does not depend on the encryption key.
+#[derive(Clone, Default)]
+pub struct SnapshotChecksum {
+    hasher: crc32fast::Hasher,
+    count: u64,
+}
+
+impl fmt::Debug for SnapshotChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotChecksum")
+            .field("checksum", &self.checksum())
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+impl SnapshotChecksum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key-value to the checksum; a tombstone is ignored.
+    pub fn update(&mut self, key: &str, value: &SeqMarked) {
+        let Some(data) = value.data_ref() else {
+            return;
+        };
+
+        let seq = *value.internal_seq();
+
+        self.hasher.update(&(key.len() as u64).to_le_bytes());
+        self.hasher.update(key.as_bytes());
+        self.hasher.update(&seq.to_le_bytes());
+        self.hasher.update(&(data.len() as u64).to_le_bytes());
+        self.hasher.update(data);
+
+        self.count += 1;
+    }
+
+    /// The number of key-values included.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The checksum of all the key-values added so far.
+    pub fn checksum(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checksum_of(kvs: &[(&str, SeqMarked)]) -> SnapshotChecksum {
+        let mut c = SnapshotChecksum::new();
+        for (k, v) in kvs {
+            c.update(k, v);
+        }
+        c
+    }
+
+    #[test]
+    fn test_snapshot_checksum() {
+        let a = SeqMarked::new_normal(1, b"x".to_vec());
+        let b = SeqMarked::new_normal(2, b"y".to_vec());
+        let tomb = SeqMarked::new_tombstone(3);
+
+        let c = checksum_of(&[("a", a.clone()), ("b", b.clone())]);
+        assert_eq!(2, c.count());
+
+        // Tombstones are ignored.
+        let with_tomb = checksum_of(&[("a", a.clone()), ("a2", tomb), ("b", b.clone())]);
+        assert_eq!(c.checksum(), with_tomb.checksum());
+        assert_eq!(2, with_tomb.count());
+
+        // Key, seq and value all contribute.
+        assert_ne!(
+            c.checksum(),
+            checksum_of(&[("a", a.clone()), ("c", b.clone())]).checksum()
+        );
+        assert_ne!(
+            c.checksum(),
+            checksum_of(&[("a", a.clone()), ("b", SeqMarked::new_normal(3, b"y".to_vec()))])
+                .checksum()
+        );
+        assert_ne!(
+            c.checksum(),
+            checksum_of(&[("a", a), ("b", SeqMarked::new_normal(2, b"z".to_vec()))]).checksum()
+        );
+
+        // Boundaries between key and value are not ambiguous.
+        assert_ne!(
+            checksum_of(&[("ab", SeqMarked::new_normal(1, b"c".to_vec()))]).checksum(),
+            checksum_of(&[("a", SeqMarked::new_normal(1, b"bc".to_vec()))]).checksum()
+        );
+    }
+}
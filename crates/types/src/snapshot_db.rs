@@ -14,20 +14,202 @@
 
 use std::fmt;
 use std::fs;
+use std::future;
 use std::io;
 use std::io::BufReader;
+use std::ops::Bound;
 use std::sync::Arc;
+use std::sync::OnceLock;
 
+use futures_util::StreamExt;
+use futures_util::TryStreamExt;
+use futures_util::stream;
 use futures_util::stream::BoxStream;
 use log::info;
 use openraft::SnapshotId;
+use rotbl::v001::Marked;
 use rotbl::v001::Rotbl;
 use rotbl::v001::SeqMarked;
 use rotbl::v001::stat::RotblStat;
 
+use crate::encryption;
+use crate::encryption::KeyRing;
 use crate::raft_types::SnapshotMeta;
+use crate::snapshot_checksum::SnapshotChecksum;
 use crate::sys_data::SysData;
 
+/// The field in the rotbl meta that stores the snapshot checksum, along with the fields of [`SysData`].
+///
+/// A version without checksum support ignores this field when loading the [`SysData`].
+const CHECKSUM_FIELD: &str = "snapshot_checksum";
+
+/// The only field in the rotbl meta of an encrypted DB: the hex encoded frame of the plaintext user data.
+const ENCRYPTED_FIELD: &str = "encrypted";
+
+#[derive(serde::Deserialize)]
+struct ChecksumField {
+    #[serde(default)]
+    snapshot_checksum: Option<u32>,
+}
+
+#[derive(serde::Deserialize)]
+struct EncryptedField {
+    #[serde(default)]
+    encrypted: Option<String>,
+}
+
+/// Encode the [`SysData`] and the checksum of the key-values into the user data of a rotbl meta.
+///
+/// If a key ring is provided, the user data is encrypted.
+pub fn encode_rotbl_user_data(
+    sys_data: &SysData,
+    checksum: Option<u32>,
+    key_ring: Option<&KeyRing>,
+) -> Result<String, io::Error> {
+    let mut v = serde_json::to_value(sys_data)?;
+
+    if let (Some(checksum), Some(obj)) = (checksum, v.as_object_mut()) {
+        obj.insert(CHECKSUM_FIELD.to_string(), checksum.into());
+    }
+
+    let plain = serde_json::to_string(&v)?;
+
+    let Some(key_ring) = key_ring else {
+        return Ok(plain);
+    };
+
+    let frame = key_ring.encrypt(plain.as_bytes())?;
+    let sealed = serde_json::json!({ ENCRYPTED_FIELD: hex::encode(frame) });
+    Ok(serde_json::to_string(&sealed)?)
+}
+
+/// Decode the [`SysData`] and the checksum of the key-values from the user data of a rotbl meta.
+///
+/// Returns the decoded data and whether the user data is encrypted.
+pub fn decode_rotbl_user_data(
+    user_data: &str,
+    key_ring: Option<&KeyRing>,
+) -> Result<(SysData, Option<u32>, bool), io::Error> {
+    let invalid = |e: serde_json::Error| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    let encrypted: EncryptedField = serde_json::from_str(user_data).map_err(invalid)?;
+
+    let (plain, is_encrypted) = match encrypted.encrypted {
+        None => (user_data.to_string(), false),
+        Some(hex_frame) => {
+            let frame = hex::decode(hex_frame)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let plain = encryption::unseal(key_ring, frame)?;
+            let plain = String::from_utf8(plain)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            (plain, true)
+        }
+    };
+
+    let sys_data: SysData = serde_json::from_str(&plain).map_err(invalid)?;
+    let checksum: ChecksumField = serde_json::from_str(&plain).map_err(invalid)?;
+    Ok((sys_data, checksum.snapshot_checksum, is_encrypted))
+}
+
+/// Encrypt a key-value of a snapshot into the value of the rotbl entry at a position.
+///
+/// The key is encrypted along with the value, thus a tombstone is stored as a normal value.
+pub fn seal_entry(key_ring: &KeyRing, key: &str, v: SeqMarked) -> Result<SeqMarked, io::Error> {
+    let (seq, data) = v.into_parts();
+
+    let mut plain = Vec::with_capacity(4 + key.len() + 1);
+    plain.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    plain.extend_from_slice(key.as_bytes());
+
+    match data {
+        Marked::TombStone => plain.push(0),
+        Marked::Normal(bytes) => {
+            plain.push(1);
+            plain.extend_from_slice(&bytes);
+        }
+    }
+
+    Ok(SeqMarked::new_normal(seq, key_ring.encrypt(&plain)?))
+}
+
+/// Decrypt the key-value of a snapshot stored by [`seal_entry`].
+pub fn unseal_entry(key_ring: &KeyRing, v: SeqMarked) -> Result<(String, SeqMarked), io::Error> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let (seq, data) = v.into_parts();
+    let Marked::Normal(frame) = data else {
+        return Err(invalid("encrypted snapshot entry must not be a tombstone"));
+    };
+
+    let plain = key_ring.decrypt(&frame)?;
+
+    if plain.len() < 4 {
+        return Err(invalid("encrypted snapshot entry is too short"));
+    }
+    let key_len = u32::from_be_bytes(plain[..4].try_into().unwrap()) as usize;
+
+    if plain.len() < 4 + key_len + 1 {
+        return Err(invalid("encrypted snapshot entry is too short"));
+    }
+    let key = String::from_utf8(plain[4..4 + key_len].to_vec())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let v = match plain[4 + key_len] {
+        0 => SeqMarked::new_tombstone(seq),
+        _ => SeqMarked::new_normal(seq, plain[4 + key_len + 1..].to_vec()),
+    };
+
+    Ok((key, v))
+}
+
+/// The rotbl key of the `i`-th key-value in an encrypted DB.
+///
+/// Fixed width hex keeps the rotbl keys in the same order as the encrypted keys.
+pub fn entry_position_key(i: u64) -> String {
+    format!("{:016x}", i)
+}
+
+/// The interval of the positions whose keys are kept in an [`EncryptedIndex`].
+const ENCRYPTED_INDEX_STRIDE: u64 = 64;
+
+/// A sparse in-memory index of an encrypted DB: the decrypted key at every [`ENCRYPTED_INDEX_STRIDE`]-th position.
+///
+/// It is loaded on the first lookup and shared by the clones of a DB.
+/// With it, a lookup reads and decrypts about `log2(ENCRYPTED_INDEX_STRIDE)` entries instead of `log2(key_num)`.
+#[derive(Clone, Default)]
+pub struct EncryptedIndex {
+    keys: Arc<OnceLock<Arc<Vec<String>>>>,
+}
+
+impl EncryptedIndex {
+    /// Returns the sampled keys, reading them from the rotbl if not yet loaded.
+    async fn load(
+        &self,
+        rotbl: &Arc<Rotbl>,
+        key_ring: &KeyRing,
+    ) -> Result<Arc<Vec<String>>, io::Error> {
+        if let Some(keys) = self.keys.get() {
+            return Ok(keys.clone());
+        }
+
+        let key_num = rotbl.stat().key_num;
+        let mut keys = Vec::with_capacity(key_num.div_ceil(ENCRYPTED_INDEX_STRIDE) as usize);
+
+        for i in (0..key_num).step_by(ENCRYPTED_INDEX_STRIDE as usize) {
+            let (k, _) = encrypted_entry_at(rotbl, key_ring, i).await?;
+            keys.push(k);
+        }
+
+        info!(
+            "loaded encrypted index: {} keys of {} key-values",
+            keys.len(),
+            key_num
+        );
+
+        Ok(self.keys.get_or_init(|| Arc::new(keys)).clone())
+    }
+}
+
 /// A readonly leveled map that owns the data.
 #[derive(Clone)]
 pub struct DB {
@@ -35,6 +217,21 @@ pub struct DB {
     pub rel_path: String,
     pub meta: SnapshotMeta,
     pub sys_data: SysData,
+
+    /// The checksum of the key-values, see [`SnapshotChecksum`].
+    ///
+    /// `None` if the DB is built by a version without checksum support.
+    pub checksum: Option<u32>,
+
+    /// The keys to decrypt the key-values, `Some` only if the DB is encrypted.
+    ///
+    /// An encrypted DB stores the `i`-th key-value, with the key encrypted, at [`entry_position_key`].
+    /// A key is looked up by a binary search over the positions, narrowed by [`Self::encrypted_index`].
+    pub key_ring: Option<Arc<KeyRing>>,
+
+    /// The sampled decrypted keys to narrow a lookup in an encrypted DB, unused if not encrypted.
+    pub encrypted_index: EncryptedIndex,
+
     pub rotbl: Arc<Rotbl>,
 }
 
@@ -45,6 +242,7 @@ impl fmt::Debug for DB {
             .field("rel_path", &self.rel_path)
             .field("meta", &self.meta)
             .field("sys_data", &self.sys_data)
+            .field("checksum", &self.checksum)
             .finish()
     }
 }
@@ -56,14 +254,21 @@ impl AsRef<SysData> for DB {
 }
 
 impl DB {
+    /// Create a DB from an opened rotbl.
+    ///
+    /// `key_ring` is required if the rotbl is encrypted, and is ignored if it is not.
     pub fn new(
         storage_path: impl ToString,
         rel_path: impl ToString,
         snapshot_id: SnapshotId,
         r: Arc<Rotbl>,
+        key_ring: Option<Arc<KeyRing>>,
     ) -> Result<Self, io::Error> {
-        let sys_data = r.meta().user_data();
-        let sys_data: SysData = serde_json::from_str(sys_data).unwrap();
+        let user_data = r.meta().user_data();
+        let (sys_data, checksum, is_encrypted) =
+            decode_rotbl_user_data(user_data, key_ring.as_deref()).map_err(|e| {
+                io::Error::new(e.kind(), format!("fail to decode snapshot meta: {}", e))
+            })?;
 
         let snapshot_meta = SnapshotMeta {
             last_log_id: *sys_data.last_applied_ref(),
@@ -76,11 +281,54 @@ impl DB {
             rel_path: rel_path.to_string(),
             meta: snapshot_meta,
             sys_data,
+            checksum,
+            key_ring: if is_encrypted { key_ring } else { None },
+            encrypted_index: EncryptedIndex::default(),
             rotbl: r,
         };
         Ok(s)
     }
 
+    /// Re-read all the key-values and check them against the checksum stored in the meta.
+    ///
+    /// Returns the number of key-values verified,
+    /// or `None` if there is no checksum stored, i.e., built by an older version.
+    pub async fn verify(&self) -> Result<Option<u64>, io::Error> {
+        let Some(expected) = self.checksum else {
+            info!("DB at {} has no checksum, skip verifying", self.path());
+            return Ok(None);
+        };
+
+        let mut checksum = SnapshotChecksum::new();
+
+        let mut strm = self.inner_range();
+        while let Some((k, v)) = strm.try_next().await? {
+            checksum.update(&k, &v);
+        }
+
+        if checksum.checksum() != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "snapshot checksum mismatch: expected: {:08x}, actual: {:08x} of {} key-values; when:(verify DB at: {})",
+                    expected,
+                    checksum.checksum(),
+                    checksum.count(),
+                    self.path()
+                ),
+            ));
+        }
+
+        info!(
+            "DB at {} verified: checksum: {:08x}, {} key-values",
+            self.path(),
+            expected,
+            checksum.count()
+        );
+
+        Ok(Some(checksum.count()))
+    }
+
     /// Create an `BufReader<std::fs::File>` pointing to the same file of this db
     pub fn open_file(&self) -> Result<BufReader<fs::File>, io::Error> {
         info!("Opening file for DB at path: {}", self.path());
@@ -97,8 +345,56 @@ impl DB {
         Ok(buf_f)
     }
 
+    /// Returns all the key-values, decrypted if they are encrypted.
     pub fn inner_range(&self) -> BoxStream<'static, Result<(String, SeqMarked), io::Error>> {
-        self.rotbl.range(..)
+        self.range((Bound::Unbounded, Bound::Unbounded))
+    }
+
+    /// Get the value of a key, decrypted if it is encrypted.
+    pub async fn get(&self, key: &str) -> Result<Option<SeqMarked>, io::Error> {
+        let Some(key_ring) = &self.key_ring else {
+            return self.rotbl.get(key).await;
+        };
+
+        let start = Bound::Included(key.to_string());
+        let i = encrypted_lower_bound(&self.rotbl, key_ring, &self.encrypted_index, &start).await?;
+
+        if i >= self.rotbl.stat().key_num {
+            return Ok(None);
+        }
+
+        let (k, v) = encrypted_entry_at(&self.rotbl, key_ring, i).await?;
+        if k == key { Ok(Some(v)) } else { Ok(None) }
+    }
+
+    /// Returns the key-values in a range, decrypted if they are encrypted.
+    pub fn range(
+        &self,
+        range: (Bound<String>, Bound<String>),
+    ) -> BoxStream<'static, Result<(String, SeqMarked), io::Error>> {
+        let Some(key_ring) = self.key_ring.clone() else {
+            return self.rotbl.range(range).boxed();
+        };
+
+        let rotbl = self.rotbl.clone();
+        let index = self.encrypted_index.clone();
+        let (start, end) = range;
+
+        let fu = async move {
+            let i = encrypted_lower_bound(&rotbl, &key_ring, &index, &start).await?;
+
+            let strm = rotbl
+                .range((Bound::Included(entry_position_key(i)), Bound::Unbounded))
+                .map(move |res| {
+                    let (_position, v) = res?;
+                    unseal_entry(&key_ring, v)
+                })
+                .try_take_while(move |(k, _)| future::ready(Ok(is_before_end(k, &end))));
+
+            Ok::<_, io::Error>(strm)
+        };
+
+        stream::once(fu).try_flatten().boxed()
     }
 
     pub fn inner(&self) -> &Arc<Rotbl> {
@@ -150,6 +446,78 @@ impl DB {
     }
 }
 
+/// Read and decrypt the key-value at position `i` of an encrypted rotbl.
+async fn encrypted_entry_at(
+    rotbl: &Arc<Rotbl>,
+    key_ring: &KeyRing,
+    i: u64,
+) -> Result<(String, SeqMarked), io::Error> {
+    let position = entry_position_key(i);
+    let v = rotbl.get(&position).await?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "encrypted snapshot entry not found at position {}",
+                position
+            ),
+        )
+    })?;
+
+    unseal_entry(key_ring, v)
+}
+
+/// Returns the position of the first key-value in an encrypted rotbl that is not before `start`.
+///
+/// The sampled keys in `index` narrow the range to one stride,
+/// which is then binary searched by reading the entries.
+async fn encrypted_lower_bound(
+    rotbl: &Arc<Rotbl>,
+    key_ring: &KeyRing,
+    index: &EncryptedIndex,
+    start: &Bound<String>,
+) -> Result<u64, io::Error> {
+    let keys = index.load(rotbl, key_ring).await?;
+
+    // The number of sampled keys before `start`.
+    let n = keys.partition_point(|k| is_before_start(k, start)) as u64;
+    if n == 0 {
+        return Ok(0);
+    }
+
+    // The entry at `(n - 1) * stride` is before `start`, the one at `n * stride`, if any, is not.
+    let mut lo = (n - 1) * ENCRYPTED_INDEX_STRIDE + 1;
+    let mut hi = std::cmp::min(n * ENCRYPTED_INDEX_STRIDE, rotbl.stat().key_num);
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let (k, _) = encrypted_entry_at(rotbl, key_ring, mid).await?;
+
+        if is_before_start(&k, start) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    Ok(lo)
+}
+
+fn is_before_start(k: &str, start: &Bound<String>) -> bool {
+    match start {
+        Bound::Included(s) => k < s.as_str(),
+        Bound::Excluded(s) => k <= s.as_str(),
+        Bound::Unbounded => false,
+    }
+}
+
+fn is_before_end(k: &str, end: &Bound<String>) -> bool {
+    match end {
+        Bound::Included(e) => k <= e.as_str(),
+        Bound::Excluded(e) => k < e.as_str(),
+        Bound::Unbounded => true,
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DBStat {
     /// Total number of blocks.
@@ -189,6 +557,8 @@ mod tests {
 
     use super::*;
 
+    const KEY: &str = "1:0000000000000000000000000000000000000000000000000000000000000001";
+
     /// Debug should not output db cache data.
     #[test]
     fn test_db_debug() {
@@ -204,12 +574,81 @@ mod tests {
             rel_path: "b".to_string(),
             meta: Default::default(),
             sys_data: Default::default(),
+            checksum: None,
+            key_ring: None,
+            encrypted_index: Default::default(),
             rotbl: Arc::new(rotbl),
         };
 
         assert_eq!(
             format!("{:?}", db),
-            r#"DB { storage_path: "a", rel_path: "b", meta: SnapshotMeta { last_log_id: None, last_membership: StoredMembership { log_id: None, membership: Membership { configs: [], nodes: {} } }, snapshot_id: "" }, sys_data: SysData { last_applied: None, last_membership: StoredMembership { log_id: None, membership: Membership { configs: [], nodes: {} } }, nodes: {}, sequence: 0, data_seq: None, key_counts: {}, sm_features: {} } }"#
+            r#"DB { storage_path: "a", rel_path: "b", meta: SnapshotMeta { last_log_id: None, last_membership: StoredMembership { log_id: None, membership: Membership { configs: [], nodes: {} } }, snapshot_id: "" }, sys_data: SysData { last_applied: None, last_membership: StoredMembership { log_id: None, membership: Membership { configs: [], nodes: {} } }, nodes: {}, sequence: 0, data_seq: None, key_counts: {}, sm_features: {}, prefix_quotas: {} }, checksum: None }"#
+        );
+    }
+
+    #[test]
+    fn test_rotbl_user_data_checksum() -> anyhow::Result<()> {
+        let mut sys_data = SysData::default();
+        sys_data.update_seq(5);
+
+        let s = encode_rotbl_user_data(&sys_data, Some(0xabcd), None)?;
+        assert_eq!(
+            (sys_data.clone(), Some(0xabcd), false),
+            decode_rotbl_user_data(&s, None)?
+        );
+
+        // Compatible with versions that do not know the checksum field.
+        let got: SysData = serde_json::from_str(&s)?;
+        assert_eq!(sys_data, got);
+
+        let s = serde_json::to_string(&sys_data)?;
+        assert_eq!(
+            (sys_data.clone(), None, false),
+            decode_rotbl_user_data(&s, None)?
         );
+        assert_eq!(s, encode_rotbl_user_data(&sys_data, None, None)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotbl_user_data_encrypted() -> anyhow::Result<()> {
+        let kr = KeyRing::parse(KEY)?;
+
+        let mut sys_data = SysData::default();
+        sys_data.update_seq(5);
+
+        let s = encode_rotbl_user_data(&sys_data, Some(0xabcd), Some(&kr))?;
+        assert!(!s.contains("sequence"));
+
+        assert_eq!(
+            (sys_data.clone(), Some(0xabcd), true),
+            decode_rotbl_user_data(&s, Some(&kr))?
+        );
+        assert!(decode_rotbl_user_data(&s, None).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seal_unseal_entry() -> anyhow::Result<()> {
+        let kr = KeyRing::parse(KEY)?;
+
+        let sealed = seal_entry(&kr, "a/b", SeqMarked::new_normal(3, b"v".to_vec()))?;
+        let frame = sealed.data_ref().unwrap();
+        assert!(!frame.windows(3).any(|w| w == b"a/b"));
+        assert_eq!(
+            ("a/b".to_string(), SeqMarked::new_normal(3, b"v".to_vec())),
+            unseal_entry(&kr, sealed)?
+        );
+
+        // A tombstone keeps its key.
+        let sealed = seal_entry(&kr, "c", SeqMarked::new_tombstone(4))?;
+        assert_eq!(
+            ("c".to_string(), SeqMarked::new_tombstone(4)),
+            unseal_entry(&kr, sealed)?
+        );
+
+        Ok(())
     }
 }
@@ -18,6 +18,7 @@ use std::collections::BTreeSet;
 use log::debug;
 
 use crate::node::Node;
+use crate::prefix_quota::QuotaState;
 use crate::raft_types::LogId;
 use crate::raft_types::NodeId;
 use crate::raft_types::StoredMembership;
@@ -60,6 +61,13 @@ pub struct SysData {
     #[serde(default)]
     #[serde(skip_serializing_if = "BTreeSet::is_empty")]
     sm_features: BTreeSet<String>,
+
+    /// The quota and usage of each key prefix that has a quota defined.
+    ///
+    /// Maintained when applying a write to a quota definition key or a key under a quota prefix.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    prefix_quotas: BTreeMap<String, QuotaState>,
 }
 
 impl SysData {
@@ -128,6 +136,14 @@ impl SysData {
         &mut self.sm_features
     }
 
+    pub fn prefix_quotas(&self) -> &BTreeMap<String, QuotaState> {
+        &self.prefix_quotas
+    }
+
+    pub fn prefix_quotas_mut(&mut self) -> &mut BTreeMap<String, QuotaState> {
+        &mut self.prefix_quotas
+    }
+
     pub fn last_applied_ref(&self) -> &Option<LogId> {
         &self.last_applied
     }
@@ -149,6 +165,8 @@ mod tests {
 
     use super::*;
     use crate::Endpoint;
+    use crate::prefix_quota::PrefixQuota;
+    use crate::prefix_quota::PrefixUsage;
     use crate::raft_types::Membership;
     use crate::raft_types::new_log_id;
 
@@ -167,6 +185,7 @@ mod tests {
             data_seq: None,
             key_counts: BTreeMap::from([]),
             sm_features: BTreeSet::from([]),
+            prefix_quotas: BTreeMap::new(),
         };
 
         let want = r#"{
@@ -231,6 +250,10 @@ mod tests {
             data_seq: Some(10),
             key_counts: BTreeMap::from([("foo".to_string(), 5), ("bar".to_string(), 6)]),
             sm_features: BTreeSet::from(["f1".to_string(), "f2".to_string()]),
+            prefix_quotas: BTreeMap::from([(
+                "t1/".to_string(),
+                QuotaState::new(PrefixQuota::new(Some(3), None), PrefixUsage::new(2, 20)),
+            )]),
         };
 
         let want = r#"{
@@ -282,7 +305,18 @@ mod tests {
   "sm_features": [
     "f1",
     "f2"
-  ]
+  ],
+  "prefix_quotas": {
+    "t1/": {
+      "quota": {
+        "max_keys": 3
+      },
+      "usage": {
+        "keys": 2,
+        "bytes": 20
+      }
+    }
+  }
 }"#;
 
         let serialized = serde_json::to_string_pretty(&sys_data).unwrap();
@@ -325,6 +359,7 @@ mod tests {
             data_seq: None,
             key_counts: BTreeMap::new(),
             sm_features: BTreeSet::new(),
+            prefix_quotas: BTreeMap::new(),
         };
 
         println!("{}", serde_json::to_string_pretty(&want).unwrap());
@@ -370,6 +405,7 @@ mod tests {
             data_seq: None,
             key_counts: BTreeMap::from([("foo".to_string(), 5), ("bar".to_string(), 6)]),
             sm_features: Default::default(),
+            prefix_quotas: Default::default(),
         };
 
         println!("{}", serde_json::to_string_pretty(&want).unwrap());
@@ -416,6 +452,7 @@ mod tests {
             data_seq: None,
             key_counts: BTreeMap::from([("foo".to_string(), 5), ("bar".to_string(), 6)]),
             sm_features: BTreeSet::from(["f1".to_string(), "f2".to_string()]),
+            prefix_quotas: BTreeMap::new(),
         };
 
         println!("{}", serde_json::to_string_pretty(&want).unwrap());
@@ -463,6 +500,61 @@ mod tests {
             data_seq: Some(10),
             key_counts: BTreeMap::from([("foo".to_string(), 5), ("bar".to_string(), 6)]),
             sm_features: BTreeSet::from(["f1".to_string(), "f2".to_string()]),
+            prefix_quotas: BTreeMap::new(),
+        };
+
+        println!("{}", serde_json::to_string_pretty(&want).unwrap());
+
+        let deserialized: SysData = serde_json::from_str(serialized).unwrap();
+        assert_eq!(want, deserialized);
+    }
+
+    /// Test newer program can deserialize 2026-10-18 version: add field `prefix_quotas`
+    #[test]
+    fn test_sys_data_deserialize_2026_10_18() {
+        // The string is serialized with old version SysData, never change it.
+        let serialized = r#"{
+          "last_applied": { "leader_id": { "term": 4, "node_id": 5 }, "index": 6 },
+          "last_membership": {
+            "log_id": { "leader_id": { "term": 1, "node_id": 2 }, "index": 3 },
+            "membership": {
+              "configs": [ [ 7, 8 ] ],
+              "nodes": { "7": {}, "8": {}, "9": {} }
+            }
+          },
+          "nodes": {
+            "2": {
+              "name": "node2",
+              "endpoint": { "addr": "127.0.0.1", "port": 16 },
+              "grpc_api_advertise_address": null
+            }
+          },
+          "sequence": 5,
+          "data_seq": 10,
+          "key_counts": { "bar": 6, "foo": 5 },
+          "sm_features": [ "f1", "f2" ],
+          "prefix_quotas": {
+            "t1/": { "quota": { "max_keys": 3 }, "usage": { "keys": 2, "bytes": 20 } }
+          }
+         }
+        "#;
+
+        let want = SysData {
+            last_applied: Some(new_log_id(4, 5, 6)),
+            last_membership: StoredMembership::new(
+                Some(new_log_id(1, 2, 3)),
+                Membership::new(vec![BTreeSet::from([7, 8])], BTreeSet::from([7u64, 8, 9]))
+                    .unwrap(),
+            ),
+            nodes: BTreeMap::from([(2, Node::new("node2", Endpoint::new("127.0.0.1", 16)))]),
+            sequence: 5,
+            data_seq: Some(10),
+            key_counts: BTreeMap::from([("foo".to_string(), 5), ("bar".to_string(), 6)]),
+            sm_features: BTreeSet::from(["f1".to_string(), "f2".to_string()]),
+            prefix_quotas: BTreeMap::from([(
+                "t1/".to_string(),
+                QuotaState::new(PrefixQuota::new(Some(3), None), PrefixUsage::new(2, 20)),
+            )]),
         };
 
         println!("{}", serde_json::to_string_pretty(&want).unwrap());
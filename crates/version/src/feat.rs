@@ -168,6 +168,14 @@ impl Feature {
             Feature::KvGetMany => "kv_get_many",
         }
     }
+
+    /// Returns the feature identified by `name`, the inverse of [`Self::as_str`].
+    ///
+    /// Returns `None` for a name unknown to this build,
+    /// e.g., a feature advertised by a newer peer.
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Self::all().iter().copied().find(|f| f.as_str() == name)
+    }
 }
 
 impl fmt::Display for Feature {
@@ -175,3 +183,18 @@ impl fmt::Display for Feature {
         f.write_str(self.as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_from_name() {
+        for f in Feature::all() {
+            assert_eq!(Some(*f), Feature::from_name(f.as_str()));
+        }
+
+        assert_eq!(Some(Feature::KvList), Feature::from_name("kv_list"));
+        assert_eq!(None, Feature::from_name("no_such_feature"));
+    }
+}
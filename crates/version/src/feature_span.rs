@@ -16,9 +16,12 @@ use crate::Version;
 use crate::feat::Feature;
 
 /// The lifetime `[since, until)` of a feature.
-pub struct FeatureSpan {
+///
+/// `F` is [`Feature`] for client-server features,
+/// or [`PeerFeature`](crate::PeerFeature) for raft RPCs between servers.
+pub struct FeatureSpan<F = Feature> {
     /// The feature being described.
-    pub feature: Feature,
+    pub feature: F,
 
     /// The version when this feature was added (inclusive).
     pub since: Version,
@@ -29,9 +32,9 @@ pub struct FeatureSpan {
     pub until: Version,
 }
 
-impl FeatureSpan {
+impl<F: Copy> FeatureSpan<F> {
     /// Creates a lifetime starting at `since` with no end (`until = Version::max()`).
-    pub const fn new(feature: Feature, since: Version) -> Self {
+    pub const fn new(feature: F, since: Version) -> Self {
         FeatureSpan {
             feature,
             since,
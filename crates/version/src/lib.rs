@@ -27,17 +27,20 @@
 //! When feature changes affect compatibility:
 //! - Update `MIN_CLIENT_VERSION` when server removes features
 //! - Update `MIN_SERVER_VERSION` when client requires new features
+//! - Update `MIN_PEER_VERSION` when raft RPCs between servers are added or removed
 //! - Run tests to verify the values match the computed results
 //!
 //! See [Compatibility Algorithm](./compatibility_algorithm.md) for details.
 
 mod feat;
 mod feature_span;
+mod peer_feat;
 mod spec;
 mod version;
 
 pub use self::feat::Feature;
 pub use self::feature_span::FeatureSpan;
+pub use self::peer_feat::PeerFeature;
 pub use self::spec::Spec;
 pub use self::version::Version;
 
@@ -55,6 +58,11 @@ pub static MIN_CLIENT_VERSION: Version = Version::new(1, 2, 676);
 /// See [module documentation](self) for details.
 pub static MIN_SERVER_VERSION: Version = Version::new(1, 2, 770);
 
+/// Minimum compatible meta-server version to run in the same cluster, i.e., as a raft peer.
+///
+/// See [module documentation](self) for details.
+pub static MIN_PEER_VERSION: Version = Version::new(1, 2, 547);
+
 use std::sync::LazyLock;
 
 /// The version string of this build.
@@ -100,17 +108,17 @@ mod tests {
 
     #[test]
     fn test_version_string() {
-        assert_eq!(version_str(), "260205.3.0");
+        assert_eq!(version_str(), "260205.4.0");
     }
 
     #[test]
     fn test_semver_components() {
-        assert_eq!(semver_tuple(version()), (260205, 3, 0));
+        assert_eq!(semver_tuple(version()), (260205, 4, 0));
     }
 
     #[test]
     fn test_semver_display() {
-        assert_eq!(version().to_semver().to_string(), "260205.3.0");
+        assert_eq!(version().to_semver().to_string(), "260205.4.0");
     }
 
     #[test]
@@ -132,4 +140,14 @@ mod tests {
             spec.min_compatible_server_version(),
         );
     }
+
+    #[test]
+    fn test_min_peer_version_matches_computed() {
+        let spec = spec();
+        assert_version_eq(
+            "MIN_PEER_VERSION",
+            &MIN_PEER_VERSION,
+            spec.min_compatible_peer_version(),
+        );
+    }
 }
@@ -0,0 +1,82 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// A raft RPC between meta-service nodes.
+///
+/// Each variant represents an RPC whose lifetime on the raft server and raft client
+/// is tracked in [`crate::Spec`] for peer compatibility calculation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PeerFeature {
+    /// `vote()` RPC with a serialized openraft `VoteRequest` in `RaftRequest`.
+    Vote,
+
+    /// `vote_v001()` RPC with protobuf `VoteRequest`.
+    VoteV001,
+
+    /// `append_entries()` RPC.
+    AppendEntries,
+
+    /// `install_snapshot()` RPC, sending the snapshot in chunks with unary calls.
+    InstallSnapshot,
+
+    /// `install_snapshot_v1()` RPC, with a snapshot format independent of the raft-store.
+    InstallSnapshotV1,
+
+    /// `install_snapshot_v002()` RPC, sending the snapshot in a stream.
+    InstallSnapshotV002,
+
+    /// `install_snapshot_v003()` RPC, sending the `rotbl` snapshot file in a stream.
+    InstallSnapshotV003,
+
+    /// `install_snapshot_v004()` RPC, sending the snapshot entries in a stream.
+    InstallSnapshotV004,
+}
+
+impl PeerFeature {
+    /// Returns all peer feature variants.
+    pub const fn all() -> &'static [PeerFeature] {
+        &[
+            PeerFeature::Vote,
+            PeerFeature::VoteV001,
+            PeerFeature::AppendEntries,
+            PeerFeature::InstallSnapshot,
+            PeerFeature::InstallSnapshotV1,
+            PeerFeature::InstallSnapshotV002,
+            PeerFeature::InstallSnapshotV003,
+            PeerFeature::InstallSnapshotV004,
+        ]
+    }
+
+    /// Returns the string identifier for this peer feature.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            PeerFeature::Vote => "vote",
+            PeerFeature::VoteV001 => "vote_v001",
+            PeerFeature::AppendEntries => "append_entries",
+            PeerFeature::InstallSnapshot => "install_snapshot",
+            PeerFeature::InstallSnapshotV1 => "install_snapshot_v1",
+            PeerFeature::InstallSnapshotV002 => "install_snapshot_v002",
+            PeerFeature::InstallSnapshotV003 => "install_snapshot_v003",
+            PeerFeature::InstallSnapshotV004 => "install_snapshot_v004",
+        }
+    }
+}
+
+impl fmt::Display for PeerFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -39,9 +39,11 @@
 //! ```
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 
 use crate::feat::Feature;
 use crate::feature_span::FeatureSpan;
+use crate::peer_feat::PeerFeature;
 use crate::version::Version;
 
 /// Parses `CARGO_PKG_VERSION` into a [`Version`].
@@ -54,7 +56,7 @@ fn parse_pkg_version() -> Version {
 }
 
 /// Record that a feature was added at `version`.
-fn add(features: &mut BTreeMap<Feature, FeatureSpan>, feature: Feature, version: Version) {
+fn add<F: Copy + Ord>(features: &mut BTreeMap<F, FeatureSpan<F>>, feature: F, version: Version) {
     let span = features
         .entry(feature)
         .or_insert_with(|| FeatureSpan::new(feature, Version::min()));
@@ -66,7 +68,7 @@ fn add(features: &mut BTreeMap<Feature, FeatureSpan>, feature: Feature, version:
 }
 
 /// Record that a feature was removed at `version`.
-fn remove(features: &mut BTreeMap<Feature, FeatureSpan>, feature: Feature, version: Version) {
+fn remove<F: Copy + Ord>(features: &mut BTreeMap<F, FeatureSpan<F>>, feature: F, version: Version) {
     let span = features
         .entry(feature)
         .or_insert_with(|| FeatureSpan::new(feature, Version::min()));
@@ -91,6 +93,12 @@ pub struct Spec {
 
     /// When each feature was added/removed on the client side.
     client_features: BTreeMap<Feature, FeatureSpan>,
+
+    /// When each raft RPC was added/removed on the raft server side.
+    peer_server_features: BTreeMap<PeerFeature, FeatureSpan<PeerFeature>>,
+
+    /// When each raft RPC became required/unrequired by the raft client side.
+    peer_client_features: BTreeMap<PeerFeature, FeatureSpan<PeerFeature>>,
 }
 
 impl Spec {
@@ -113,6 +121,34 @@ impl Spec {
     pub fn client_features(&self) -> &BTreeMap<Feature, FeatureSpan> {
         &self.client_features
     }
+
+    /// Returns the features a server of `version` provides.
+    pub fn server_features_at(&self, version: Version) -> BTreeSet<Feature> {
+        Self::active_at(&self.server_features, version)
+    }
+
+    /// Returns the features a client of `version` uses.
+    pub fn client_features_at(&self, version: Version) -> BTreeSet<Feature> {
+        Self::active_at(&self.client_features, version)
+    }
+
+    /// Returns the raft RPCs the raft server provides.
+    pub fn peer_server_features(&self) -> &BTreeMap<PeerFeature, FeatureSpan<PeerFeature>> {
+        &self.peer_server_features
+    }
+
+    /// Returns the raft RPCs the raft client requires.
+    pub fn peer_client_features(&self) -> &BTreeMap<PeerFeature, FeatureSpan<PeerFeature>> {
+        &self.peer_client_features
+    }
+
+    fn active_at(features: &BTreeMap<Feature, FeatureSpan>, version: Version) -> BTreeSet<Feature> {
+        features
+            .values()
+            .filter(|span| span.is_active_at(version))
+            .map(|span| span.feature)
+            .collect()
+    }
 }
 
 impl Spec {
@@ -281,10 +317,87 @@ impl Spec {
         Self::assert_all_features(&srv);
         Self::assert_all_features(&cli);
 
+        let mut peer_srv = BTreeMap::new();
+        let mut peer_cli = BTreeMap::new();
+
+        {
+            type P = PeerFeature;
+
+            // 2023-02-16: since 0.9.41:
+            // 🖥 raft server: add vote, append_entries, install_snapshot
+            add(&mut peer_srv, P::Vote, ver(0, 9, 41));
+            add(&mut peer_srv, P::AppendEntries, ver(0, 9, 41));
+            add(&mut peer_srv, P::InstallSnapshot, ver(0, 9, 41));
+
+            add(&mut peer_cli, P::Vote, ver(0, 9, 41));
+            add(&mut peer_cli, P::AppendEntries, ver(0, 9, 41));
+            add(&mut peer_cli, P::InstallSnapshot, ver(0, 9, 41));
+
+            // 2023-11-16: since 1.2.212:
+            // 🖥 raft server: add install_snapshot_v1
+            // 👥 raft client: try install_snapshot_v1, fall back to install_snapshot
+            add(&mut peer_srv, P::InstallSnapshotV1, ver(1, 2, 212));
+
+            // since 1.2.288:
+            // 👥 raft client: no longer require install_snapshot;
+            // it is required until 1.2.288 because `1.2.212~1.2.287` are removed.
+            remove(&mut peer_cli, P::InstallSnapshot, ver(1, 2, 288));
+
+            // 2024-05-06: since 1.2.453:
+            // 🖥 raft server: add install_snapshot_v002
+            add(&mut peer_srv, P::InstallSnapshotV002, ver(1, 2, 453));
+
+            // 2024-05-21: since 1.2.479:
+            // 🖥 raft server: remove install_snapshot
+            // 👥 raft client: require install_snapshot_v1
+            remove(&mut peer_srv, P::InstallSnapshot, ver(1, 2, 479));
+
+            add(&mut peer_cli, P::InstallSnapshotV1, ver(1, 2, 479));
+
+            // since 1.2.547:
+            // 🖥 raft server: add install_snapshot_v003
+            // 👥 raft client: try install_snapshot_v003, fall back to install_snapshot_v1
+            add(&mut peer_srv, P::InstallSnapshotV003, ver(1, 2, 547));
+
+            remove(&mut peer_cli, P::InstallSnapshotV1, ver(1, 2, 547));
+
+            // 2024-07-02: since 1.2.552:
+            // 🖥 raft server: remove install_snapshot_v002
+            remove(&mut peer_srv, P::InstallSnapshotV002, ver(1, 2, 552));
+
+            // 2025-07-02: since 1.2.769:
+            // 🖥 raft server: remove install_snapshot_v1
+            remove(&mut peer_srv, P::InstallSnapshotV1, ver(1, 2, 769));
+
+            // 2025-07-20: since 1.2.777:
+            // 🖥 raft server: add vote_v001
+            add(&mut peer_srv, P::VoteV001, ver(1, 2, 777));
+
+            // 2025-09-24: since 1.2.818:
+            // 🖥 raft server: add install_snapshot_v004
+            add(&mut peer_srv, P::InstallSnapshotV004, ver(1, 2, 818));
+
+            // 👥 raft client: try these first, fall back if the raft server does not provide them:
+            // install_snapshot_v003 falls back to install_snapshot_v1,
+            // vote_v001 falls back to vote,
+            // install_snapshot_v004 falls back to install_snapshot_v003.
+            add(&mut peer_cli, P::InstallSnapshotV003, Version::max());
+            add(&mut peer_cli, P::VoteV001, Version::max());
+            add(&mut peer_cli, P::InstallSnapshotV004, Version::max());
+
+            // raft client never used it.
+            add(&mut peer_cli, P::InstallSnapshotV002, Version::max());
+        }
+
+        Self::assert_all_peer_features(&peer_srv);
+        Self::assert_all_peer_features(&peer_cli);
+
         Spec {
             version,
             server_features: srv,
             client_features: cli,
+            peer_server_features: peer_srv,
+            peer_client_features: peer_cli,
         }
     }
 
@@ -298,6 +411,16 @@ impl Spec {
         }
     }
 
+    fn assert_all_peer_features(features: &BTreeMap<PeerFeature, FeatureSpan<PeerFeature>>) {
+        for feature in PeerFeature::all() {
+            assert!(
+                features.contains_key(feature),
+                "Missing peer feature: {:?}",
+                feature
+            );
+        }
+    }
+
     /// Minimum server version that can serve this client.
     ///
     /// Returns `max(server.since)` across all features the client requires
@@ -337,6 +460,81 @@ impl Spec {
 
         min_client
     }
+
+    /// Minimum server version that can run in the same cluster as this server.
+    ///
+    /// Returns the max of `server.since` across all raft RPCs this raft client requires
+    /// and `client.until` across all raft RPCs this raft server has removed.
+    pub fn min_compatible_peer_version(&self) -> Version {
+        let mut min_peer = Version::min();
+
+        for feature in PeerFeature::all() {
+            let client_lt = self.peer_client_features.get(feature).unwrap();
+            let server_lt = self.peer_server_features.get(feature).unwrap();
+
+            // The peer has to provide what this raft client requires.
+            if client_lt.is_active_at(self.version) {
+                min_peer = min_peer.max(server_lt.since);
+            }
+
+            // The peer must not require what this raft server has removed.
+            // Skip the ones never required.
+            if server_lt.until <= self.version && client_lt.since < client_lt.until {
+                min_peer = min_peer.max(client_lt.until);
+            }
+        }
+
+        min_peer
+    }
+
+    /// Check if a server of version `peer` can run in the same cluster as this server.
+    ///
+    /// Both directions are checked, since either of them can be the leader:
+    /// every raft RPC required by one raft client has to be provided by the other raft server.
+    ///
+    /// A raft client that falls back to an older RPC requires one of them,
+    /// which is not tracked per RPC, thus each side must also be at least
+    /// the [`min_compatible_peer_version()`](Self::min_compatible_peer_version) of the other.
+    pub fn check_peer_compatible(&self, peer: Version) -> Result<(), String> {
+        let me = self.version;
+
+        for feature in PeerFeature::all() {
+            let client_lt = self.peer_client_features.get(feature).unwrap();
+            let server_lt = self.peer_server_features.get(feature).unwrap();
+
+            if client_lt.is_active_at(me) && !server_lt.is_active_at(peer) {
+                return Err(format!(
+                    "{} requires raft RPC {}, which is not provided by {}",
+                    me, feature, peer
+                ));
+            }
+
+            if client_lt.is_active_at(peer) && !server_lt.is_active_at(me) {
+                return Err(format!(
+                    "{} requires raft RPC {}, which is not provided by {}",
+                    peer, feature, me
+                ));
+            }
+        }
+
+        let min_peer = self.min_compatible_peer_version();
+        if peer < min_peer {
+            return Err(format!(
+                "{} requires peer version >= {}, but got {}",
+                me, min_peer, peer
+            ));
+        }
+
+        let min_me = Spec::new(peer).min_compatible_peer_version();
+        if me < min_me {
+            return Err(format!(
+                "{} requires peer version >= {}, but got {}",
+                peer, min_me, me
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -366,4 +564,130 @@ mod tests {
 
         assert_eq!(min_client, Version::new(1, 2, 676));
     }
+
+    #[test]
+    fn test_server_features_at() {
+        let spec = Spec::load();
+
+        let at_1_2_770 = spec.server_features_at(Version::new(1, 2, 770));
+        assert!(at_1_2_770.contains(&Feature::PutSequential));
+        assert!(!at_1_2_770.contains(&Feature::KvList));
+        assert!(!at_1_2_770.contains(&Feature::KvApiGetKv));
+
+        let at_1_2_869 = spec.server_features_at(Version::new(1, 2, 869));
+        assert!(at_1_2_869.contains(&Feature::KvList));
+        assert!(at_1_2_869.contains(&Feature::KvGetMany));
+
+        let old = spec.server_features_at(Version::new(1, 2, 162));
+        assert!(old.is_empty());
+    }
+
+    #[test]
+    fn test_changes_includes_all_peer_features() {
+        let spec = Spec::load();
+
+        Spec::assert_all_peer_features(&spec.peer_server_features);
+        Spec::assert_all_peer_features(&spec.peer_client_features);
+    }
+
+    #[test]
+    fn test_min_compatible_peer_version() {
+        let spec = Spec::load();
+        let min_peer = spec.min_compatible_peer_version();
+
+        assert_eq!(min_peer, Version::new(1, 2, 547));
+    }
+
+    /// The server-server compatibility table in `docs/README.md` must agree with the spec.
+    #[test]
+    fn test_peer_compatibility_table_in_doc() {
+        let readme = include_str!("../../../docs/README.md");
+
+        let parse_ver = |s: &str| {
+            let s = s.trim();
+            if s == "+∞" {
+                Version::max()
+            } else {
+                Version::from(semver::Version::parse(s).unwrap())
+            }
+        };
+
+        // A cell is a range in the form `[from, to)`.
+        let parse_range = |cell: &str| {
+            let cell = cell
+                .trim()
+                .strip_prefix('[')
+                .unwrap()
+                .strip_suffix(')')
+                .unwrap();
+            let (from, to) = cell.split_once(',').unwrap();
+            (parse_ver(from), parse_ver(to))
+        };
+
+        let rows = readme
+            .lines()
+            .filter(|l| l.starts_with("| ["))
+            .map(|l| {
+                let cells = l.split('|').collect::<Vec<_>>();
+                (parse_range(cells[1]), parse_range(cells[2]))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(5, rows.len());
+
+        for ((server_from, _), (compat_from, _)) in rows.iter().copied() {
+            let spec = Spec::new(server_from);
+            assert_eq!(
+                compat_from,
+                spec.min_compatible_peer_version(),
+                "row of server version {}",
+                server_from
+            );
+            assert!(spec.check_peer_compatible(compat_from).is_ok());
+        }
+
+        // The last row covers the current version.
+        let ((last_from, last_to), (compat_from, _)) = rows.last().copied().unwrap();
+        assert!(last_from <= *Spec::load().version() && last_to == Version::max());
+        assert_eq!(compat_from, Spec::load().min_compatible_peer_version());
+    }
+
+    #[test]
+    fn test_check_peer_compatible() {
+        let spec = Spec::load();
+
+        assert!(spec.check_peer_compatible(*spec.version()).is_ok());
+        assert!(spec.check_peer_compatible(Version::new(1, 2, 547)).is_ok());
+        assert!(spec.check_peer_compatible(Version::new(1, 2, 818)).is_ok());
+
+        let res = spec.check_peer_compatible(Version::new(1, 2, 479));
+        assert_eq!(
+            res.unwrap_err(),
+            format!(
+                "1.2.479 requires raft RPC install_snapshot_v1, which is not provided by {}",
+                spec.version()
+            )
+        );
+
+        // 1.2.300 falls back to install_snapshot, neither of which is provided.
+        let res = spec.check_peer_compatible(Version::new(1, 2, 300));
+        assert_eq!(
+            res.unwrap_err(),
+            format!(
+                "{} requires peer version >= 1.2.547, but got 1.2.300",
+                spec.version()
+            )
+        );
+
+        // Checked from the old side.
+        let old = Spec::new(Version::new(1, 2, 500));
+        let res = old.check_peer_compatible(*spec.version());
+        assert_eq!(
+            res.unwrap_err(),
+            format!(
+                "1.2.500 requires raft RPC install_snapshot_v1, which is not provided by {}",
+                spec.version()
+            )
+        );
+    }
 }